@@ -6,10 +6,16 @@
 //! This module contains the trait crc and it's default naive implementation.
 //! 
 //! The trait [`CrcCalculator`] declares the function [`CrcCalculator::calculate_crc32`] used to compute Crc. \
-//! [`DefaultCrc`] computes the Crc (in big endian).
+//! The Crc can also be computed incrementally, chunk by chunk, with [`CrcCalculator::init_crc32`],
+//! [`CrcCalculator::update_crc32`] and [`CrcCalculator::finalize_crc32`]. \
+//! [`DefaultCrc`] computes the Crc (in big endian). \
+//! [`FastCrc`] computes the same Crc with slicing tables, or with the carry-less multiplication when the CPU supports it.
 use crate::gse_standard::CRC_INIT;
 
 /// Trait defining the computation of the CRC.
+///
+/// The value handled by the incremental functions is the state of the computation,
+/// it becomes the Crc once given to [`CrcCalculator::finalize_crc32`].
 pub trait CrcCalculator {
     /// Calculate 32bit CRC for `pdu`, `total_length`, `protocol_type` and `label`
     fn calculate_crc32(
@@ -18,7 +24,19 @@ pub trait CrcCalculator {
         protocol_type: u16,
         total_length: u16,
         label: &[u8],
-    ) -> u32;
+    ) -> u32 {
+        let crc = self.init_crc32(protocol_type, total_length, label);
+        self.finalize_crc32(self.update_crc32(crc, pdu))
+    }
+
+    /// Start the computation of the CRC with the header fields `total_length`, `protocol_type` and `label`
+    fn init_crc32(&self, protocol_type: u16, total_length: u16, label: &[u8]) -> u32;
+
+    /// Continue the computation of the CRC with the next bytes covered, such as header extensions or a pdu fragment
+    fn update_crc32(&self, crc: u32, data: &[u8]) -> u32;
+
+    /// End the computation of the CRC and return the value to write in the end packet
+    fn finalize_crc32(&self, crc: u32) -> u32;
 }
 
 /** CRC-32 table */
@@ -65,22 +83,230 @@ fn crc32(data: &[u8], crc: u32) -> u32 {
 }
 
 /// Implementation of the `CrcCalculator` trait.
-/// The function is not optimized and works in big endian.
+/// The function is not optimized and works in big endian, see [`FastCrc`] for an optimized implementation.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct DefaultCrc;
 impl CrcCalculator for DefaultCrc {
-    fn calculate_crc32(
-        &self,
-        pdu: &[u8],
-        protocol_type: u16,
-        total_length: u16,
-        label: &[u8],
-    ) -> u32 {
+    fn init_crc32(&self, protocol_type: u16, total_length: u16, label: &[u8]) -> u32 {
         let mut crc = crc32(&total_length.to_be_bytes(), CRC_INIT);
         crc = crc32(&protocol_type.to_be_bytes(), crc);
-        crc = crc32(label, crc);
-        crc = crc32(pdu, crc);
+        crc32(label, crc)
+    }
+
+    fn update_crc32(&self, crc: u32, data: &[u8]) -> u32 {
+        crc32(data, crc)
+    }
 
+    fn finalize_crc32(&self, crc: u32) -> u32 {
+        crc
+    }
+}
+
+/// Generate the slicing tables from the CRC-32 table
+///
+/// The entry `i` of the table `k` is the crc of the byte `i` followed by `k` null bytes.
+const fn slicing_tables() -> [[u32; 256]; 16] {
+    let mut tables = [[0; 256]; 16];
+    let mut i = 0;
+    while i < 256 {
+        tables[0][i] = CRC_TAB[i];
+        i += 1;
+    }
+    let mut k = 1;
+    while k < 16 {
+        let mut i = 0;
+        while i < 256 {
+            let crc = tables[k - 1][i];
+            tables[k][i] = (crc << 8) ^ CRC_TAB[(crc >> 24) as usize];
+            i += 1;
+        }
+        k += 1;
+    }
+    tables
+}
+
+static SLICING_TAB: [[u32; 256]; 16] = slicing_tables();
+
+/// Process a chunk of `N` bytes, `N` being a multiple of 4 not greater than 16
+#[inline(always)]
+fn slice<const N: usize>(crc: u32, chunk: &[u8]) -> u32 {
+    let word = crc ^ u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    let mut crc = SLICING_TAB[N - 1][(word >> 24) as usize]
+        ^ SLICING_TAB[N - 2][((word >> 16) & 0xFF) as usize]
+        ^ SLICING_TAB[N - 3][((word >> 8) & 0xFF) as usize]
+        ^ SLICING_TAB[N - 4][(word & 0xFF) as usize];
+    for (k, octet) in chunk[4..N].iter().enumerate() {
+        crc ^= SLICING_TAB[N - 5 - k][*octet as usize];
+    }
+    crc
+}
+
+/// Crc computed 16 bytes at a time, then 8 bytes at a time, then byte by byte
+fn crc32_slicing(data: &[u8], mut crc: u32) -> u32 {
+    let mut chunks = data.chunks_exact(16);
+    for chunk in &mut chunks {
+        crc = slice::<16>(crc, chunk);
+    }
+    let mut chunks = chunks.remainder().chunks_exact(8);
+    for chunk in &mut chunks {
+        crc = slice::<8>(crc, chunk);
+    }
+    crc32(chunks.remainder(), crc)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod clmul {
+    //! Crc computed with the carry-less multiplication
+    //!
+    //! The data are read as a polynomial by blocks of 128 bits, four blocks are folded in parallel
+    //! until the last 64 bytes, which are folded into a single block.
+    //! The crc of the data is then the crc of this block, computed with the slicing tables.
+    use std::arch::x86_64::{__m128i, _mm_clmulepi64_si128, _mm_set_epi64x};
+
+    use super::crc32_slicing;
+
+    /// Generator polynomial of the CRC-32, without the x^32 term
+    const POLY: u32 = 0x04C1_1DB7;
+
+    /// Return x^n mod P
+    const fn x_pow_mod(n: u32) -> u32 {
+        let mut remainder: u32 = 1;
+        let mut i = 0;
+        while i < n {
+            remainder = if remainder & 0x8000_0000 != 0 {
+                (remainder << 1) ^ POLY
+            } else {
+                remainder << 1
+            };
+            i += 1;
+        }
+        remainder
+    }
+
+    /// Constants to shift a block by `n` bits: x^(n + 64) mod P and x^n mod P
+    const fn fold_constants(n: u32) -> (u64, u64) {
+        (x_pow_mod(n + 64) as u64, x_pow_mod(n) as u64)
+    }
+
+    const FOLD_512: (u64, u64) = fold_constants(512);
+    const FOLD_384: (u64, u64) = fold_constants(384);
+    const FOLD_256: (u64, u64) = fold_constants(256);
+    const FOLD_128: (u64, u64) = fold_constants(128);
+
+    /// Minimum length processed by the carry-less multiplication, the shorter data are processed with the tables
+    pub(super) const MIN_LEN: usize = 128;
+
+    #[inline]
+    #[target_feature(enable = "pclmulqdq,sse2")]
+    unsafe fn clmul(a: u64, b: u64) -> u128 {
+        let product = _mm_clmulepi64_si128(
+            _mm_set_epi64x(0, a as i64),
+            _mm_set_epi64x(0, b as i64),
+            0x00,
+        );
+        std::mem::transmute::<__m128i, u128>(product)
+    }
+
+    /// Return a polynomial congruent to `block` * x^n mod P, `constants` being the fold constants of n
+    #[inline]
+    #[target_feature(enable = "pclmulqdq,sse2")]
+    unsafe fn fold(block: u128, constants: (u64, u64)) -> u128 {
+        clmul((block >> 64) as u64, constants.0) ^ clmul(block as u64, constants.1)
+    }
+
+    fn load(chunk: &[u8]) -> u128 {
+        u128::from_be_bytes(chunk[..16].try_into().unwrap())
+    }
+
+    /// Update the crc with the data
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the `pclmulqdq` and `sse2` instructions.
+    #[target_feature(enable = "pclmulqdq,sse2")]
+    pub(super) unsafe fn crc32_clmul(data: &[u8], crc: u32) -> u32 {
+        if data.len() < MIN_LEN {
+            return crc32_slicing(data, crc);
+        }
+        let (folded, rest) = data.split_at(data.len() - data.len() % 64);
+
+        // the crc is equivalent to a xor on the first 32 bits of the data
+        let mut acc = [
+            load(&folded[0..]) ^ ((crc as u128) << 96),
+            load(&folded[16..]),
+            load(&folded[32..]),
+            load(&folded[48..]),
+        ];
+        for chunk in folded[64..].chunks_exact(64) {
+            for (i, block) in acc.iter_mut().enumerate() {
+                *block = fold(*block, FOLD_512) ^ load(&chunk[16 * i..]);
+            }
+        }
+        let block =
+            fold(acc[0], FOLD_384) ^ fold(acc[1], FOLD_256) ^ fold(acc[2], FOLD_128) ^ acc[3];
+
+        let crc = crc32_slicing(&block.to_be_bytes(), 0);
+        crc32_slicing(rest, crc)
+    }
+}
+
+/// Fast implementation of the `CrcCalculator` trait, bit-exact with [`DefaultCrc`].
+///
+/// The data are processed 16 bytes at a time with slicing tables. \
+/// On x86_64, if the CPU supports the carry-less multiplication, the large buffers are folded with `pclmulqdq`.
+/// The CPU is checked once by [`FastCrc::new`], [`FastCrc::portable`] always uses the tables.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FastCrc {
+    clmul: bool,
+}
+
+impl Default for FastCrc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FastCrc {
+    /// FastCrc constructor, the carry-less multiplication is used if the CPU supports it
+    pub fn new() -> FastCrc {
+        #[cfg(target_arch = "x86_64")]
+        let clmul = is_x86_feature_detected!("pclmulqdq") && is_x86_feature_detected!("sse2");
+        #[cfg(not(target_arch = "x86_64"))]
+        let clmul = false;
+
+        FastCrc { clmul }
+    }
+
+    /// FastCrc constructor using only the slicing tables
+    pub fn portable() -> FastCrc {
+        FastCrc { clmul: false }
+    }
+
+    /// Return true if the carry-less multiplication is used
+    pub fn is_clmul(&self) -> bool {
+        self.clmul
+    }
+}
+
+impl CrcCalculator for FastCrc {
+    fn init_crc32(&self, protocol_type: u16, total_length: u16, label: &[u8]) -> u32 {
+        let [t0, t1] = total_length.to_be_bytes();
+        let [p0, p1] = protocol_type.to_be_bytes();
+        let crc = slice::<4>(CRC_INIT, &[t0, t1, p0, p1]);
+        crc32_slicing(label, crc)
+    }
+
+    fn update_crc32(&self, crc: u32, data: &[u8]) -> u32 {
+        #[cfg(target_arch = "x86_64")]
+        if self.clmul && data.len() >= clmul::MIN_LEN {
+            // SAFETY: the support of the instructions is checked by the constructor
+            return unsafe { clmul::crc32_clmul(data, crc) };
+        }
+
+        crc32_slicing(data, crc)
+    }
+
+    fn finalize_crc32(&self, crc: u32) -> u32 {
         crc
     }
 }
@@ -144,3 +370,68 @@ fn test_calculate_crc32_004() {
 
     assert_eq!(crc_exp, crc_obs);
 }
+
+/// Incremental CRC test: the crc computed chunk by chunk is the crc of the whole pdu
+#[test]
+fn test_incremental_crc32_001() {
+    let (pdu_in, protocol_type_in, total_length_in, label_in): (&[u8], u16, u16, &[u8]) =
+        (b"abcdefghijklmnopqrstuvwxyz", 0xFFFF, 32, b"012345");
+
+    let crc_calculator = DefaultCrc {};
+    let crc_exp =
+        crc_calculator.calculate_crc32(pdu_in, protocol_type_in, total_length_in, label_in);
+
+    for split in 0..=pdu_in.len() {
+        let mut crc = crc_calculator.init_crc32(protocol_type_in, total_length_in, label_in);
+        crc = crc_calculator.update_crc32(crc, &pdu_in[..split]);
+        crc = crc_calculator.update_crc32(crc, &pdu_in[split..]);
+        let crc_obs = crc_calculator.finalize_crc32(crc);
+
+        assert_eq!(crc_exp, crc_obs, "split at {}", split);
+    }
+}
+
+/// Fast CRC test: the crc is the same as the default crc, for all the lengths and alignments
+#[test]
+fn test_fast_crc32_001() {
+    let data: Vec<u8> = (0..1200u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let label: &[u8] = b"012345";
+
+    let default_crc = DefaultCrc {};
+    for crc_calculator in [FastCrc::new(), FastCrc::portable()] {
+        for start in 0..5 {
+            for end in (start..data.len()).step_by(7) {
+                let pdu_in = &data[start..end];
+                let crc_exp = default_crc.calculate_crc32(pdu_in, 0xFFFF, end as u16, label);
+                let crc_obs = crc_calculator.calculate_crc32(pdu_in, 0xFFFF, end as u16, label);
+
+                assert_eq!(
+                    crc_exp, crc_obs,
+                    "{:?} data[{}..{}]",
+                    crc_calculator, start, end
+                );
+            }
+        }
+    }
+}
+
+/// Fast CRC test: the crc computed chunk by chunk is the same as the default crc
+#[test]
+fn test_fast_crc32_002() {
+    let data: Vec<u8> = (0..700u32).map(|i| (i * 7 + 3) as u8).collect();
+
+    let default_crc = DefaultCrc {};
+    let crc_exp = default_crc.calculate_crc32(&data, 0x0800, 702, &[]);
+    for crc_calculator in [FastCrc::new(), FastCrc::portable()] {
+        for split in [0, 1, 63, 64, 129, 300, 699, 700] {
+            let mut crc = crc_calculator.init_crc32(0x0800, 702, &[]);
+            crc = crc_calculator.update_crc32(crc, &data[..split]);
+            crc = crc_calculator.update_crc32(crc, &data[split..]);
+            let crc_obs = crc_calculator.finalize_crc32(crc);
+
+            assert_eq!(crc_exp, crc_obs, "{:?} split at {}", crc_calculator, split);
+        }
+    }
+}
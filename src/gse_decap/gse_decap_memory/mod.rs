@@ -7,6 +7,7 @@
 mod tests;
 
 use super::super::gse_decap::DecapContext;
+use std::collections::BTreeMap;
 use std::mem;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -19,6 +20,33 @@ pub enum DecapMemoryError { //TODO
     UndefinedId,
     BufferTooSmall(Box<[u8]>),
     MemoryCorrupted,
+    /// The pdu has been received in more fragments than allowed, its context has been dropped.
+    TooManyFragments(DecapContext),
+}
+
+impl DecapMemoryError {
+    /// Return the variant of the error, without the buffer or the context it carries
+    pub fn kind(&self) -> DecapMemoryErrorKind {
+        match self {
+            Self::StorageOverflow(_) => DecapMemoryErrorKind::StorageOverflow,
+            Self::StorageUnderflow => DecapMemoryErrorKind::StorageUnderflow,
+            Self::UndefinedId => DecapMemoryErrorKind::UndefinedId,
+            Self::BufferTooSmall(_) => DecapMemoryErrorKind::BufferTooSmall,
+            Self::MemoryCorrupted => DecapMemoryErrorKind::MemoryCorrupted,
+            Self::TooManyFragments(_) => DecapMemoryErrorKind::TooManyFragments,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Variant of a [`DecapMemoryError`], without the data it carries
+pub enum DecapMemoryErrorKind {
+    StorageOverflow,
+    StorageUnderflow,
+    UndefinedId,
+    BufferTooSmall,
+    MemoryCorrupted,
+    TooManyFragments,
 }
 
 /// Represents the current state of the reconstruction process for a GSE packet from the fragments received up to the present moment.
@@ -31,6 +59,8 @@ pub type MemoryContext = (DecapContext, Box<[u8]>);
 /// Trait defining the function required by the decap memory struct.
 pub trait GseDecapMemory {
     /// Create a new empty `DecapMemory`
+    /// *   `max_delay` is the number of ticks a context can wait for the end of its pdu
+    /// *   `max_pdu_frag` is the maximum number of fragments of a pdu
     fn new(max_frag_id: usize, max_pdu_size: usize, max_delay: usize, max_pdu_frag: usize) -> Self;
 
     /// Provision of a storage buffer for the decap memory for writing purposes.
@@ -57,6 +87,24 @@ pub trait GseDecapMemory {
     /// Should be called after `take_context` or `new_context` to save the current state.
     /// If there is already a fragment saved it should return an `MemoryCorrupted` Error.
     fn save_frag(&mut self, context: MemoryContext) -> Result<(), DecapMemoryError>;
+
+    /// Advance the clock of the memory by `ticks`, e.g. one tick for each new base band frame.
+    /// The contexts waiting for more than `max_delay` ticks should be dropped, their buffer returned to the storage,
+    /// and the dropped contexts returned.
+    /// By default, the contexts never expire.
+    fn tick(&mut self, _ticks: usize) -> Vec<DecapContext> {
+        vec![]
+    }
+
+    /// Take the context replaced by the last call of `new_frag`, if any.
+    /// By default, the replaced contexts are not reported.
+    fn take_replaced(&mut self) -> Option<DecapContext> {
+        None
+    }
+
+    /// Set the input stream of the next packets, given by [`Decapsulator::set_stream`](crate::gse_decap::Decapsulator::set_stream).
+    /// By default, the frag ids are not scoped by stream.
+    fn set_stream(&mut self, _stream: u8) {}
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -64,35 +112,63 @@ pub trait GseDecapMemory {
 /// ### Limitations:
 /// *   The maximum number of buffer is fixed at the initialisation
 /// *   The index of the frag ids are calculted with `frag_id % max_frag_id`
+///
+/// A context is dropped when it has been waiting for more than `max_delay` ticks (see [`GseDecapMemory::tick`]),
+/// or when its pdu is received in more than `max_pdu_frag` fragments.
+/// A value of 0 disables the corresponding limit.
 pub struct SimpleGseMemory {
     storages: Vec<Box<[u8]>>,
     frags: Box<[Option<MemoryContext>]>,
+    frags_info: Box<[FragInfo]>,
+    replaced: Option<DecapContext>,
 
     max_frag_id: usize,
     max_pdu_size: usize,
-    // TODO:
-    //max_pdu_frag: usize,
-    //max_delay: usize,
+    max_pdu_frag: usize,
+    max_delay: usize,
+    now: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Information kept for each context to apply the limits of the memory
+/// *   Start describe the tick at which the first fragment was received
+/// *   Frag count describe the number of fragments received
+struct FragInfo {
+    start: usize,
+    frag_count: usize,
 }
 
 impl SimpleGseMemory {
     const MIN_MARGIN: usize = 2;
+
+    /// Give back a buffer to the storage, the buffer is freed if the storage is full
+    fn release(&mut self, storage: Box<[u8]>) {
+        if self.storages.len() < self.storages.capacity() {
+            self.storages.push(storage);
+        }
+    }
 }
 
 impl GseDecapMemory for SimpleGseMemory {
     fn new(
         max_frag_id: usize,
         max_pdu_size: usize,
-        _max_delay: usize,
-        _max_pdu_frag: usize,
+        max_delay: usize,
+        max_pdu_frag: usize,
     ) -> Self {
         let storages = Vec::with_capacity(max_frag_id + Self::MIN_MARGIN);
         let frags = vec![None; max_frag_id].into_boxed_slice();
+        let frags_info = vec![FragInfo::default(); max_frag_id].into_boxed_slice();
         Self {
             storages,
             frags,
+            frags_info,
+            replaced: None,
             max_frag_id,
             max_pdu_size,
+            max_pdu_frag,
+            max_delay,
+            now: 0,
         }
     }
 
@@ -123,13 +199,19 @@ impl GseDecapMemory for SimpleGseMemory {
         let mut frag: Option<MemoryContext> = None;
         mem::swap(&mut self.frags[idx], &mut frag);
 
-        match frag {
-            None => match self.new_pdu() {
-                Ok(pdu) => Ok((context, pdu)),
-                Err(err) => Err(err),
-            },
-            Some((_, pdu)) => Ok((context, pdu)),
-        }
+        self.replaced = None;
+        let pdu = match frag {
+            None => self.new_pdu()?,
+            Some((replaced, pdu)) => {
+                self.replaced = Some(replaced);
+                pdu
+            }
+        };
+        self.frags_info[idx] = FragInfo {
+            start: self.now,
+            frag_count: 1,
+        };
+        Ok((context, pdu))
     }
 
     fn take_frag(&mut self, frag_id: u8) -> Result<MemoryContext, DecapMemoryError> {
@@ -141,11 +223,17 @@ impl GseDecapMemory for SimpleGseMemory {
         match frag {
             None => Err(DecapMemoryError::UndefinedId),
             Some((context, pdu)) => {
-                if context.frag_id == frag_id {
-                    Ok((context, pdu))
-                } else {
-                    Err(DecapMemoryError::UndefinedId)
+                if context.frag_id != frag_id {
+                    return Err(DecapMemoryError::UndefinedId);
                 }
+
+                let info = &mut self.frags_info[idx];
+                info.frag_count += 1;
+                if self.max_pdu_frag != 0 && info.frag_count > self.max_pdu_frag {
+                    self.release(pdu);
+                    return Err(DecapMemoryError::TooManyFragments(context));
+                }
+                Ok((context, pdu))
             }
         }
     }
@@ -162,4 +250,196 @@ impl GseDecapMemory for SimpleGseMemory {
             Some(_) => Err(DecapMemoryError::MemoryCorrupted),
         }
     }
+
+    fn tick(&mut self, ticks: usize) -> Vec<DecapContext> {
+        self.now = self.now.wrapping_add(ticks);
+        let mut dropped = vec![];
+        if self.max_delay == 0 {
+            return dropped;
+        }
+
+        for idx in 0..self.frags.len() {
+            let delay = self.now.wrapping_sub(self.frags_info[idx].start);
+            if self.frags[idx].is_some() && delay > self.max_delay {
+                if let Some((context, pdu)) = self.frags[idx].take() {
+                    self.release(pdu);
+                    dropped.push(context);
+                }
+            }
+        }
+        dropped
+    }
+    fn take_replaced(&mut self) -> Option<DecapContext> {
+        self.replaced.take()
+    }
+}
+
+/// Key of a context in [`StreamGseMemory`]: the input stream and the frag id
+type FragKey = (u8, u8);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Implementation of the trait [`GseDecapMemory`] indexed by the full frag id
+///
+/// Unlike [`SimpleGseMemory`], the 256 frag ids can be used simultaneously without collision,
+/// only the number of buffers is bounded (`max_frag_id` contexts, plus a small margin for the complete packets).
+///
+/// When a first fragment is received for a frag id that already has a context, the old context is evicted:
+/// its buffer is reused for the new context and the old context is given by `take_replaced`,
+/// the `Decapsulator` reporting it to its [`DecapObserver`](crate::gse_decap::DecapObserver).
+///
+/// The frag ids can optionally be scoped by input stream with [`GseDecapMemory::set_stream`],
+/// called by [`Decapsulator::set_stream`](crate::gse_decap::Decapsulator::set_stream),
+/// so that the same frag id can be used at the same time on different streams.
+///
+/// The contexts expire like in [`SimpleGseMemory`] with `max_delay` and `max_pdu_frag`.
+pub struct StreamGseMemory {
+    storages: Vec<Box<[u8]>>,
+    frags: BTreeMap<FragKey, MemoryContext>,
+    frags_info: BTreeMap<FragKey, FragInfo>,
+    replaced: Option<DecapContext>,
+
+    max_pdu_size: usize,
+    max_pdu_frag: usize,
+    max_delay: usize,
+    now: usize,
+    stream: u8,
+}
+
+impl StreamGseMemory {
+    const MIN_MARGIN: usize = 2;
+
+    /// Get the current input stream
+    pub fn stream(&self) -> u8 {
+        self.stream
+    }
+
+    /// Give back a buffer to the storage, the buffer is freed if the storage is full
+    fn release(&mut self, storage: Box<[u8]>) {
+        if self.storages.len() < self.storages.capacity() {
+            self.storages.push(storage);
+        }
+    }
+}
+
+impl GseDecapMemory for StreamGseMemory {
+    fn new(
+        max_frag_id: usize,
+        max_pdu_size: usize,
+        max_delay: usize,
+        max_pdu_frag: usize,
+    ) -> Self {
+        Self {
+            storages: Vec::with_capacity(max_frag_id + Self::MIN_MARGIN),
+            frags: BTreeMap::new(),
+            frags_info: BTreeMap::new(),
+            replaced: None,
+            max_pdu_size,
+            max_pdu_frag,
+            max_delay,
+            now: 0,
+            stream: 0,
+        }
+    }
+
+    fn provision_storage(&mut self, storage: Box<[u8]>) -> Result<(), DecapMemoryError> {
+        if self.storages.capacity() == self.storages.len() {
+            return Err(DecapMemoryError::StorageOverflow(storage));
+        }
+
+        if storage.len() < self.max_pdu_size {
+            return Err(DecapMemoryError::BufferTooSmall(storage));
+        }
+
+        self.storages.push(storage);
+        Ok(())
+    }
+
+    fn new_pdu(&mut self) -> Result<Box<[u8]>, DecapMemoryError> {
+        self.storages.pop().ok_or(DecapMemoryError::StorageUnderflow)
+    }
+
+    fn new_frag(&mut self, context: DecapContext) -> Result<MemoryContext, DecapMemoryError> {
+        let key = (self.stream, context.frag_id);
+
+        self.replaced = None;
+        let pdu = match self.frags.remove(&key) {
+            None => self.new_pdu()?,
+            Some((replaced, pdu)) => {
+                self.replaced = Some(replaced);
+                pdu
+            }
+        };
+        self.frags_info.insert(
+            key,
+            FragInfo {
+                start: self.now,
+                frag_count: 1,
+            },
+        );
+        Ok((context, pdu))
+    }
+
+    fn take_frag(&mut self, frag_id: u8) -> Result<MemoryContext, DecapMemoryError> {
+        let key = (self.stream, frag_id);
+
+        let Some((context, pdu)) = self.frags.remove(&key) else {
+            return Err(DecapMemoryError::UndefinedId);
+        };
+
+        let info = self.frags_info.entry(key).or_default();
+        info.frag_count += 1;
+        if self.max_pdu_frag != 0 && info.frag_count > self.max_pdu_frag {
+            self.frags_info.remove(&key);
+            self.release(pdu);
+            return Err(DecapMemoryError::TooManyFragments(context));
+        }
+        Ok((context, pdu))
+    }
+
+    fn save_frag(&mut self, context: MemoryContext) -> Result<(), DecapMemoryError> {
+        let key = (self.stream, context.0.frag_id);
+
+        if self.frags.contains_key(&key) {
+            return Err(DecapMemoryError::MemoryCorrupted);
+        }
+        self.frags.insert(key, context);
+        Ok(())
+    }
+
+    fn tick(&mut self, ticks: usize) -> Vec<DecapContext> {
+        self.now = self.now.wrapping_add(ticks);
+        let mut dropped = vec![];
+        if self.max_delay == 0 {
+            return dropped;
+        }
+
+        let now = self.now;
+        let max_delay = self.max_delay;
+        let expired: Vec<FragKey> = self
+            .frags
+            .keys()
+            .filter(|key| {
+                let start = self.frags_info.get(key).map_or(now, |info| info.start);
+                now.wrapping_sub(start) > max_delay
+            })
+            .copied()
+            .collect();
+
+        for key in expired {
+            self.frags_info.remove(&key);
+            if let Some((context, pdu)) = self.frags.remove(&key) {
+                self.release(pdu);
+                dropped.push(context);
+            }
+        }
+        dropped
+    }
+    fn take_replaced(&mut self) -> Option<DecapContext> {
+        self.replaced.take()
+    }
+
+    /// Set the input stream of the next packets, the frag ids are scoped by stream.
+    fn set_stream(&mut self, stream: u8) {
+        self.stream = stream;
+    }
 }
@@ -1,7 +1,9 @@
 // Copyright 2023, Viveris Technologies
 // Distributed under the terms of the MIT License
 
-use crate::gse_decap::{DecapContext, DecapMemoryError, GseDecapMemory, SimpleGseMemory};
+use crate::gse_decap::{
+    DecapContext, DecapMemoryError, GseDecapMemory, SimpleGseMemory, StreamGseMemory,
+};
 use crate::label::Label;
 
 // -------------------- SimpleGseMemory
@@ -265,3 +267,348 @@ fn test_simple_memory_multiple_frag() {
     let exp = Ok((exp_context, exp_storage));
     assert_eq!(exp, obs);
 }
+
+#[test]
+fn test_simple_memory_expired_frag() {
+    let max_frag_id = 2;
+    let max_pdu_size = 100;
+    let max_delay = 2;
+    let max_pdu_frag = 0;
+
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![1; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 0, 1, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    // Not expired yet
+    assert_eq!(Vec::<DecapContext>::new(), memory.tick(1));
+    assert_eq!(Vec::<DecapContext>::new(), memory.tick(1));
+
+    // Expired
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 1, 0, 0, false, vec![]);
+    assert_eq!(vec![exp_context], memory.tick(1));
+    assert_eq!(Err(DecapMemoryError::UndefinedId), memory.take_frag(1));
+
+    // The buffer is back in the storage
+    let exp = Ok(vec![1; max_pdu_size].into_boxed_slice());
+    assert_eq!(exp, memory.new_pdu());
+}
+
+#[test]
+fn test_simple_memory_expired_frag_many_ticks() {
+    let max_frag_id = 2;
+    let max_pdu_size = 100;
+    let max_delay = 10;
+    let max_pdu_frag = 0;
+
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    for _ in 0..2 {
+        memory
+            .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+            .unwrap();
+    }
+
+    let context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+    memory.tick(6);
+
+    let context = DecapContext::new(Label::Broadcast, 0, 1, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    // Only the first context is expired
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    assert_eq!(vec![exp_context], memory.tick(5));
+    assert!(memory.take_frag(1).is_ok());
+}
+
+#[test]
+fn test_simple_memory_no_delay() {
+    let max_frag_id = 1;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    // The contexts never expire
+    assert_eq!(Vec::<DecapContext>::new(), memory.tick(usize::MAX / 2));
+    assert!(memory.take_frag(0).is_ok());
+}
+
+#[test]
+fn test_simple_memory_too_many_fragments() {
+    let max_frag_id = 1;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 3;
+
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![2; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    // First fragment
+    let context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    // Second and third fragments
+    for _ in 0..2 {
+        let frag = memory.take_frag(0).unwrap();
+        memory.save_frag(frag).unwrap();
+    }
+
+    // Fourth fragment
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let obs = memory.take_frag(0);
+    assert_eq!(Err(DecapMemoryError::TooManyFragments(exp_context)), obs);
+
+    // The buffer is back in the storage
+    let exp = Ok(vec![2; max_pdu_size].into_boxed_slice());
+    assert_eq!(exp, memory.new_pdu());
+}
+
+#[test]
+fn test_simple_memory_replaced() {
+    let max_frag_id = 1;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 1, 0, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    assert_eq!(None, memory.take_replaced());
+    memory.save_frag(frag).unwrap();
+
+    // frag ids 0 and 1 share the same index, the first context is replaced
+    let context = DecapContext::new(Label::Broadcast, 2, 1, 0, 0, false, vec![]);
+    memory.new_frag(context).unwrap();
+    let exp_replaced = DecapContext::new(Label::Broadcast, 1, 0, 0, 0, false, vec![]);
+    assert_eq!(Some(exp_replaced), memory.take_replaced());
+    assert_eq!(None, memory.take_replaced());
+}
+
+// -------------------- StreamGseMemory
+#[test]
+fn test_stream_memory_colliding_frag_ids() {
+    let max_frag_id = 8;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    for i in 0..2 {
+        memory
+            .provision_storage(vec![i; max_pdu_size].into_boxed_slice())
+            .unwrap();
+    }
+
+    // frag ids 0 and 8 would share the same index in a SimpleGseMemory
+    let context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+    let context = DecapContext::new(Label::Broadcast, 0, 8, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    assert_eq!(None, memory.take_replaced());
+    memory.save_frag(frag).unwrap();
+
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let exp = Ok((exp_context, vec![1; max_pdu_size].into_boxed_slice()));
+    assert_eq!(exp, memory.take_frag(0));
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 8, 0, 0, false, vec![]);
+    let exp = Ok((exp_context, vec![0; max_pdu_size].into_boxed_slice()));
+    assert_eq!(exp, memory.take_frag(8));
+}
+
+#[test]
+fn test_stream_memory_all_frag_ids() {
+    let max_frag_id = 256;
+    let max_pdu_size = 10;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    for _ in 0..max_frag_id {
+        memory
+            .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+            .unwrap();
+    }
+
+    for frag_id in 0..=u8::MAX {
+        let context = DecapContext::new(Label::Broadcast, 0, frag_id, 0, 0, false, vec![]);
+        let frag = memory.new_frag(context).unwrap();
+        memory.save_frag(frag).unwrap();
+    }
+    for frag_id in 0..=u8::MAX {
+        let (context, _) = memory.take_frag(frag_id).unwrap();
+        assert_eq!(frag_id, context.frag_id);
+    }
+}
+
+#[test]
+fn test_stream_memory_eviction() {
+    let max_frag_id = 1;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![3; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 1, 4, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    // A new first fragment with the same frag id evicts the context and reuses its buffer
+    let context = DecapContext::new(Label::Broadcast, 2, 4, 0, 0, false, vec![]);
+    let obs = memory.new_frag(context);
+    let exp_context = DecapContext::new(Label::Broadcast, 2, 4, 0, 0, false, vec![]);
+    assert_eq!(Ok((exp_context, vec![3; max_pdu_size].into_boxed_slice())), obs);
+
+    let exp_evicted = DecapContext::new(Label::Broadcast, 1, 4, 0, 0, false, vec![]);
+    assert_eq!(Some(exp_evicted), memory.take_replaced());
+    assert_eq!(None, memory.take_replaced());
+}
+
+#[test]
+fn test_stream_memory_streams() {
+    let max_frag_id = 2;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    for _ in 0..2 {
+        memory
+            .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+            .unwrap();
+    }
+
+    // The same frag id on two streams
+    memory.set_stream(1);
+    let context = DecapContext::new(Label::Broadcast, 1, 5, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+    memory.set_stream(2);
+    let context = DecapContext::new(Label::Broadcast, 2, 5, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    assert_eq!(None, memory.take_replaced());
+    memory.save_frag(frag).unwrap();
+
+    memory.set_stream(3);
+    assert_eq!(Err(DecapMemoryError::UndefinedId), memory.take_frag(5));
+    memory.set_stream(1);
+    assert_eq!(1, memory.take_frag(5).unwrap().0.protocol_type);
+    memory.set_stream(2);
+    assert_eq!(2, memory.take_frag(5).unwrap().0.protocol_type);
+    assert_eq!(2, memory.stream());
+}
+
+#[test]
+fn test_stream_memory_underflow() {
+    let max_frag_id = 1;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 0, 0, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    // No buffer left, the existing context is kept
+    let context = DecapContext::new(Label::Broadcast, 0, 1, 0, 0, false, vec![]);
+    assert_eq!(Err(DecapMemoryError::StorageUnderflow), memory.new_frag(context));
+    assert!(memory.take_frag(0).is_ok());
+}
+
+#[test]
+fn test_stream_memory_save_frag_corrupted() {
+    let max_frag_id = 8;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+
+    let context = DecapContext::new(Label::ReUse, 0, 0, 0, 0, false, vec![]);
+    let storage: Box<[u8]> = vec![0; max_pdu_size].into_boxed_slice();
+    memory.save_frag((context, storage)).unwrap();
+
+    let context = DecapContext::new(Label::ReUse, 0, 0, 0, 0, false, vec![]);
+    let storage: Box<[u8]> = vec![0; max_pdu_size].into_boxed_slice();
+    let obs = memory.save_frag((context, storage));
+
+    assert_eq!(obs, Err(DecapMemoryError::MemoryCorrupted));
+}
+
+#[test]
+fn test_stream_memory_expired_frag() {
+    let max_frag_id = 2;
+    let max_pdu_size = 100;
+    let max_delay = 1;
+    let max_pdu_frag = 0;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![1; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 0, 200, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    assert_eq!(Vec::<DecapContext>::new(), memory.tick(1));
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 200, 0, 0, false, vec![]);
+    assert_eq!(vec![exp_context], memory.tick(1));
+    assert_eq!(Err(DecapMemoryError::UndefinedId), memory.take_frag(200));
+    assert_eq!(Ok(vec![1; max_pdu_size].into_boxed_slice()), memory.new_pdu());
+}
+
+#[test]
+fn test_stream_memory_too_many_fragments() {
+    let max_frag_id = 1;
+    let max_pdu_size = 100;
+    let max_delay = 0;
+    let max_pdu_frag = 2;
+
+    let mut memory = StreamGseMemory::new(max_frag_id, max_pdu_size, max_delay, max_pdu_frag);
+    memory
+        .provision_storage(vec![0; max_pdu_size].into_boxed_slice())
+        .unwrap();
+
+    let context = DecapContext::new(Label::Broadcast, 0, 9, 0, 0, false, vec![]);
+    let frag = memory.new_frag(context).unwrap();
+    memory.save_frag(frag).unwrap();
+    let frag = memory.take_frag(9).unwrap();
+    memory.save_frag(frag).unwrap();
+
+    let exp_context = DecapContext::new(Label::Broadcast, 0, 9, 0, 0, false, vec![]);
+    let obs = memory.take_frag(9);
+    assert_eq!(Err(DecapMemoryError::TooManyFragments(exp_context)), obs);
+    assert!(memory.new_pdu().is_ok());
+}
@@ -0,0 +1,62 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the filtering of the received pdus by their label
+//!
+//! A terminal only keeps the pdus addressed to its own labels, to the multicast labels it has subscribed to and, optionally, to the broadcast label.
+//! The [`LabelFilter`] set in the [`Decapsulator`](super::Decapsulator) is checked before any buffer is taken from the memory:
+//! the complete packets and the first fragments sent with another label are dropped, and so are the next fragments of the same frag id.
+//!
+//! Each label accepted is routed to a sink, an index chosen by the user, so that the pdus received can be demultiplexed.
+
+#[cfg(test)]
+mod tests;
+
+use crate::label::Label;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+/// Structure LabelFilter
+///
+/// The filter contains the labels accepted with the sink of their pdus, all the other labels are dropped.
+/// The re-use label is resolved by the decapsulator before the filtering, so it never has to be added.
+pub struct LabelFilter {
+    labels: Vec<(Label, usize)>,
+}
+
+impl LabelFilter {
+    /// Create a filter dropping every label
+    pub fn new() -> Self {
+        Self { labels: vec![] }
+    }
+
+    /// Accept the pdus sent with `label` and route them to `sink`
+    ///
+    /// If the label is already accepted, its sink is replaced.
+    pub fn add_label(&mut self, label: Label, sink: usize) {
+        match self
+            .labels
+            .iter_mut()
+            .find(|(accepted, _)| *accepted == label)
+        {
+            Some((_, accepted_sink)) => *accepted_sink = sink,
+            None => self.labels.push((label, sink)),
+        }
+    }
+
+    /// Drop the pdus sent with `label`, return the sink they were routed to if the label was accepted
+    pub fn remove_label(&mut self, label: &Label) -> Option<usize> {
+        let index = self
+            .labels
+            .iter()
+            .position(|(accepted, _)| accepted == label)?;
+        Some(self.labels.remove(index).1)
+    }
+
+    /// Return the sink of the pdus sent with `label`, or `None` if they are dropped
+    pub fn route(&self, label: &Label) -> Option<usize> {
+        self.labels
+            .iter()
+            .find(|(accepted, _)| accepted == label)
+            .map(|(_, sink)| *sink)
+    }
+}
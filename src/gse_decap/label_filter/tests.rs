@@ -0,0 +1,293 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::DefaultCrc;
+use crate::gse_decap::{
+    DecapMetadata, DecapStatus, Decapsulator, GseDecapMemory, LabelFilter, SimpleGseMemory,
+};
+use crate::gse_encap::{ContextFrag, EncapMetadata, EncapStatus, Encapsulator};
+use crate::header_extension::SimpleMandatoryExtensionHeaderManager;
+use crate::label::Label;
+
+const OWN_LABEL: Label = Label::SixBytesLabel(*b"012345");
+const MULTICAST_LABEL: Label = Label::ThreeBytesLabel(*b"abc");
+const FOREIGN_LABEL: Label = Label::SixBytesLabel(*b"543210");
+
+fn create_decapsulator(
+    storage_count: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> {
+    let mut memory = SimpleGseMemory::new(storage_count, 26, 0, 0);
+    for _ in 0..storage_count {
+        memory
+            .provision_storage(vec![0; 26].into_boxed_slice())
+            .unwrap();
+    }
+    let mut decapsulator = Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+    );
+    let mut label_filter = LabelFilter::new();
+    label_filter.add_label(OWN_LABEL, 0);
+    label_filter.add_label(MULTICAST_LABEL, 1);
+    decapsulator.set_label_filter(Some(label_filter));
+    decapsulator
+}
+
+/// Encapsulate the pdu in a first fragment of `first_len` bytes, return its length and the context
+fn encap_first(
+    encapsulator: &mut Encapsulator<DefaultCrc>,
+    pdu: &[u8],
+    frag_id: u8,
+    label: Label,
+    first_len: usize,
+    buffer: &mut [u8],
+) -> (usize, ContextFrag) {
+    let metadata = EncapMetadata::new(0x1234, label);
+    let Ok(EncapStatus::FragmentedPkt(len, context)) =
+        encapsulator.encap(pdu, frag_id, metadata, &mut buffer[..first_len])
+    else {
+        panic!()
+    };
+    (len as usize, context)
+}
+
+#[test]
+fn test_label_filter_001() {
+    let comment = "labels added, replaced and removed";
+    let mut label_filter = LabelFilter::new();
+    assert_eq!(None, label_filter.route(&Label::Broadcast), "{}", comment);
+
+    label_filter.add_label(OWN_LABEL, 0);
+    label_filter.add_label(Label::Broadcast, 2);
+    label_filter.add_label(OWN_LABEL, 1);
+
+    assert_eq!(Some(1), label_filter.route(&OWN_LABEL), "{}", comment);
+    assert_eq!(
+        Some(2),
+        label_filter.route(&Label::Broadcast),
+        "{}",
+        comment
+    );
+    assert_eq!(None, label_filter.route(&FOREIGN_LABEL), "{}", comment);
+    assert_eq!(
+        Some(2),
+        label_filter.remove_label(&Label::Broadcast),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        None,
+        label_filter.remove_label(&Label::Broadcast),
+        "{}",
+        comment
+    );
+    assert_eq!(None, label_filter.route(&Label::Broadcast), "{}", comment);
+}
+
+#[test]
+fn test_label_filter_002() {
+    let comment = "complete packets of the labels not accepted dropped without memory";
+    // a single buffer in memory, used by the only pdu accepted
+    let mut decapsulator = create_decapsulator(1);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut frame = [0; 200];
+    let mut offset = 0;
+    for label in [
+        FOREIGN_LABEL,
+        FOREIGN_LABEL,
+        Label::Broadcast,
+        MULTICAST_LABEL,
+    ] {
+        let metadata = EncapMetadata::new(0x1234, label);
+        let Ok(EncapStatus::CompletedPkt(len)) =
+            encapsulator.encap(b"pdu", 0, metadata, &mut frame[offset..])
+        else {
+            panic!("{}", comment)
+        };
+        offset += len as usize;
+    }
+
+    let obs: Vec<_> = decapsulator
+        .decap_frame(&frame)
+        .map(|status| status.unwrap())
+        .collect();
+
+    // the second packet re-uses the foreign label
+    let exp = vec![
+        DecapStatus::FilteredPkt,
+        DecapStatus::FilteredPkt,
+        DecapStatus::FilteredPkt,
+        DecapStatus::CompletedPkt(
+            Box::new([
+                b'p', b'd', b'u', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0,
+            ]),
+            DecapMetadata::new(3, 0x1234, MULTICAST_LABEL, vec![]),
+        ),
+    ];
+    assert_eq!(exp, obs, "{}", comment);
+    assert_eq!(
+        Some(1),
+        decapsulator.label_filter().unwrap().route(&MULTICAST_LABEL),
+        "{}",
+        comment
+    );
+
+    // without filter, every pdu is kept
+    let Some(DecapStatus::CompletedPkt(pdu, _)) = obs.into_iter().last() else {
+        panic!("{}", comment)
+    };
+    decapsulator.provision_storage(pdu).unwrap();
+    decapsulator.set_label_filter(None);
+    let status = decapsulator.decap(&frame).unwrap().0;
+    assert!(
+        matches!(status, DecapStatus::CompletedPkt(_, _)),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_label_filter_003() {
+    let comment = "fragments of a foreign pdu dropped by frag id, interleaved with an accepted pdu";
+    let mut decapsulator = create_decapsulator(1);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.disable_re_use_label();
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut frame = [0; 200];
+
+    let (len1, foreign_context) =
+        encap_first(&mut encapsulator, pdu, 1, FOREIGN_LABEL, 20, &mut frame);
+    let (len2, own_context) =
+        encap_first(&mut encapsulator, pdu, 2, OWN_LABEL, 20, &mut frame[len1..]);
+    let mut offset = len1 + len2;
+    let Ok(EncapStatus::FragmentedPkt(len, foreign_context)) =
+        encapsulator.encap_frag(pdu, &foreign_context, &mut frame[offset..offset + 10])
+    else {
+        panic!("{}", comment)
+    };
+    offset += len as usize;
+    for context in [foreign_context, own_context] {
+        let Ok(EncapStatus::CompletedPkt(len)) =
+            encapsulator.encap_frag(pdu, &context, &mut frame[offset..])
+        else {
+            panic!("{}", comment)
+        };
+        offset += len as usize;
+    }
+
+    let obs: Vec<_> = decapsulator
+        .decap_frame(&frame[..offset])
+        .map(|status| status.unwrap())
+        .collect();
+
+    let exp = vec![
+        DecapStatus::FilteredPkt,
+        DecapStatus::FragmentedPkt(DecapMetadata::new(0, 0x1234, OWN_LABEL, vec![])),
+        DecapStatus::FilteredPkt,
+        DecapStatus::FilteredPkt,
+        DecapStatus::CompletedPkt(
+            Box::new(*pdu),
+            DecapMetadata::new(26, 0x1234, OWN_LABEL, vec![]),
+        ),
+    ];
+    assert_eq!(exp, obs, "{}", comment);
+}
+
+#[test]
+fn test_label_filter_004() {
+    let comment =
+        "foreign first fragment dropped without touching the context sharing its slot in memory";
+    // frag ids 0 and 1 share the only slot of the memory
+    let mut decapsulator = create_decapsulator(1);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.disable_re_use_label();
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut frame = [0; 200];
+
+    let (len1, own_context) = encap_first(&mut encapsulator, pdu, 0, OWN_LABEL, 20, &mut frame);
+    let (len2, _) = encap_first(
+        &mut encapsulator,
+        pdu,
+        1,
+        FOREIGN_LABEL,
+        20,
+        &mut frame[len1..],
+    );
+    let mut offset = len1 + len2;
+    let Ok(EncapStatus::CompletedPkt(len)) =
+        encapsulator.encap_frag(pdu, &own_context, &mut frame[offset..])
+    else {
+        panic!("{}", comment)
+    };
+    offset += len as usize;
+
+    let obs: Vec<_> = decapsulator
+        .decap_frame(&frame[..offset])
+        .map(|status| status.unwrap())
+        .collect();
+
+    let exp = vec![
+        DecapStatus::FragmentedPkt(DecapMetadata::new(0, 0x1234, OWN_LABEL, vec![])),
+        DecapStatus::FilteredPkt,
+        DecapStatus::CompletedPkt(
+            Box::new(*pdu),
+            DecapMetadata::new(26, 0x1234, OWN_LABEL, vec![]),
+        ),
+    ];
+    assert_eq!(exp, obs, "{}", comment);
+}
+
+#[test]
+fn test_label_filter_005() {
+    let comment = "frag id of a foreign pdu dropped in its input stream only";
+    let mut decapsulator = create_decapsulator(2);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.disable_re_use_label();
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut foreign_first = [0; 20];
+    let mut own_first = [0; 20];
+    let (_, foreign_context) = encap_first(
+        &mut encapsulator,
+        pdu,
+        1,
+        FOREIGN_LABEL,
+        20,
+        &mut foreign_first,
+    );
+    let (_, own_context) = encap_first(&mut encapsulator, pdu, 1, OWN_LABEL, 20, &mut own_first);
+    let mut foreign_end = [0; 100];
+    let mut own_end = [0; 100];
+    encapsulator
+        .encap_frag(pdu, &foreign_context, &mut foreign_end)
+        .unwrap();
+    encapsulator
+        .encap_frag(pdu, &own_context, &mut own_end)
+        .unwrap();
+
+    decapsulator.set_stream(1);
+    let (status, _) = decapsulator.decap(&foreign_first).unwrap();
+    assert_eq!(DecapStatus::FilteredPkt, status, "{}", comment);
+    decapsulator.set_stream(2);
+    let (status, _) = decapsulator.decap(&own_first).unwrap();
+    assert!(
+        matches!(status, DecapStatus::FragmentedPkt(_)),
+        "{}",
+        comment
+    );
+    decapsulator.set_stream(1);
+    let (status, _) = decapsulator.decap(&foreign_end).unwrap();
+    assert_eq!(DecapStatus::FilteredPkt, status, "{}", comment);
+    decapsulator.set_stream(2);
+    let (status, _) = decapsulator.decap(&own_end).unwrap();
+    assert_eq!(
+        DecapStatus::CompletedPkt(
+            Box::new(*pdu),
+            DecapMetadata::new(26, 0x1234, OWN_LABEL, vec![])
+        ),
+        status,
+        "{}",
+        comment
+    );
+}
@@ -7,36 +7,78 @@
 //! It supports complete packet, first fragment packet, intermediate fragment packet, end fragment packet and padding.
 //! It also allows you to manage any type of label including the re-use label.
 
-pub use self::gse_decap_memory::{DecapMemoryError, GseDecapMemory, SimpleGseMemory};
+pub use self::gse_decap_memory::{
+    DecapMemoryError, DecapMemoryErrorKind, GseDecapMemory, SimpleGseMemory, StreamGseMemory,
+};
+pub use self::label_filter::LabelFilter;
+pub use self::observer::{DecapObserver, EvictionReason, NoDecapObserver};
 use crate::crc::CrcCalculator;
 use crate::gse_standard::{
-    COMPLETE_PKT, CRC_LEN, END_PKT, FIRST_PKT, FIXED_HEADER_LEN, FRAG_ID_LEN, GSE_LEN_MASK,
-    H_LEN_MASK, INTERMEDIATE_PKT, LABEL_3_B, LABEL_3_B_LEN, LABEL_6_B, LABEL_6_B_LEN,
-    LABEL_BROADCAST, LABEL_REUSE, LABEL_TYPE_MASK, PROTOCOL_LEN, SECOND_RANGE_PTYPE,
-    START_END_MASK, TOTAL_LENGTH_LEN,
+    BRIDGED_FRAME_PROTOCOL_ID, COMPLETE_PKT, CRC_INIT, CRC_LEN, END_PKT, ETHERNET_HEADER_LEN,
+    FIRST_PKT, FIXED_HEADER_LEN, FRAG_ID_LEN, GSE_LEN_MASK, H_LEN_MASK, INTERMEDIATE_PKT,
+    LABEL_3_B, LABEL_3_B_LEN, LABEL_6_B, LABEL_6_B_LEN, LABEL_BROADCAST, LABEL_REUSE,
+    LABEL_TYPE_MASK, MAX_MANDATORY_VAL_PTYPE, PROTOCOL_LEN, SECOND_RANGE_PTYPE, START_END_MASK,
+    TOTAL_LENGTH_LEN, TS_CONCAT_PROTOCOL_ID,
 };
 use crate::header_extension::{
-    optionnal_extension_data_size_from_hlen, Extension, MandatoryHeaderExt,
-    MandatoryHeaderExtensionManager,
+    optionnal_extension_data_size_from_hlen, pdu_concat, ts_concat, EthernetHeader, Extension,
+    ExtensionCoverage, MandatoryHeaderExt, MandatoryHeaderExtensionManager,
 };
 use crate::label::{Label, LabelType};
 use crate::pkt_type::PktType;
+use crate::stats::DecapStats;
+use std::collections::BTreeSet;
+use std::ops::Range;
 
 pub mod gse_decap_memory;
+pub mod label_filter;
+pub mod observer;
 #[cfg(test)]
 mod tests;
 
+/// Range of each pdu of a packet of concatenated pdus
+type PduRanges = Vec<Range<usize>>;
+
+/// Packet read by the decapsulation, as counted in the statistics and reported to the observer
+enum ReadPkt {
+    Padding(usize),
+    Filtered,
+    /// Label, protocol type and number of the pdus completed by the packet
+    Pdus(Label, u16, usize),
+    Fragment,
+}
+
+impl ReadPkt {
+    /// Packet of `pkt_len` bytes read by [`Decapsulator::decap`] with the returned `status`
+    fn new(status: &DecapStatus, pkt_len: usize) -> Self {
+        match status {
+            DecapStatus::Padding => Self::Padding(pkt_len),
+            DecapStatus::CompletedPkt(_, metadata) => {
+                Self::Pdus(metadata.label, metadata.protocol_type, 1)
+            }
+            DecapStatus::ConcatenatedPkt(_, metadata, pdus) => {
+                Self::Pdus(metadata.label, metadata.protocol_type, pdus.len())
+            }
+            DecapStatus::FilteredPkt => Self::Filtered,
+            DecapStatus::FragmentedPkt(_) => Self::Fragment,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// Store the Metadata read from GSE packet
 ///
 /// *   Pdu length describe the length of the pdu store in a buffer
 /// *   Protocol type describe the protocol of that pdu
 /// *   Label describe the recipient of that pdu
+/// *   Bridged frame is the Ethernet header of a pdu received with the Bridged Frame extension,
+///     its EtherType is then the protocol type and the pdu is the payload of the frame
 pub struct DecapMetadata {
     pdu_len: usize,
     protocol_type: u16,
     label: Label,
     extensions: Vec<Extension>,
+    bridged_frame: Option<EthernetHeader>,
 }
 
 impl DecapMetadata {
@@ -51,6 +93,7 @@ impl DecapMetadata {
             protocol_type,
             label,
             extensions,
+            bridged_frame: None,
         }
     }
 
@@ -66,6 +109,9 @@ impl DecapMetadata {
     pub fn extensions(&self) -> &Vec<Extension> {
         &self.extensions
     }
+    pub fn bridged_frame(&self) -> Option<EthernetHeader> {
+        self.bridged_frame
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -73,18 +119,80 @@ impl DecapMetadata {
 /// TODO developp variant
 /// If a pdu is completely decapsulated, the status return a by payload the option completed packet.
 /// Else, if the decapsulation failed, the status return a comment about the error that occured.
+///
+/// The enum is non exhaustive since `FilteredPkt` and `ConcatenatedPkt` were added,
+/// a match on the status needs a wildcard arm for the statuses added later.
+#[non_exhaustive]
 pub enum DecapStatus {
     CompletedPkt(Box<[u8]>, DecapMetadata),
     FragmentedPkt(DecapMetadata),
     Padding,
+    /// The packet is dropped by the label filter.
+    FilteredPkt,
+    /// The pdus packed with the PDU-Concat extension, or the TS packets carried with the TS-Concat extension, each one at its range in the buffer.
+    /// The pdu length of the metadata is the length of the whole sequence,
+    /// see [`crate::header_extension::pdu_concat`] and [`crate::header_extension::ts_concat`].
+    ConcatenatedPkt(Box<[u8]>, DecapMetadata, Vec<Range<usize>>),
 }
 
 impl DecapStatus {
     pub fn to_str(&self) -> &'static str {
         match self {
             Self::CompletedPkt(_, _) => "Fully decapsulated packet",
+            Self::ConcatenatedPkt(_, _, _) => "Fully decapsulated packet of concatenated pdus",
             Self::FragmentedPkt(_) => "Partially decapsulated packet",
             Self::Padding => "Padding detected",
+            Self::FilteredPkt => "Packet dropped by the label filter",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Define the status of the decapsulation by [`Decapsulator::decap_borrowed`].
+///
+/// The pdu of a complete packet is borrowed from the input buffer,
+/// only the pdus reassembled from fragments are written in a buffer of the memory.
+///
+/// As [`DecapStatus`], the enum is non exhaustive.
+#[non_exhaustive]
+pub enum DecapBorrowedStatus<'a> {
+    CompletedPkt(&'a [u8], DecapMetadata),
+    ReassembledPkt(Box<[u8]>, DecapMetadata),
+    FragmentedPkt(DecapMetadata),
+    Padding,
+    FilteredPkt,
+    /// The pdus packed with the PDU-Concat extension, or the TS packets, in a complete packet.
+    ConcatenatedPkt(Vec<&'a [u8]>, DecapMetadata),
+    /// The pdus packed with the PDU-Concat extension, or the TS packets, in a reassembled pdu, each one at its range in the buffer.
+    ReassembledConcatenatedPkt(Box<[u8]>, DecapMetadata, Vec<Range<usize>>),
+}
+
+impl DecapBorrowedStatus<'_> {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::CompletedPkt(_, _) => "Fully decapsulated packet",
+            Self::ReassembledPkt(_, _) => "Fully decapsulated and reassembled packet",
+            Self::ConcatenatedPkt(_, _) => "Fully decapsulated packet of concatenated pdus",
+            Self::ReassembledConcatenatedPkt(_, _, _) => {
+                "Fully decapsulated and reassembled packet of concatenated pdus"
+            }
+            Self::FragmentedPkt(_) => "Partially decapsulated packet",
+            Self::Padding => "Padding detected",
+            Self::FilteredPkt => "Packet dropped by the label filter",
+        }
+    }
+}
+
+impl From<DecapStatus> for DecapBorrowedStatus<'_> {
+    fn from(status: DecapStatus) -> Self {
+        match status {
+            DecapStatus::CompletedPkt(pdu, metadata) => Self::ReassembledPkt(pdu, metadata),
+            DecapStatus::FragmentedPkt(metadata) => Self::FragmentedPkt(metadata),
+            DecapStatus::Padding => Self::Padding,
+            DecapStatus::FilteredPkt => Self::FilteredPkt,
+            DecapStatus::ConcatenatedPkt(pdu, metadata, pdus) => {
+                Self::ReassembledConcatenatedPkt(pdu, metadata, pdus)
+            }
         }
     }
 }
@@ -130,6 +238,9 @@ pub enum DecapError {
 
     /// Indicates that a unknown mandatory extension has been read, so the packet should be dropped.
     ErrorUnkownMandatoryHeader,
+
+    /// Indicates that a header extension can not be decoded, so the packet should be dropped.
+    ErrorInvalidHeaderExtension,
 }
 
 impl DecapError {
@@ -157,8 +268,48 @@ impl DecapError {
                 "Total length in header doesn't correspond to the total length of the defragmented packet"
             }
             Self::ErrorGseLength => "Pdu buffer is smaller than pdu received",
+            Self::ErrorInvalidHeaderExtension => "Header extension can not be decoded",
         }
     }
+
+    /// Return the variant of the error, without the data carried by a memory error
+    pub fn kind(&self) -> DecapErrorKind {
+        match self {
+            Self::ErrorSizeBuffer => DecapErrorKind::ErrorSizeBuffer,
+            Self::ErrorTotalLength => DecapErrorKind::ErrorTotalLength,
+            Self::ErrorGseLength => DecapErrorKind::ErrorGseLength,
+            Self::ErrorSizePduBuffer => DecapErrorKind::ErrorSizePduBuffer,
+            Self::ErrorProtocolType => DecapErrorKind::ErrorProtocolType,
+            Self::ErrorMemory(error) => DecapErrorKind::ErrorMemory(error.kind()),
+            Self::ErrorCrc => DecapErrorKind::ErrorCrc,
+            Self::ErrorInvalidLabel => DecapErrorKind::ErrorInvalidLabel,
+            Self::ErrorNoLabelSaved => DecapErrorKind::ErrorNoLabelSaved,
+            Self::ErrorLabelBroadcastSaved => DecapErrorKind::ErrorLabelBroadcastSaved,
+            Self::ErrorLabelReUseSaved => DecapErrorKind::ErrorLabelReUseSaved,
+            Self::ErrorUnkownMandatoryHeader => DecapErrorKind::ErrorUnkownMandatoryHeader,
+            Self::ErrorInvalidHeaderExtension => DecapErrorKind::ErrorInvalidHeaderExtension,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+/// Variant of a [`DecapError`], the memory errors being given by their own variant only.
+///
+/// The kinds are finite, so that they can be counted without keeping the buffers or the contexts of the errors.
+pub enum DecapErrorKind {
+    ErrorSizeBuffer,
+    ErrorTotalLength,
+    ErrorGseLength,
+    ErrorSizePduBuffer,
+    ErrorProtocolType,
+    ErrorMemory(DecapMemoryErrorKind),
+    ErrorCrc,
+    ErrorInvalidLabel,
+    ErrorNoLabelSaved,
+    ErrorLabelBroadcastSaved,
+    ErrorLabelReUseSaved,
+    ErrorUnkownMandatoryHeader,
+    ErrorInvalidHeaderExtension,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -167,6 +318,8 @@ impl DecapError {
 /// *   Label, protocol type, total len are read in the first fragment
 /// *   Frag id is used to identify the context.
 /// *   Pdu len represents the length of the PDU already received, it's updated with each new fragment received
+/// *   Crc represents the state of the cyclic redundancy check over the fragments already received,
+///     it's initialised by the decapsulator and updated with each new fragment received
 pub struct DecapContext {
     pub label: Label,
     pub protocol_type: u16,
@@ -175,6 +328,7 @@ pub struct DecapContext {
     pub pdu_len: u16,
     pub from_label_reuse: bool,
     pub extensions_header: Vec<Extension>,
+    pub crc: u32,
 }
 
 impl DecapContext {
@@ -195,6 +349,7 @@ impl DecapContext {
             pdu_len,
             from_label_reuse,
             extensions_header,
+            crc: CRC_INIT,
         }
     }
 }
@@ -205,13 +360,44 @@ impl DecapContext {
 /// TODO add header ext infoT
 /// The memory has to implement the trait `GseDecapMemory`. It is required to use the decap function.
 ///
-/// The last label has to be reset by the user at the begining of each new base band frame
-pub struct Decapsulator<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
-{
+/// The last label has to be reset by the user at the begining of each new base band frame.
+/// It follows the label of the complete packets and of the first fragments of the current input stream:
+/// *   `None` when no label has been read since the start of the base band frame, a re use label is then an error,
+/// *   a 3 or 6 bytes label, re used by the next packets sent with a re use label,
+/// *   `Label::Broadcast` after a broadcast label, a re use label is then an error.
+///
+/// A label read without error is kept even if the rest of its packet is dropped.
+/// The last label is only reset when the header of a packet can not be read, since the start of the next packet is then unknown.
+/// Each input stream, selected with [`Decapsulator::set_stream`], has its own last label.
+///
+/// When a [`LabelFilter`] is set, the packets sent with a label it does not accept are dropped with the status `FilteredPkt`,
+/// `filtered_frag_ids` marks the frag ids of the first fragments dropped, in their input stream, so that their next fragments are dropped too.
+/// The memory is not used by the packets dropped.
+///
+/// When the statistics are enabled, `stats` counts the packets read and the errors returned, see the [`crate::stats`] module.
+///
+/// The header extensions of the fragmented pdus are expected in the total length and the crc as specified by the standard,
+/// unless `extension_coverage` is set to [`ExtensionCoverage::Legacy`].
+///
+/// The observer is notified of the events of the decapsulation, see the [`observer`] module.
+/// The decapsulator created with [`Decapsulator::new`] uses [`NoDecapObserver`], which ignores them.
+pub struct Decapsulator<
+    T: GseDecapMemory,
+    C: CrcCalculator,
+    MHEM: MandatoryHeaderExtensionManager,
+    O: DecapObserver = NoDecapObserver,
+> {
     pub memory: T,
     crc_calculator: C,
     last_label: Option<Label>,
+    stream: u8,
+    stream_last_labels: [Option<Label>; 256],
     mandatory_extension_manager: MHEM,
+    label_filter: Option<LabelFilter>,
+    filtered_frag_ids: BTreeSet<(u8, u8)>,
+    stats: Option<DecapStats>,
+    extension_coverage: ExtensionCoverage,
+    observer: O,
 }
 
 impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
@@ -222,15 +408,148 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         crc_calculator: C,
         mandatory_extension_manager: MHEM,
     ) -> Decapsulator<T, C, MHEM> {
-        let decapsulator: Decapsulator<T, C, MHEM> = Decapsulator {
+        Decapsulator::with_observer(
+            memory,
+            crc_calculator,
+            mandatory_extension_manager,
+            NoDecapObserver {},
+        )
+    }
+}
+
+impl<
+        T: GseDecapMemory,
+        C: CrcCalculator,
+        MHEM: MandatoryHeaderExtensionManager,
+        O: DecapObserver,
+    > Decapsulator<T, C, MHEM, O>
+{
+    /// Create a decapsulator notifying `observer` of the events of the decapsulation
+    pub fn with_observer(
+        memory: T,
+        crc_calculator: C,
+        mandatory_extension_manager: MHEM,
+        observer: O,
+    ) -> Decapsulator<T, C, MHEM, O> {
+        let decapsulator: Decapsulator<T, C, MHEM, O> = Decapsulator {
             last_label: None,
+            stream: 0,
+            stream_last_labels: [None; 256],
             memory,
             crc_calculator,
             mandatory_extension_manager,
+            label_filter: None,
+            filtered_frag_ids: BTreeSet::new(),
+            stats: None,
+            extension_coverage: ExtensionCoverage::Standard,
+            observer,
         };
         decapsulator
     }
 
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Set how the header extensions of the fragmented pdus are expected in the total length and the crc
+    pub fn set_extension_coverage(&mut self, coverage: ExtensionCoverage) {
+        self.extension_coverage = coverage;
+    }
+
+    pub fn extension_coverage(&self) -> ExtensionCoverage {
+        self.extension_coverage
+    }
+
+    /// Set the filter of the labels received, or remove it with `None` to keep every pdu
+    pub fn set_label_filter(&mut self, label_filter: Option<LabelFilter>) {
+        self.label_filter = label_filter;
+        self.filtered_frag_ids.clear();
+    }
+
+    pub fn label_filter(&self) -> Option<&LabelFilter> {
+        self.label_filter.as_ref()
+    }
+
+    /// Modify the filter in place, e.g. to subscribe to a new multicast label
+    pub fn label_filter_mut(&mut self) -> Option<&mut LabelFilter> {
+        self.label_filter.as_mut()
+    }
+
+    /// Return true if the pdus sent with this label are dropped by the label filter
+    fn is_filtered(&self, label: &Label) -> bool {
+        match &self.label_filter {
+            Some(label_filter) => label_filter.route(label).is_none(),
+            None => false,
+        }
+    }
+
+    /// Give the buffer of a pdu dropped back to the memory, the buffer is dropped too if the storage is already full
+    fn release_pdu(&mut self, pdu: Box<[u8]>) {
+        let _ = self.memory.provision_storage(pdu);
+    }
+
+    /// Start counting the packets and the errors, the counters already enabled are kept
+    pub fn enable_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(DecapStats::default());
+        }
+    }
+
+    /// Stop counting and drop the counters
+    pub fn disable_stats(&mut self) {
+        self.stats = None;
+    }
+
+    /// Return a snapshot of the counters, or `None` if the statistics are disabled
+    pub fn stats(&self) -> Option<DecapStats> {
+        self.stats.clone()
+    }
+
+    /// Set all the counters back to 0, if the statistics are enabled
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            *stats = DecapStats::default();
+        }
+    }
+
+    /// Count the packet read or the error returned by the decapsulation of `buffer`, and notify the observer
+    ///
+    /// `had_label` tells if a last label was set before the decapsulation.
+    fn record(&mut self, buffer: &[u8], had_label: bool, result: Result<ReadPkt, &DecapError>) {
+        if let Some(stats) = &mut self.stats {
+            match &result {
+                Err(error) => stats.count_error(error),
+                Ok(ReadPkt::Padding(len)) => stats.count_padding(*len),
+                Ok(read) => {
+                    // the header has already been read without error
+                    if let Some((_, pkt_type, label_type)) =
+                        read_gse_header(u16::from_be_bytes([buffer[0], buffer[1]]))
+                    {
+                        stats.count_pkt(pkt_type, label_type);
+                    }
+                    match read {
+                        ReadPkt::Pdus(label, protocol_type, count) => {
+                            for _ in 0..*count {
+                                stats.count_pdu(*label, *protocol_type)
+                            }
+                        }
+                        ReadPkt::Filtered => stats.count_filtered(),
+                        _ => (),
+                    }
+                }
+            }
+        }
+        match result {
+            Ok(ReadPkt::Padding(len)) => self.observer.on_padding(len),
+            Err(error) => self.observe_error(had_label, error),
+            _ => (),
+        }
+    }
+
     pub fn new_pdu(&mut self) -> Result<Box<[u8]>, DecapMemoryError> {
         self.memory.new_pdu()
     }
@@ -239,11 +558,71 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         self.memory.provision_storage(storage)
     }
 
+    /// Advance the clock of the memory by `ticks` and return the contexts dropped because they waited too long.
+    ///
+    /// It can be called with 1 at each new base band frame, or with the time elapsed since the last call.
+    pub fn tick(&mut self, ticks: usize) -> Vec<DecapContext> {
+        let expired = self.memory.tick(ticks);
+        for context in &expired {
+            self.observer.on_context_evicted(context, EvictionReason::Expired);
+        }
+        expired
+    }
+
     /// Set the last label at None, it has to be done at the begining of each new base band frame
     pub fn reset_last_label(&mut self) {
         self.last_label = None;
     }
 
+    /// Get the label re used by the next packet sent with a re use label, in the current input stream
+    pub fn last_label(&self) -> Option<Label> {
+        self.last_label
+    }
+
+    /// Set the last label of the current input stream, e.g. to resume a base band frame
+    pub fn set_last_label(&mut self, label: Option<Label>) {
+        self.last_label = label;
+    }
+
+    /// Set the input stream of the next packets, the last label is saved for each input stream
+    ///
+    /// The stream is given to the memory too, a [`StreamGseMemory`] scopes the frag ids by stream.
+    pub fn set_stream(&mut self, stream: u8) {
+        self.memory.set_stream(stream);
+        self.stream_last_labels[self.stream as usize] = self.last_label;
+        self.stream = stream;
+        self.last_label = self.stream_last_labels[stream as usize];
+    }
+
+    /// Get the current input stream
+    pub fn stream(&self) -> u8 {
+        self.stream
+    }
+
+    /// Resolve the label of a complete packet or of a first fragment and update the last label
+    ///
+    /// A re use label is replaced by the last label, which is kept, any other label becomes the last label.
+    /// On error, the last label is kept, unless it is itself a re use label.
+    fn resolve_label(&mut self, label_type: LabelType, label: Label) -> Result<Label, DecapError> {
+        if label_type != LabelType::ReUse {
+            self.last_label = Some(label);
+            return Ok(label);
+        }
+        match self.last_label {
+            None => Err(DecapError::ErrorNoLabelSaved),
+            Some(Label::Broadcast) => Err(DecapError::ErrorLabelBroadcastSaved),
+            Some(Label::ReUse) => {
+                // a re use label can not be re used, it is dropped
+                self.last_label = None;
+                Err(DecapError::ErrorLabelReUseSaved)
+            }
+            Some(last_label) => {
+                self.observer.on_label_re_use(&last_label);
+                Ok(last_label)
+            }
+        }
+    }
+
     /// GSE decapsulation of the payload from a buffer
     ///
     /// The function decap reads the buffer to extract a packet.
@@ -257,6 +636,8 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
     /// *  if the packet is an end packet, the context in the `GseDecapMemory` is taken, updated and
     ///    and returned as a file with the status `CompletedPkt`.
     /// *  if the buffer contains 0, Padding status is returned.
+    /// *  if a label filter is set and the label of the pdu is not accepted, `FilteredPkt` status is returned
+    ///    without taking a buffer from the memory.
     ///    in each case, it returns the offset of the buffer to apply.
     ///    Error cases:
     /// *  if the inputs are wrong, the function returns an error via the status
@@ -309,6 +690,24 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
     /// assert_eq!(pkt_len, exp_pkt_len);
     /// ```
     pub fn decap(&mut self, buffer: &[u8]) -> Result<(DecapStatus, usize), (DecapError, usize)> {
+        let had_label = self.last_label.is_some();
+        let result = self.decap_pkt(buffer);
+        let read = match &result {
+            Ok((status, pkt_len)) => Ok(ReadPkt::new(status, *pkt_len)),
+            Err((error, _)) => Err(error),
+        };
+        self.record(buffer, had_label, read);
+        result
+    }
+
+    /// Notify the observer if the last label has been reset by `error`
+    fn observe_error(&mut self, had_label: bool, error: &DecapError) {
+        if had_label && self.last_label.is_none() {
+            self.observer.on_label_reset(error);
+        }
+    }
+
+    fn decap_pkt(&mut self, buffer: &[u8]) -> Result<(DecapStatus, usize), (DecapError, usize)> {
         let buffer_len = buffer.len();
 
         //Check if buffer is not too small (should have a len of 3 at least to contains header)
@@ -318,9 +717,9 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         }
 
         // read gse header
-        let (gse_len, pkt_type, label_type) = if let Some(header) = read_gse_header(
-            u16::from_be_bytes(buffer[..FIXED_HEADER_LEN].try_into().unwrap()),
-        ) {
+        let (gse_len, pkt_type, label_type) = if let Some(header) =
+            read_gse_header(u16::from_be_bytes([buffer[0], buffer[1]]))
+        {
             header
         } else {
             self.last_label = None;
@@ -344,6 +743,119 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         }
     }
 
+    /// GSE decapsulation of the payload from a buffer, without copy for complete packets
+    ///
+    /// The function behaves as [`Decapsulator::decap`], except for complete packets:
+    /// the pdu is returned as a slice of `buffer` and no memory buffer is taken in the `GseDecapMemory`.
+    /// The memory is only used to reassemble the fragments, the pdu is then returned with the status `ReassembledPkt`.
+    ///
+    /// # Example of decapsulating a complete packet without copy
+    ///
+    /// ```
+    /// use dvb_gse_rust::gse_decap::{Decapsulator, DecapBorrowedStatus, SimpleGseMemory, GseDecapMemory};
+    /// use dvb_gse_rust::label::Label;
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::header_extension::SimpleMandatoryExtensionHeaderManager;
+    ///
+    /// // Complete packet, label broadcast
+    /// let buffer = [0xE0, 6, 0xFF, 0xFF, b'a', b'b', b'c', b'd'];
+    ///
+    /// // The memory is not provisioned, it is only required by fragmented pdus
+    /// let memory = SimpleGseMemory::new(0, 0, 0, 0);
+    /// let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, SimpleMandatoryExtensionHeaderManager {});
+    ///
+    /// let (decap_status, pkt_len) = decapsulator.decap_borrowed(&buffer).unwrap();
+    /// let DecapBorrowedStatus::CompletedPkt(pdu, metadata) = decap_status else { unreachable!() };
+    /// assert_eq!(pdu, b"abcd");
+    /// assert_eq!(metadata.label(), Label::Broadcast);
+    /// assert_eq!(pkt_len, 8);
+    /// ```
+    pub fn decap_borrowed<'a>(
+        &mut self,
+        buffer: &'a [u8],
+    ) -> Result<(DecapBorrowedStatus<'a>, usize), (DecapError, usize)> {
+        if buffer.len() >= FIXED_HEADER_LEN {
+            if let Some((gse_len, PktType::CompletePkt, label_type)) =
+                read_gse_header(u16::from_be_bytes([buffer[0], buffer[1]]))
+            {
+                let pkt_len = gse_len + FIXED_HEADER_LEN;
+                if buffer.len() >= pkt_len {
+                    let had_label = self.last_label.is_some();
+                    let read = self.read_complete(buffer, label_type, pkt_len, gse_len);
+                    let filtered =
+                        matches!(&read, Ok((_, metadata, _)) if self.is_filtered(&metadata.label));
+                    let read_pkt = match &read {
+                        Ok(_) if filtered => Ok(ReadPkt::Filtered),
+                        Ok((_, metadata, pdus)) => Ok(ReadPkt::Pdus(
+                            metadata.label,
+                            metadata.protocol_type,
+                            pdus.as_ref().map_or(1, Vec::len),
+                        )),
+                        Err((error, _)) => Err(error),
+                    };
+                    self.record(buffer, had_label, read_pkt);
+                    let (offset, metadata, pdus) = read?;
+                    if filtered {
+                        return Ok((DecapBorrowedStatus::FilteredPkt, pkt_len));
+                    }
+                    let pdu = &buffer[offset..offset + metadata.pdu_len];
+                    let status = match pdus {
+                        Some(pdus) => DecapBorrowedStatus::ConcatenatedPkt(
+                            pdus.into_iter().map(|range| &pdu[range]).collect(),
+                            metadata,
+                        ),
+                        None => DecapBorrowedStatus::CompletedPkt(pdu, metadata),
+                    };
+                    return Ok((status, pkt_len));
+                }
+            }
+        }
+
+        // the other packets are handled by the memory
+        let (status, pkt_len) = self.decap(buffer)?;
+        Ok((status.into(), pkt_len))
+    }
+
+    /// GSE decapsulation of all the packets of a base band frame data field
+    ///
+    /// The re use label is reset, then the returned iterator decapsulates the packets of `buffer` one after the other
+    /// and yields the status or the error of each packet in order, as [`Decapsulator::decap`] would.
+    /// The iteration stops at the end of the buffer, at the first padding, or after an error whose offset
+    /// covers the rest of the buffer (the start of the next packet is then undefined).
+    ///
+    /// # Example of decapsulating a base band frame
+    ///
+    /// ```
+    /// use dvb_gse_rust::gse_decap::{Decapsulator, DecapStatus, SimpleGseMemory, GseDecapMemory};
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::header_extension::SimpleMandatoryExtensionHeaderManager;
+    ///
+    /// // Two complete packets with a broadcast label, followed by padding
+    /// let mut frame = [0; 100];
+    /// frame[0..8].copy_from_slice(&[0xE0, 6, 0xFF, 0xFF, b'a', b'b', b'c', b'd']);
+    /// frame[8..14].copy_from_slice(&[0xE0, 4, 0xFF, 0xFF, b'e', b'f']);
+    ///
+    /// let mut memory = SimpleGseMemory::new(2, 4, 0, 0);
+    /// memory.provision_storage(vec![0; 4].into_boxed_slice()).unwrap();
+    /// memory.provision_storage(vec![0; 4].into_boxed_slice()).unwrap();
+    /// let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, SimpleMandatoryExtensionHeaderManager {});
+    ///
+    /// let mut frame_decap = decapsulator.decap_frame(&frame);
+    /// assert!(matches!(frame_decap.next(), Some(Ok(DecapStatus::CompletedPkt(_, _)))));
+    /// assert!(matches!(frame_decap.next(), Some(Ok(DecapStatus::CompletedPkt(_, _)))));
+    /// assert_eq!(frame_decap.next(), None);
+    /// assert_eq!(frame_decap.offset(), 14);
+    /// ```
+    pub fn decap_frame<'a>(&'a mut self, buffer: &'a [u8]) -> DecapFrame<'a, T, C, MHEM, O> {
+        // new base band frame
+        self.reset_last_label();
+        DecapFrame {
+            decapsulator: self,
+            buffer,
+            offset: 0,
+        }
+    }
+
     #[inline(always)]
     fn decap_complete(
         &mut self,
@@ -352,6 +864,42 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         pkt_len: usize,
         gse_len: usize,
     ) -> Result<(DecapStatus, usize), (DecapError, usize)> {
+        let (offset, metadata, pdus) = self.read_complete(buffer, label_type, pkt_len, gse_len)?;
+        if self.is_filtered(&metadata.label) {
+            return Ok((DecapStatus::FilteredPkt, pkt_len));
+        }
+
+        // get pdu buffer
+        let mut pdu_buffer = match self.memory.new_pdu() {
+            Ok(pdu) => pdu,
+            Err(err) => {
+                return Err((DecapError::ErrorMemory(err), pkt_len));
+            }
+        };
+
+        // check buffer size
+        if pdu_buffer.len() < metadata.pdu_len {
+            self.release_pdu(pdu_buffer);
+            return Err((DecapError::ErrorSizePduBuffer, pkt_len));
+        }
+
+        // read pdu
+        pdu_buffer[..metadata.pdu_len].copy_from_slice(&buffer[offset..offset + metadata.pdu_len]);
+
+        // return status and pkt_length
+        Ok((completed_status(pdu_buffer, metadata, pdus), pkt_len))
+    }
+
+    /// Read the header of a complete packet, update the last label and return the offset of the pdu with the metadata,
+    /// and the range of each pdu in the pdu if they are concatenated
+    #[inline(always)]
+    fn read_complete(
+        &mut self,
+        buffer: &[u8],
+        label_type: LabelType,
+        pkt_len: usize,
+        gse_len: usize,
+    ) -> Result<(usize, DecapMetadata, Option<PduRanges>), (DecapError, usize)> {
         let mut offset = FIXED_HEADER_LEN;
         let buffer_len: usize = buffer.len();
         let label_len = label_type.len();
@@ -360,9 +908,16 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
 
         let mut header_ext_len: usize = 0;
 
+        // check the gse length before reading the header
+        if gse_len < label_len + PROTOCOL_LEN {
+            self.last_label = None;
+            // len_pkt = buffer_len because the label type or the gse length is wrong so the start of the next packet is undefined
+            // the entire buffer can not be proceed and should be dropped
+            return Err((DecapError::ErrorGseLength, buffer_len));
+        }
+
         // read protocol_type
-        let mut protocol_type =
-            u16::from_be_bytes(buffer[offset..offset + PROTOCOL_LEN].try_into().unwrap());
+        let mut protocol_type = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
         offset += PROTOCOL_LEN;
         if protocol_type < SECOND_RANGE_PTYPE {
             // there is atleast one header extension
@@ -381,21 +936,28 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
             return Err((DecapError::ErrorInvalidLabel, pkt_len));
         }
 
+        // update last label, the label read is kept even if the rest of the packet is wrong
+        let current_label = match self.resolve_label(label_type, label) {
+            Ok(current_label) => current_label,
+            Err(err) => return Err((err, pkt_len)),
+        };
+
         if is_there_header_ext {
             match iterate_over_extension_header(
-                &buffer[offset..],
+                &buffer[offset..pkt_len],
                 &self.mandatory_extension_manager,
                 protocol_type,
             ) {
                 Err(e) => match e {
                     ExtensionHeaderError::BufferTooSmall => {
-                        self.last_label = None;
-                        return Err((DecapError::ErrorSizePduBuffer, buffer_len));
+                        return Err((DecapError::ErrorSizePduBuffer, pkt_len));
                     }
                     ExtensionHeaderError::UnknownMandatoryHeader => {
-                        self.last_label = None;
                         return Err((DecapError::ErrorUnkownMandatoryHeader, pkt_len));
                     }
+                    ExtensionHeaderError::InvalidExtension => {
+                        return Err((DecapError::ErrorInvalidHeaderExtension, pkt_len));
+                    }
                 },
                 Ok(r) => {
                     offset += r.header_ext_len;
@@ -405,74 +967,26 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
                 }
             };
         };
-        // get pdu buffer
-        let mut pdu_buffer = match self.memory.new_pdu() {
-            Ok(pdu) => pdu,
-            Err(err) => {
-                self.last_label = None;
-                return Err((DecapError::ErrorMemory(err), pkt_len));
-            }
-        };
-
-        // check pdu buffer size
-        let pdu_buffer_len = pdu_buffer.len();
-        if gse_len < label_len + PROTOCOL_LEN {
-            self.last_label = None;
-            self.memory.provision_storage(pdu_buffer).unwrap();
-            // len_pkt = buffer_len because the label type or the gse length is wrong so the start of the next packet is undefined
-            // the entire buffer can not be proceed and should be dropped
-            return Err((DecapError::ErrorGseLength, buffer_len));
-        }
-
-        // check buffer size
-        if pdu_buffer_len + label_len + header_ext_len + PROTOCOL_LEN < gse_len {
-            self.last_label = None;
-            self.memory.provision_storage(pdu_buffer).unwrap();
-            return Err((DecapError::ErrorSizePduBuffer, pkt_len));
-        }
+        // the header extensions are bounded by the packet
         let calculed_pdu_len = gse_len - label_len - header_ext_len - PROTOCOL_LEN;
 
-        // read pdu
-        pdu_buffer[..calculed_pdu_len].copy_from_slice(&buffer[offset..offset + calculed_pdu_len]);
-
-        // update last label
-        let current_label = match label_type {
-            // read last_label
-            LabelType::ReUse => match self.last_label {
-                Some(Label::Broadcast) => {
-                    self.last_label = None;
-                    return Err((DecapError::ErrorLabelBroadcastSaved, pkt_len));
-                }
-                Some(Label::ReUse) => {
-                    self.last_label = None;
-                    return Err((DecapError::ErrorLabelReUseSaved, pkt_len));
-                }
-                None => {
-                    self.last_label = None;
-                    return Err((DecapError::ErrorNoLabelSaved, pkt_len));
-                }
-                _ => self.last_label.unwrap(),
-            },
-            // label broadcast
-            LabelType::Broadcast => {
-                self.last_label = None;
-                Label::Broadcast
-            }
-            _ => {
-                // save label
-                self.last_label = Some(label);
-                label
-            }
-        };
-
-        // return status and pkt_length
-        let metadata = DecapMetadata {
+        let mut metadata = DecapMetadata {
             pdu_len: calculed_pdu_len,
             label: current_label,
             protocol_type,
             extensions,
+            bridged_frame: None,
         };
-        Ok((DecapStatus::CompletedPkt(pdu_buffer, metadata), pkt_len))
+
+        // the pdu delivered may be a part of the pdu received, depending on its header extensions
+        match decode_pdu(
+            &self.mandatory_extension_manager,
+            &mut metadata,
+            &buffer[offset..offset + calculed_pdu_len],
+        ) {
+            Ok((range, pdus)) => Ok((offset + range.start, metadata, pdus)),
+            Err(err) => Err((err, pkt_len)),
+        }
     }
 
     pub fn get_label_or_frag_id(
@@ -556,17 +1070,6 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         let label_len = label_type.len();
         let mut extensions: Vec<Extension> = vec![];
         let mut is_there_extension_header = false;
-        // read frag id
-        let frag_id = u8::from_be_bytes(buffer[offset..offset + FRAG_ID_LEN].try_into().unwrap());
-        offset += FRAG_ID_LEN;
-
-        // read total length
-        let total_len = u16::from_be_bytes(
-            buffer[offset..offset + TOTAL_LENGTH_LEN]
-                .try_into()
-                .unwrap(),
-        );
-        offset += TOTAL_LENGTH_LEN;
 
         // check buffer size
         if gse_len < label_len + PROTOCOL_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN {
@@ -574,9 +1077,18 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
             self.last_label = None;
             return Err((DecapError::ErrorGseLength, buffer_len));
         }
+
+        // read frag id
+        let frag_id = buffer[offset];
+        offset += FRAG_ID_LEN;
+
+        // read total length
+        let total_len = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
+        offset += TOTAL_LENGTH_LEN;
+
         // read protocol_type
-        let mut protocol_type =
-            u16::from_be_bytes(buffer[offset..offset + PROTOCOL_LEN].try_into().unwrap());
+        let protocol_type_field = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
+        let mut protocol_type = protocol_type_field;
         offset += PROTOCOL_LEN;
         if protocol_type < SECOND_RANGE_PTYPE {
             // there is atleast one header extension
@@ -594,51 +1106,35 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
             return Err((DecapError::ErrorInvalidLabel, pkt_len));
         }
 
-        // update last label
-        let current_label = match label_type {
-            // read last_label
-            LabelType::ReUse => match self.last_label {
-                Some(Label::Broadcast) => {
-                    self.last_label = None;
-                    return Err((DecapError::ErrorLabelBroadcastSaved, pkt_len));
-                }
-                Some(Label::ReUse) => {
-                    self.last_label = None;
-                    return Err((DecapError::ErrorLabelReUseSaved, pkt_len));
-                }
-                None => {
-                    self.last_label = None;
-                    return Err((DecapError::ErrorNoLabelSaved, pkt_len));
-                }
-                _ => self.last_label.unwrap(),
-            },
-            // label broadcast
-            LabelType::Broadcast => {
-                self.last_label = None;
-                Label::Broadcast
-            }
-            _ => {
-                // save label
-                self.last_label = Some(label);
-                label
-            }
+        // update last label, the label read is kept even if the rest of the packet is wrong
+        let current_label = match self.resolve_label(label_type, label) {
+            Ok(current_label) => current_label,
+            Err(err) => return Err((err, pkt_len)),
         };
 
+        // drop the pdu before taking a buffer from the memory, and its next fragments
+        if self.is_filtered(&current_label) {
+            self.filtered_frag_ids.insert((self.stream, frag_id));
+            return Ok((DecapStatus::FilteredPkt, pkt_len));
+        }
+        self.filtered_frag_ids.remove(&(self.stream, frag_id));
+
         if is_there_extension_header {
             match iterate_over_extension_header(
-                &buffer[offset..],
+                &buffer[offset..pkt_len],
                 &self.mandatory_extension_manager,
                 protocol_type,
             ) {
                 Err(e) => match e {
                     ExtensionHeaderError::BufferTooSmall => {
-                        self.last_label = None;
-                        return Err((DecapError::ErrorSizePduBuffer, buffer_len));
+                        return Err((DecapError::ErrorSizePduBuffer, pkt_len));
                     }
                     ExtensionHeaderError::UnknownMandatoryHeader => {
-                        self.last_label = None;
                         return Err((DecapError::ErrorUnkownMandatoryHeader, pkt_len));
                     }
+                    ExtensionHeaderError::InvalidExtension => {
+                        return Err((DecapError::ErrorInvalidHeaderExtension, pkt_len));
+                    }
                 },
                 Ok(r) => {
                     offset += r.header_ext_len;
@@ -652,12 +1148,11 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
             gse_len - (FRAG_ID_LEN + TOTAL_LENGTH_LEN + label_len + header_ext_len + PROTOCOL_LEN);
         // check the total len
         if total_len <= calculed_pdu_len as u16 {
-            self.last_label = None;
             return Err((DecapError::ErrorTotalLength, buffer_len));
         }
 
         // create a new decap context
-        let decap_context = DecapContext::new(
+        let mut decap_context = DecapContext::new(
             current_label,
             protocol_type,
             frag_id,
@@ -667,33 +1162,53 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
             extensions.clone(),
         );
 
+        // start the crc, the label is not covered when it is re used
+        let crc_label: &[u8] = if decap_context.from_label_reuse {
+            &[]
+        } else {
+            current_label.get_bytes()
+        };
+        let crc = self.extension_coverage.init_crc32(
+            &self.crc_calculator,
+            protocol_type_field,
+            protocol_type,
+            total_len,
+            crc_label,
+            &buffer[offset - header_ext_len..offset],
+        );
+        decap_context.crc = self
+            .crc_calculator
+            .update_crc32(crc, &buffer[offset..offset + calculed_pdu_len]);
+
         // Take a new frag from memory
         let (decap_context, mut pdu_buffer) = match self.memory.new_frag(decap_context) {
             Ok(ok) => ok,
             Err(err) => {
-                self.last_label = None;
                 return Err((DecapError::ErrorMemory(err), pkt_len));
             }
         };
-
-        // read pdu
-        pdu_buffer[..calculed_pdu_len].copy_from_slice(&buffer[offset..offset + calculed_pdu_len]);
+        if let Some(replaced) = self.memory.take_replaced() {
+            self.observer.on_context_evicted(&replaced, EvictionReason::Replaced);
+        }
 
         // check pdu buffer size
-        let pdu_buffer_len = pdu_buffer.len();
-        if pdu_buffer_len + label_len + PROTOCOL_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN < gse_len {
-            self.last_label = None;
-            self.memory.provision_storage(pdu_buffer).unwrap();
+        if pdu_buffer.len() < calculed_pdu_len {
+            self.release_pdu(pdu_buffer);
             return Err((DecapError::ErrorSizePduBuffer, pkt_len));
         }
 
+        // read pdu
+        pdu_buffer[..calculed_pdu_len].copy_from_slice(&buffer[offset..offset + calculed_pdu_len]);
+
         let metadata = DecapMetadata {
             pdu_len: 0,
             protocol_type: decap_context.protocol_type,
             label: decap_context.label,
             extensions,
+            bridged_frame: None,
         };
         // save state
+        self.observer.on_context_created(&decap_context);
         match self.memory.save_frag((decap_context, pdu_buffer)) {
             Ok(_) => Ok((DecapStatus::FragmentedPkt(metadata), pkt_len)),
             Err(err) => Err((DecapError::ErrorMemory(err), pkt_len)),
@@ -710,18 +1225,22 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         let mut offset = FIXED_HEADER_LEN;
         let buffer_len = buffer.len();
 
-        let frag_id = buffer[offset];
-        offset += FRAG_ID_LEN;
-
         if gse_len <= FRAG_ID_LEN {
             self.last_label = None;
             return Err((DecapError::ErrorGseLength, buffer_len));
         }
+
+        let frag_id = buffer[offset];
+        offset += FRAG_ID_LEN;
         let calculed_pdu_len = gse_len - FRAG_ID_LEN;
 
+        if self.filtered_frag_ids.contains(&(self.stream, frag_id)) {
+            return Ok((DecapStatus::FilteredPkt, pkt_len));
+        }
+
         let (mut decap_context, mut pdu) = match self.memory.take_frag(frag_id) {
             Ok(ok) => ok,
-            Err(err) => return Err((DecapError::ErrorMemory(err), pkt_len)),
+            Err(err) => return Err((self.observe_memory_error(frag_id, err), pkt_len)),
         };
 
         let pdu_buffer = &mut pdu[decap_context.pdu_len as usize..];
@@ -729,21 +1248,26 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         let pdu_buffer_len = pdu_buffer.len();
 
         if pdu_buffer_len < calculed_pdu_len {
-            self.memory.provision_storage(pdu).unwrap();
+            self.release_pdu(pdu);
             return Err((DecapError::ErrorSizePduBuffer, pkt_len));
         }
         pdu_buffer[..calculed_pdu_len].copy_from_slice(&buffer[offset..offset + calculed_pdu_len]);
 
         // save state
         decap_context.pdu_len += calculed_pdu_len as u16;
+        decap_context.crc = self
+            .crc_calculator
+            .update_crc32(decap_context.crc, &buffer[offset..offset + calculed_pdu_len]);
 
         let metadata = DecapMetadata {
             pdu_len: 0,
             protocol_type: decap_context.protocol_type,
             label: decap_context.label,
             extensions: decap_context.extensions_header.clone(),
+            bridged_frame: None,
         };
 
+        self.observer.on_fragment_appended(&decap_context);
         match self.memory.save_frag((decap_context, pdu)) {
             Err(err) => Err((DecapError::ErrorMemory(err), pkt_len)),
             Ok(()) => Ok((DecapStatus::FragmentedPkt(metadata), pkt_len)),
@@ -759,18 +1283,23 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
     ) -> Result<(DecapStatus, usize), (DecapError, usize)> {
         let mut offset = FIXED_HEADER_LEN;
         let buffer_len = buffer.len();
-        let frag_id = buffer[offset];
-        offset += FRAG_ID_LEN;
-
         if gse_len < FRAG_ID_LEN + CRC_LEN {
             self.last_label = None;
             return Err((DecapError::ErrorSizeBuffer, buffer_len));
         }
+
+        let frag_id = buffer[offset];
+        offset += FRAG_ID_LEN;
         let calculed_pdu_len = gse_len - (FRAG_ID_LEN + CRC_LEN);
 
+        // the end of the pdu dropped frees its frag id
+        if self.filtered_frag_ids.remove(&(self.stream, frag_id)) {
+            return Ok((DecapStatus::FilteredPkt, pkt_len));
+        }
+
         let (decap_context, mut pdu) = match self.memory.take_frag(frag_id) {
             Ok(ok) => ok,
-            Err(err) => return Err((DecapError::ErrorMemory(err), pkt_len)),
+            Err(err) => return Err((self.observe_memory_error(frag_id, err), pkt_len)),
         };
 
         let pdu_buffer = &mut pdu[decap_context.pdu_len as usize..];
@@ -778,52 +1307,220 @@ impl<T: GseDecapMemory, C: CrcCalculator, MHEM: MandatoryHeaderExtensionManager>
         let pdu_buffer_len = pdu_buffer.len();
 
         if pdu_buffer_len < calculed_pdu_len {
-            self.memory.provision_storage(pdu).unwrap();
+            self.release_pdu(pdu);
             return Err((DecapError::ErrorSizePduBuffer, pkt_len));
         }
 
         pdu_buffer[..calculed_pdu_len].copy_from_slice(&buffer[offset..offset + calculed_pdu_len]);
+        let calculted_crc = self.crc_calculator.finalize_crc32(
+            self.crc_calculator
+                .update_crc32(decap_context.crc, &buffer[offset..offset + calculed_pdu_len]),
+        );
         offset += calculed_pdu_len;
 
-        let buffer_crc: [u8; 4] = buffer[offset..offset + CRC_LEN].try_into().unwrap();
-        let received_crc: u32 = u32::from_be_bytes(buffer_crc);
+        let received_crc = u32::from_be_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ]);
 
         let pdu_len = decap_context.pdu_len as usize + calculed_pdu_len;
-        let metadata = DecapMetadata {
+        let mut metadata = DecapMetadata {
             pdu_len,
             protocol_type: decap_context.protocol_type,
             label: decap_context.label,
-            extensions: decap_context.extensions_header,
+            extensions: decap_context.extensions_header.clone(),
+            bridged_frame: None,
         };
 
-        let (first_label_len, crc_label): (usize, &[u8]) = if decap_context.from_label_reuse {
-            (0, &[])
+        let first_label_len = if decap_context.from_label_reuse {
+            0
         } else {
-            (
-                decap_context.label.get_type().len(),
-                decap_context.label.get_bytes(),
-            )
+            decap_context.label.get_type().len()
         };
 
-        let total_len_received = (pdu_len + PROTOCOL_LEN + first_label_len) as u16;
+        let total_len_received = self.extension_coverage.total_len(
+            pdu_len,
+            first_label_len,
+            extensions_len(&decap_context),
+        ) as u16;
         if decap_context.total_len != total_len_received {
-            self.memory.provision_storage(pdu).unwrap();
+            self.release_pdu(pdu);
             return Err((DecapError::ErrorTotalLength, pkt_len));
         }
 
-        let calculted_crc = self.crc_calculator.calculate_crc32(
-            &pdu[..pdu_len],
-            decap_context.protocol_type,
-            decap_context.total_len,
-            crc_label,
-        );
-
         if calculted_crc != received_crc {
-            self.memory.provision_storage(pdu).unwrap();
+            self.observer.on_crc_failure(&decap_context, received_crc, calculted_crc);
+            self.release_pdu(pdu);
             return Err((DecapError::ErrorCrc, pkt_len));
         }
 
-        Ok((DecapStatus::CompletedPkt(pdu, metadata), pkt_len))
+        match decode_pdu(
+            &self.mandatory_extension_manager,
+            &mut metadata,
+            &pdu[..pdu_len],
+        ) {
+            Ok((range, pdus)) => {
+                // the pdu delivered is moved at the start of the storage
+                pdu.copy_within(range, 0);
+                Ok((completed_status(pdu, metadata, pdus), pkt_len))
+            }
+            Err(err) => {
+                self.release_pdu(pdu);
+                Err((err, pkt_len))
+            }
+        }
+    }
+
+    /// Notify the observer of the fragment discarded because its context can not be taken from the memory
+    fn observe_memory_error(&mut self, frag_id: u8, err: DecapMemoryError) -> DecapError {
+        match &err {
+            DecapMemoryError::UndefinedId => self.observer.on_unknown_frag_id(frag_id),
+            DecapMemoryError::TooManyFragments(context) => self
+                .observer
+                .on_context_evicted(context, EvictionReason::TooManyFragments),
+            _ => (),
+        }
+        DecapError::ErrorMemory(err)
+    }
+}
+
+/// Length of the header extensions read between the label and the pdu of the first fragment
+fn extensions_len(context: &DecapContext) -> usize {
+    let len: usize = context.extensions_header.iter().map(Extension::len).sum();
+    if context.protocol_type < MAX_MANDATORY_VAL_PTYPE {
+        // the final mandatory header extension replaces the protocol type
+        len.saturating_sub(PROTOCOL_LEN)
+    } else {
+        len
+    }
+}
+
+/// Decode the pdu received with the metadata, return the range of the pdu delivered,
+/// and the range of each pdu in the pdu delivered if they are concatenated
+///
+/// The range is given by the manager according to the header extensions. Then the pdus packed with the PDU-Concat extension
+/// or the TS packets carried with the TS-Concat extension are split, the TS packets being checked,
+/// or the Ethernet header of a bridged frame is read in the metadata and left out of the range. The pdu length of the metadata is updated.
+fn decode_pdu<MHEM: MandatoryHeaderExtensionManager>(
+    manager: &MHEM,
+    metadata: &mut DecapMetadata,
+    pdu: &[u8],
+) -> Result<(Range<usize>, Option<PduRanges>), DecapError> {
+    let mut range = 0..pdu.len();
+    if !metadata.extensions.is_empty() {
+        range = match manager.decode_pdu(&metadata.extensions, pdu) {
+            Ok(range) if range.start <= range.end && range.end <= pdu.len() => range,
+            _ => return Err(DecapError::ErrorInvalidHeaderExtension),
+        };
+    }
+    if pdu_concat::is_pdu_concat(&metadata.extensions) {
+        let Ok(pdus) = pdu_concat::split(&pdu[range.clone()]) else {
+            return Err(DecapError::ErrorInvalidHeaderExtension);
+        };
+        metadata.pdu_len = range.len();
+        return Ok((range, Some(pdus)));
+    }
+    if metadata.protocol_type == TS_CONCAT_PROTOCOL_ID {
+        let Ok(packets) = ts_concat::split(&pdu[range.clone()]) else {
+            return Err(DecapError::ErrorInvalidHeaderExtension);
+        };
+        metadata.pdu_len = range.len();
+        return Ok((range, Some(packets)));
+    }
+    if metadata.protocol_type == BRIDGED_FRAME_PROTOCOL_ID {
+        // the ether type replaces the protocol type of the bridged frame
+        let Some(header) = EthernetHeader::decode(&pdu[range.clone()]) else {
+            return Err(DecapError::ErrorInvalidHeaderExtension);
+        };
+        metadata.protocol_type = header.ether_type;
+        metadata.bridged_frame = Some(header);
+        range.start += ETHERNET_HEADER_LEN;
+    }
+    metadata.pdu_len = range.len();
+    Ok((range, None))
+}
+
+/// Status of a pdu completely received, the pdus concatenated are given apart
+fn completed_status(
+    pdu: Box<[u8]>,
+    metadata: DecapMetadata,
+    pdus: Option<PduRanges>,
+) -> DecapStatus {
+    match pdus {
+        Some(pdus) => DecapStatus::ConcatenatedPkt(pdu, metadata, pdus),
+        None => DecapStatus::CompletedPkt(pdu, metadata),
+    }
+}
+
+/// Iterator over the packets of a base band frame data field, returned by [`Decapsulator::decap_frame`].
+///
+/// Each item is the status of one packet, or the error returned by its decapsulation.
+pub struct DecapFrame<
+    'a,
+    T: GseDecapMemory,
+    C: CrcCalculator,
+    MHEM: MandatoryHeaderExtensionManager,
+    O: DecapObserver = NoDecapObserver,
+> {
+    decapsulator: &'a mut Decapsulator<T, C, MHEM, O>,
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+impl<
+        T: GseDecapMemory,
+        C: CrcCalculator,
+        MHEM: MandatoryHeaderExtensionManager,
+        O: DecapObserver,
+    > DecapFrame<'_, T, C, MHEM, O>
+{
+    /// Get the offset of the next packet in the buffer
+    ///
+    /// Once the iteration is over, it is the length of the data read before the padding.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<
+        T: GseDecapMemory,
+        C: CrcCalculator,
+        MHEM: MandatoryHeaderExtensionManager,
+        O: DecapObserver,
+    > Iterator for DecapFrame<'_, T, C, MHEM, O>
+{
+    type Item = Result<DecapStatus, DecapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buffer = &self.buffer[self.offset.min(self.buffer.len())..];
+        if buffer.is_empty() {
+            return None;
+        }
+
+        // a single byte left can not contain a packet, it is padding if it is 0
+        if buffer.len() < FIXED_HEADER_LEN && buffer.iter().all(|byte| *byte == 0) {
+            self.decapsulator.observer.on_padding(buffer.len());
+            self.buffer = &self.buffer[..self.offset];
+            return None;
+        }
+
+        match self.decapsulator.decap(buffer) {
+            Ok((DecapStatus::Padding, _)) => {
+                // the rest of the frame is padding
+                self.buffer = &self.buffer[..self.offset];
+                None
+            }
+            Ok((status, pkt_len)) => {
+                self.offset += pkt_len;
+                Some(Ok(status))
+            }
+            Err((err, pkt_len)) => {
+                self.offset += pkt_len;
+                Some(Err(err))
+            }
+        }
     }
 }
 
@@ -903,6 +1600,7 @@ impl GetLabelorFragIdError {
 /// # Variantss
 /// * `UnknownMandatoryHeader` - Indicates the presence of an unkown mandatory header extension.
 /// * `BufferTooSmall` - Indicates that the input buffer is too small to be a Gse Packet.
+/// * `InvalidExtension` - Indicates that an header extension can not be decoded.
 ///
 /// Enumeration DecapError
 ///
@@ -910,6 +1608,7 @@ impl GetLabelorFragIdError {
 pub enum ExtensionHeaderError {
     UnknownMandatoryHeader,
     BufferTooSmall,
+    InvalidExtension,
 }
 
 impl ExtensionHeaderError {
@@ -917,6 +1616,7 @@ impl ExtensionHeaderError {
         match self {
             Self::UnknownMandatoryHeader => "Header contains unknown Mandatory Header Extension ",
             Self::BufferTooSmall => "Buffer too small to contain the promised header extension(s)",
+            Self::InvalidExtension => "Header extension can not be decoded",
         }
     }
 }
@@ -950,57 +1650,55 @@ fn iterate_over_extension_header<MHEM: MandatoryHeaderExtensionManager>(
         // enter in at least one
         // this is an header extension
         // reading the size of the extension
-        let h_len: u8 = ((protocol_type & H_LEN_MASK) >> 8).try_into().unwrap();
-        if h_len == 0 {
+        let h_len = ((protocol_type & H_LEN_MASK) >> 8) as u8;
+        let (ext_data_len, is_final) = if h_len == 0 {
             // this is a mandatory header extension
             // if we don't know this extension, we must drop the packet
-            match mandatory_extension_header_manager.is_mandatory_header_id_known(protocol_type) {
+            match mandatory_extension_header_manager
+                .decode_mandatory_header(protocol_type, &pdu[offset..])
+            {
                 // unknown -> drop the packet
                 MandatoryHeaderExt::Unknown => {
                     return Err(ExtensionHeaderError::UnknownMandatoryHeader);
                 }
-
-                MandatoryHeaderExt::Final(size_data) => {
-                    match Extension::new(protocol_type, &pdu[offset..offset + size_data as usize]) {
-                        Ok(extension) => extensions.push(extension),
-                        Err(_) => todo!(),
-                    };
-
-                    offset += size_data as usize;
-                    break; // final ->  no more extension, neither protocol type
-                }
-
-                MandatoryHeaderExt::NonFinal(size_data) => {
-                    match Extension::new(protocol_type, &pdu[offset..offset + size_data as usize]) {
-                        Ok(extension) => extensions.push(extension),
-                        Err(_) => todo!(),
-                    };
-
-                    offset += size_data as usize;
-                }
+                // final ->  no more extension, neither protocol type
+                MandatoryHeaderExt::Final(size_data) => (size_data as usize, true),
+                MandatoryHeaderExt::NonFinal(size_data) => (size_data as usize, false),
             }
         } else {
             // this is a optionnal header extension
             // using H-LEN to determine the size of the extension DATA
-            let Ok(current_ext_data_len) = optionnal_extension_data_size_from_hlen(h_len) else {
-                unreachable!()
-            };
-            // H-LEN = 0 <=> mandatory header extension, case already managed
-            // H-LEN > 5 <=> protocol type > SECOND_RANGE_PTYPE, unreachable
+            // H-LEN > 5 <=> protocol type > SECOND_RANGE_PTYPE, excluded by the loop condition
+            match optionnal_extension_data_size_from_hlen(h_len) {
+                Ok(size_data) => (size_data, false),
+                Err(_) => return Err(ExtensionHeaderError::InvalidExtension),
+            }
+        };
 
-            let current_ext = Extension::new(
-                protocol_type,
-                &pdu[offset..offset + current_ext_data_len],
-            );
+        let Some(ext_data) = pdu.get(offset..offset + ext_data_len) else {
+            return Err(ExtensionHeaderError::BufferTooSmall);
+        };
+        let Ok(extension) = Extension::new(protocol_type, ext_data) else {
+            return Err(ExtensionHeaderError::InvalidExtension);
+        };
+        if mandatory_extension_header_manager
+            .check_extension(&extension)
+            .is_err()
+        {
+            return Err(ExtensionHeaderError::InvalidExtension);
+        }
+        extensions.push(extension);
+        offset += ext_data_len;
 
-            match current_ext {
-                Ok(extension) => extensions.push(extension),
-                Err(_) => todo!(),
-            }
-            offset += current_ext_data_len;
+        if is_final {
+            break;
         }
+
         // reading protocol type for next iteration
-        protocol_type = u16::from_be_bytes(pdu[offset..offset + PROTOCOL_LEN].try_into().unwrap());
+        let Some(next_type) = pdu.get(offset..offset + PROTOCOL_LEN) else {
+            return Err(ExtensionHeaderError::BufferTooSmall);
+        };
+        protocol_type = u16::from_be_bytes([next_type[0], next_type[1]]);
         offset += PROTOCOL_LEN;
     }
     Ok(IterateOverExtensionHeaderStatus {
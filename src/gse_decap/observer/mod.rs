@@ -0,0 +1,60 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the observation of the decapsulation
+//!
+//! The status returned by [`Decapsulator::decap`](super::Decapsulator::decap) does not tell why a fragment has been discarded.
+//! A [`DecapObserver`] given to the decapsulator is notified of the life of the contexts of fragmentation,
+//! of the crc failures, of the resolution and the reset of the re use label, and of the padding.
+//!
+//! All the methods of the trait do nothing by default, so that only the events of interest have to be implemented.
+//! The default observer [`NoDecapObserver`] ignores all the events, its calls are removed by the compiler.
+
+#[cfg(test)]
+mod tests;
+
+use crate::gse_decap::{DecapContext, DecapError};
+use crate::label::Label;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Reason of the drop of a context before the end of its pdu
+pub enum EvictionReason {
+    /// A first fragment has been received with the same frag id, or with a frag id stored at the same place in the memory
+    Replaced,
+    /// The context has been waiting for more than the maximum delay of the memory
+    Expired,
+    /// The pdu has been received in more fragments than allowed by the memory
+    TooManyFragments,
+}
+
+/// Trait defining the events of the decapsulation notified to the user.
+pub trait DecapObserver {
+    /// A first fragment has been received, its context is saved in the memory
+    fn on_context_created(&mut self, _context: &DecapContext) {}
+
+    /// An intermediate fragment has been appended to its context
+    fn on_fragment_appended(&mut self, _context: &DecapContext) {}
+
+    /// A context has been dropped before the end of its pdu
+    fn on_context_evicted(&mut self, _context: &DecapContext, _reason: EvictionReason) {}
+
+    /// An intermediate or end fragment has been received but no context is saved with its frag id, the fragment is discarded
+    fn on_unknown_frag_id(&mut self, _frag_id: u8) {}
+
+    /// The crc computed over the pdu reassembled doesn't correspond to the crc received, the pdu is discarded
+    fn on_crc_failure(&mut self, _context: &DecapContext, _received_crc: u32, _computed_crc: u32) {}
+
+    /// A re use label has been resolved to the last label of the base band frame
+    fn on_label_re_use(&mut self, _label: &Label) {}
+
+    /// The last label has been reset because of this error, the next re use labels of the base band frame can not be resolved
+    fn on_label_reset(&mut self, _error: &DecapError) {}
+
+    /// Padding has been detected, the `len` bytes left in the buffer are skipped
+    fn on_padding(&mut self, _len: usize) {}
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+/// Implementation of the trait [`DecapObserver`] ignoring all the events, used by default by the decapsulator
+pub struct NoDecapObserver {}
+impl DecapObserver for NoDecapObserver {}
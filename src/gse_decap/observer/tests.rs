@@ -0,0 +1,281 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::DefaultCrc;
+use crate::gse_decap::{
+    DecapContext, DecapError, DecapMemoryError, DecapObserver, DecapStatus, Decapsulator,
+    EvictionReason, GseDecapMemory, SimpleGseMemory,
+};
+use crate::gse_encap::{ContextFrag, EncapMetadata, EncapStatus, Encapsulator};
+use crate::header_extension::SimpleMandatoryExtensionHeaderManager;
+use crate::label::Label;
+
+const PDU: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+const LABEL: Label = Label::SixBytesLabel(*b"012345");
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Event {
+    Created(u8),
+    Appended(u8, u16),
+    Evicted(u8, EvictionReason),
+    UnknownFragId(u8),
+    CrcFailure(u8),
+    LabelReUse(Label),
+    LabelReset(DecapError),
+    Padding(usize),
+}
+
+#[derive(Debug, Default)]
+struct RecordObserver {
+    events: Vec<Event>,
+}
+
+impl DecapObserver for RecordObserver {
+    fn on_context_created(&mut self, context: &DecapContext) {
+        self.events.push(Event::Created(context.frag_id));
+    }
+
+    fn on_fragment_appended(&mut self, context: &DecapContext) {
+        self.events
+            .push(Event::Appended(context.frag_id, context.pdu_len));
+    }
+
+    fn on_context_evicted(&mut self, context: &DecapContext, reason: EvictionReason) {
+        self.events.push(Event::Evicted(context.frag_id, reason));
+    }
+
+    fn on_unknown_frag_id(&mut self, frag_id: u8) {
+        self.events.push(Event::UnknownFragId(frag_id));
+    }
+
+    fn on_crc_failure(&mut self, context: &DecapContext, _received_crc: u32, _computed_crc: u32) {
+        self.events.push(Event::CrcFailure(context.frag_id));
+    }
+
+    fn on_label_re_use(&mut self, label: &Label) {
+        self.events.push(Event::LabelReUse(*label));
+    }
+
+    fn on_label_reset(&mut self, error: &DecapError) {
+        self.events.push(Event::LabelReset(error.clone()));
+    }
+
+    fn on_padding(&mut self, len: usize) {
+        self.events.push(Event::Padding(len));
+    }
+}
+
+fn create_decapsulator(
+    max_frag_id: usize,
+    max_delay: usize,
+    max_pdu_frag: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager, RecordObserver>
+{
+    let mut memory = SimpleGseMemory::new(max_frag_id, PDU.len(), max_delay, max_pdu_frag);
+    for _ in 0..max_frag_id + 1 {
+        memory
+            .provision_storage(vec![0; PDU.len()].into_boxed_slice())
+            .unwrap();
+    }
+    Decapsulator::with_observer(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+        RecordObserver::default(),
+    )
+}
+
+/// Encapsulate the first fragment of the pdu in `buffer`, return its length and the context
+fn encap_first(frag_id: u8, buffer: &mut [u8]) -> (usize, ContextFrag) {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let metadata = EncapMetadata::new(0x1234, LABEL);
+    let Ok(EncapStatus::FragmentedPkt(len, context)) =
+        encapsulator.encap(PDU, frag_id, metadata, buffer)
+    else {
+        panic!()
+    };
+    (len as usize, context)
+}
+
+/// Encapsulate the next fragment of the pdu in `buffer`, return its length and the context if it is not the end
+fn encap_next(context: &ContextFrag, buffer: &mut [u8]) -> (usize, Option<ContextFrag>) {
+    let encapsulator = Encapsulator::new(DefaultCrc {});
+    match encapsulator.encap_frag(PDU, context, buffer) {
+        Ok(EncapStatus::FragmentedPkt(len, context)) => (len as usize, Some(context)),
+        Ok(EncapStatus::CompletedPkt(len)) => (len as usize, None),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_observer_001() {
+    let comment = "context created, fragment appended, pdu completed";
+    let mut decapsulator = create_decapsulator(1, 0, 0);
+    let mut buffer = [0; 100];
+
+    let (first_len, context) = encap_first(3, &mut buffer[..20]);
+    let (intermediate_len, context) = encap_next(&context, &mut buffer[first_len..first_len + 10]);
+    let offset = first_len + intermediate_len;
+    let (end_len, _) = encap_next(&context.unwrap(), &mut buffer[offset..]);
+
+    decapsulator.decap(&buffer[..first_len]).unwrap();
+    decapsulator.decap(&buffer[first_len..offset]).unwrap();
+    let (status, _) = decapsulator
+        .decap(&buffer[offset..offset + end_len])
+        .unwrap();
+
+    assert!(
+        matches!(status, DecapStatus::CompletedPkt(_, _)),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        vec![Event::Created(3), Event::Appended(3, 14)],
+        decapsulator.observer().events,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_observer_002() {
+    let comment = "context replaced by a first fragment stored at the same place";
+    let mut decapsulator = create_decapsulator(1, 0, 0);
+    let mut buffer = [0; 20];
+
+    let (len, _) = encap_first(3, &mut buffer);
+    decapsulator.decap(&buffer[..len]).unwrap();
+    let (len, _) = encap_first(4, &mut buffer);
+    decapsulator.decap(&buffer[..len]).unwrap();
+
+    assert_eq!(
+        vec![
+            Event::Created(3),
+            Event::Evicted(3, EvictionReason::Replaced),
+            Event::Created(4)
+        ],
+        decapsulator.observer().events,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_observer_003() {
+    let comment = "fragment with an unknown frag id";
+    let mut decapsulator = create_decapsulator(1, 0, 0);
+    let mut buffer = [0; 100];
+
+    let (first_len, context) = encap_first(3, &mut buffer[..20]);
+    let (end_len, _) = encap_next(&context, &mut buffer[first_len..]);
+
+    let result = decapsulator.decap(&buffer[first_len..first_len + end_len]);
+
+    assert_eq!(
+        Err((
+            DecapError::ErrorMemory(DecapMemoryError::UndefinedId),
+            end_len
+        )),
+        result,
+        "{}",
+        comment
+    );
+    assert_eq!(
+        vec![Event::UnknownFragId(3)],
+        decapsulator.observer().events,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_observer_004() {
+    let comment = "crc failure of the reassembled pdu";
+    let mut decapsulator = create_decapsulator(1, 0, 0);
+    let mut buffer = [0; 100];
+
+    let (first_len, context) = encap_first(3, &mut buffer[..20]);
+    let (end_len, _) = encap_next(&context, &mut buffer[first_len..]);
+    // corrupt the last byte of the crc
+    buffer[first_len + end_len - 1] ^= 0xFF;
+
+    decapsulator.decap(&buffer[..first_len]).unwrap();
+    let result = decapsulator.decap(&buffer[first_len..first_len + end_len]);
+
+    assert_eq!(Err((DecapError::ErrorCrc, end_len)), result, "{}", comment);
+    assert_eq!(
+        vec![Event::Created(3), Event::CrcFailure(3)],
+        decapsulator.observer().events,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_observer_005() {
+    let comment = "context expired and context with too many fragments";
+    let mut decapsulator = create_decapsulator(2, 1, 2);
+    let mut buffer = [0; 100];
+
+    let (first_len, context) = encap_first(0, &mut buffer[..20]);
+    let (intermediate_len, _) = encap_next(&context, &mut buffer[first_len..first_len + 5]);
+    let offset = first_len + intermediate_len;
+    decapsulator.decap(&buffer[..first_len]).unwrap();
+    decapsulator.decap(&buffer[first_len..offset]).unwrap();
+    decapsulator.decap(&buffer[first_len..offset]).unwrap_err();
+
+    let (first_len, _) = encap_first(1, &mut buffer[..20]);
+    decapsulator.decap(&buffer[..first_len]).unwrap();
+    let expired = decapsulator.tick(2);
+
+    assert_eq!(1, expired.len(), "{}", comment);
+    assert_eq!(
+        vec![
+            Event::Created(0),
+            Event::Appended(0, 9),
+            Event::Evicted(0, EvictionReason::TooManyFragments),
+            Event::Created(1),
+            Event::Evicted(1, EvictionReason::Expired),
+        ],
+        decapsulator.observer().events,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_observer_006() {
+    let comment = "label re used, reset by an error, then padding";
+    let mut decapsulator = create_decapsulator(1, 0, 0);
+
+    // complete packet with a 3 bytes label, then a complete packet re using it
+    let mut buffer = vec![
+        0xD0, 9, 0xFF, 0xFF, b'a', b'b', b'c', b'd', b'e', b'f', b'g',
+    ];
+    buffer.extend_from_slice(&[0xF0, 4, 0xFF, 0xFF, b'h', b'i']);
+    // complete packet with the invalid label 0, then padding
+    buffer.extend_from_slice(&[0xC0, 9, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, b'j']);
+    buffer.extend_from_slice(&[0; 5]);
+
+    let mut offset = 0;
+    for _ in 0..2 {
+        let (_, pkt_len) = decapsulator.decap(&buffer[offset..]).unwrap();
+        offset += pkt_len;
+    }
+    let (error, pkt_len) = decapsulator.decap(&buffer[offset..]).unwrap_err();
+    offset += pkt_len;
+    let (status, _) = decapsulator.decap(&buffer[offset..]).unwrap();
+
+    assert_eq!(DecapError::ErrorInvalidLabel, error, "{}", comment);
+    assert_eq!(DecapStatus::Padding, status, "{}", comment);
+    assert_eq!(
+        vec![
+            Event::LabelReUse(Label::ThreeBytesLabel(*b"abc")),
+            Event::LabelReset(DecapError::ErrorInvalidLabel),
+            Event::Padding(5),
+        ],
+        decapsulator.observer().events,
+        "{}",
+        comment
+    );
+}
@@ -1,19 +1,25 @@
 // Copyright 2023, Viveris Technologies
 // Distributed under the terms of the MIT License
 
+use std::ops::Range;
 use std::vec;
 
 use crate::crc::{CrcCalculator, DefaultCrc};
 use crate::gse_decap::{
-    read_gse_header, DecapContext, DecapError, DecapMetadata, DecapStatus, Decapsulator, GetLabelorFragIdError, LabelorFragId
+    read_gse_header, DecapBorrowedStatus, DecapContext, DecapError, DecapMetadata, DecapStatus, Decapsulator, GetLabelorFragIdError, LabelorFragId
 };
+use crate::gse_encap::{EncapMetadata, EncapStatus, Encapsulator};
 use crate::gse_standard::{
     COMPLETE_PKT, CRC_LEN, END_PKT, FIRST_PKT, FIXED_HEADER_LEN, FRAG_ID_LEN, INTERMEDIATE_PKT,
     LABEL_3_B, LABEL_3_B_LEN, LABEL_6_B, LABEL_6_B_LEN, LABEL_BROADCAST, LABEL_BROADCAST_LEN,
-    LABEL_REUSE, LABEL_REUSE_LEN, PROTOCOL_LEN, TOTAL_LENGTH_LEN,
+    LABEL_REUSE, LABEL_REUSE_LEN, NCR_PROTOCOL_ID, PROTOCOL_LEN, TOTAL_LENGTH_LEN,
 };
 use crate::header_extension::{
+    BridgedFrameHandler, BridgedFrameMandatoryExtensionHeaderManager, EthernetHeader, Extension,
+    ExtensionCoverage, ExtensionData, ExtensionHandler, ExtensionHandlerError, ExtensionRegistry,
+    MandatoryHeaderExt, MandatoryHeaderExtensionManager, PduConcatHandler,
     SignalisationMandatoryExtensionHeaderManager, SimpleMandatoryExtensionHeaderManager,
+    TsConcatHandler, TsConcatMandatoryExtensionHeaderManager,
 };
 use crate::label::{Label, LabelType};
 use crate::pkt_type::PktType;
@@ -21,7 +27,9 @@ use crate::utils::{
     GseCompletePacket, GseEndFragPacket, GseFirstFragPacket, GseIntermediatePacket, Serialisable,
 };
 
-use crate::gse_decap::gse_decap_memory::{DecapMemoryError, GseDecapMemory, SimpleGseMemory};
+use crate::gse_decap::gse_decap_memory::{
+    DecapMemoryError, GseDecapMemory, SimpleGseMemory, StreamGseMemory,
+};
 
 /// Read_gse_header tests
 ///
@@ -327,7 +335,7 @@ fn create_decapsulator_signalisation(
 macro_rules! test_decap_complete {
     ($comment:expr,  $buffer:expr,  $packet:expr,  $decapsulator:expr,  $exp_decapsulator:expr, $exp_pkt_len:expr,  $exp_status:expr) => {
         // Feed the buffer
-        $packet.generate(&mut $buffer);
+        $packet.generate(&mut $buffer).unwrap();
         println!("{:?}", &$buffer);
 
         // Use the decap function
@@ -374,6 +382,7 @@ fn test_decap_complete_001() {
             pdu_len: PDU_LEN,
             label: Label::SixBytesLabel(*b"012345"),
             protocol_type: 0xFFFF,
+            bridged_frame: None,
         },
     ));
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
@@ -417,6 +426,7 @@ fn test_decap_complete_002() {
             pdu_len: PDU_LEN,
             label: Label::ThreeBytesLabel(*b"012"),
             protocol_type: 0xF0F0,
+            bridged_frame: None,
         },
     ));
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
@@ -511,10 +521,11 @@ fn test_decap_complete_005() {
             pdu_len: PDU_LEN,
             label: Label::Broadcast,
             protocol_type: 0x0F0F,
+            bridged_frame: None,
         },
     ));
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::Broadcast);
 
     test_decap_complete!(
         comment,
@@ -554,6 +565,7 @@ fn test_decap_complete_006() {
             pdu_len: PDU_LEN,
             label: Label::ThreeBytesLabel(*b"012"),
             protocol_type: 0xF0F0,
+            bridged_frame: None,
         },
     ));
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
@@ -597,6 +609,7 @@ fn test_decap_complete_007() {
             pdu_len: PDU_LEN,
             label: Label::SixBytesLabel(*b"012345"),
             protocol_type: 0xF0F0,
+            bridged_frame: None,
         },
     ));
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
@@ -634,7 +647,7 @@ fn test_decap_complete_008() {
     let exp_pkt_len = PKT_LEN;
     let exp_status = Err(DecapError::ErrorLabelBroadcastSaved);
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::Broadcast);
 
     test_decap_complete!(
         comment,
@@ -800,7 +813,7 @@ fn test_decap_complete_013() {
         Label::SixBytesLabel([0, 0, 0, 0, 0, 0]),
         b"abcdefghijklmnopqrstuvwxyz",
     );
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = Some(Label::ThreeBytesLabel(*b"012"));
 
@@ -837,7 +850,7 @@ fn test_decap_complete_014() {
     let exp_pkt_len = PKT_LEN;
     let exp_status = Err(DecapError::ErrorSizePduBuffer);
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::SixBytesLabel(*b"012345"));
 
     test_decap_complete!(
         comment,
@@ -872,7 +885,7 @@ fn test_decap_complete_015() {
     let exp_pkt_len = PKT_LEN;
     let exp_status = Err(DecapError::ErrorUnkownMandatoryHeader);
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::SixBytesLabel(*b"012345"));
 
     test_decap_complete!(
         comment,
@@ -887,7 +900,7 @@ fn test_decap_complete_015() {
 
 macro_rules! test_decap_frag {
     ($comment: expr, $buffer: expr, $decapsulator: expr, $exp_decapsulator: expr, $frag: expr, $frag_id: expr, $exp_decap_status: expr, $exp_pkt_len: expr, $exp_pdu: expr) => {
-        $frag.generate(&mut $buffer);
+        $frag.generate(&mut $buffer).unwrap();
         let (obs_decap_status, obs_pkt_len) = match $decapsulator.decap(&$buffer) {
             Ok((status, len)) => (Ok(status), len),
             Err((status, len)) => (Err(status), len),
@@ -943,6 +956,7 @@ fn test_decap_first_001() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
 
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
@@ -990,6 +1004,7 @@ fn test_decap_first_002() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
@@ -1073,7 +1088,7 @@ fn test_decap_first_004() {
     let exp_pkt_len = PKT_LEN;
     let exp_pdu: [u8; 26] = [0; 26];
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::ThreeBytesLabel(*b"012"));
 
     test_decap_frag!(
         comment,
@@ -1107,7 +1122,7 @@ fn test_decap_first_005() {
     let label = Label::SixBytesLabel([0, 0, 0, 0, 0, 0]);
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
 
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
     exp_decapsulator.last_label = None;
@@ -1166,6 +1181,7 @@ fn test_decap_first_006() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
@@ -1212,12 +1228,13 @@ fn test_decap_first_007() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
     let exp_pdu = pdu;
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::Broadcast);
 
     test_decap_frag!(
         comment,
@@ -1250,7 +1267,7 @@ fn test_decap_first_008() {
     let label = Label::SixBytesLabel([0, 1, 2, 3, 4, 5]);
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     if let Err((obs_status, obs_pkt_len)) = decapsulator.decap(&buffer) {
         let exp_decap_status = DecapError::ErrorUnkownMandatoryHeader;
         let exp_pkt_len = PKT_LEN;
@@ -1287,6 +1304,7 @@ fn test_decap_first_009() {
         pdu_len: 0,
         protocol_type,
         label: Label::SixBytesLabel(*b"012345"),
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
@@ -1334,6 +1352,7 @@ fn test_decap_first_010() {
         pdu_len: 0,
         protocol_type,
         label: Label::ThreeBytesLabel(*b"012"),
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
@@ -1379,7 +1398,7 @@ fn test_decap_first_011() {
     let exp_pkt_len = PKT_LEN;
     let exp_pdu: [u8; 26] = [0; 26];
     let mut exp_decapsulator = create_decapsulator(1, PDU_LEN);
-    exp_decapsulator.last_label = None;
+    exp_decapsulator.last_label = Some(Label::Broadcast);
 
     test_decap_frag!(
         comment,
@@ -1515,6 +1534,7 @@ fn test_decap_intermediate_001() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = FIXED_HEADER_LEN + gse_len as usize;
@@ -1576,6 +1596,7 @@ fn test_decap_intermediate_002() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
@@ -1636,6 +1657,7 @@ fn test_decap_intermediate_003() {
         pdu_len: 0,
         protocol_type,
         label,
+        bridged_frame: None,
     };
     let exp_decap_status = Ok(DecapStatus::FragmentedPkt(exp_metadata));
     let exp_pkt_len = PKT_LEN;
@@ -1767,7 +1789,7 @@ fn test_decap_end_001() {
     let total_len = (pdu.len() + PROTOCOL_LEN + label.len()) as u16;
 
     let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
-    let context = DecapContext::new(
+    let mut context = DecapContext::new(
         label,
         protocol_type,
         frag_id,
@@ -1776,6 +1798,8 @@ fn test_decap_end_001() {
         false,
         vec![],
     );
+    // no fragment received yet
+    context.crc = DefaultCrc {}.init_crc32(protocol_type, total_len, label.get_bytes());
     let frag = GseEndFragPacket::new(GSE_LEN as u16, frag_id, &pdu, crc);
 
     let state = decapsulator.memory.new_frag(context).unwrap();
@@ -1787,6 +1811,7 @@ fn test_decap_end_001() {
         label,
         pdu_len: pdu.len(),
         protocol_type,
+        bridged_frame: None,
     };
 
     let exp_pdu = pdu;
@@ -1830,7 +1855,7 @@ fn test_decap_end_002() {
     let total_len = (pdu.len() + PROTOCOL_LEN + label.len()) as u16;
 
     let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
-    let context = DecapContext::new(
+    let mut context = DecapContext::new(
         label,
         protocol_type,
         frag_id,
@@ -1839,6 +1864,8 @@ fn test_decap_end_002() {
         false,
         vec![],
     );
+    // no fragment received yet
+    context.crc = DefaultCrc {}.init_crc32(protocol_type, total_len, label.get_bytes());
     let frag = GseEndFragPacket::new(GSE_LEN as u16, frag_id, &pdu, crc);
 
     let mut state = decapsulator.memory.new_frag(context).unwrap();
@@ -1851,6 +1878,7 @@ fn test_decap_end_002() {
         label,
         pdu_len: pdu.len(),
         protocol_type,
+        bridged_frame: None,
     };
 
     let exp_pdu = *b"abcdefghijklmnopqrstuvwxyz-";
@@ -1892,7 +1920,7 @@ fn test_decap_end_003() {
     let total_len = (pdu.len() + PROTOCOL_LEN + label.len()) as u16;
 
     let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
-    let context = DecapContext::new(
+    let mut context = DecapContext::new(
         label,
         protocol_type,
         frag_id,
@@ -1901,6 +1929,8 @@ fn test_decap_end_003() {
         false,
         vec![],
     );
+    // no fragment received yet
+    context.crc = DefaultCrc {}.init_crc32(protocol_type, total_len, label.get_bytes());
     let frag = GseEndFragPacket::new(GSE_LEN as u16, frag_id, &pdu, crc);
 
     let state = decapsulator.memory.new_frag(context).unwrap();
@@ -1912,6 +1942,7 @@ fn test_decap_end_003() {
         label,
         pdu_len: pdu.len(),
         protocol_type,
+        bridged_frame: None,
     };
 
     let exp_pdu = pdu;
@@ -2062,7 +2093,7 @@ fn test_decap_end_006() {
     let total_len = (pdu.len() + PROTOCOL_LEN + label.len()) as u16;
 
     let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
-    let context = DecapContext::new(
+    let mut context = DecapContext::new(
         label,
         protocol_type,
         frag_id,
@@ -2071,6 +2102,8 @@ fn test_decap_end_006() {
         false,
         vec![],
     );
+    // no fragment received yet
+    context.crc = DefaultCrc {}.init_crc32(protocol_type, total_len, label.get_bytes());
     let frag = GseEndFragPacket::new(GSE_LEN as u16, frag_id, &pdu, crc);
 
     let state = decapsulator.memory.new_frag(context).unwrap();
@@ -2119,7 +2152,7 @@ fn test_decap_end_007() {
 
     let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
     let frag = GseEndFragPacket::new(GSE_LEN as u16, frag_id, &pdu, crc);
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
 
     if let Err((obs_decap_status, obs_pkt_len)) = decapsulator.decap(&buffer) {
         let (exp_decap_status, exp_pkt_len) = (
@@ -2345,7 +2378,7 @@ fn test_get_label_or_frag_id_001() {
         Label::SixBytesLabel(*b"012345"),
         b"abcdefghijklmnopqrstuvwxyz",
     );
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2375,7 +2408,7 @@ fn test_get_label_or_frag_id_002() {
         Label::ThreeBytesLabel(*b"GGG"),
         b"abcdefghijklmnopqrstuvwxyz",
     );
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2406,7 +2439,7 @@ fn test_get_label_or_frag_id_003() {
         Label::ReUse,
         b"abcdefghijklmnopqrstuvwxyz",
     );
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2435,7 +2468,7 @@ fn test_get_label_or_frag_id_004() {
         Label::Broadcast,
         b"abcdefghijklmnopqrstuvwxyz",
     );
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2466,7 +2499,7 @@ fn test_get_label_or_frag_id_005() {
         b"abcdefghijklmnopqrstuvwxyz",
     );
 
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2510,7 +2543,7 @@ fn test_get_label_or_frag_id_006() {
     //  PP :     2B PROTOCOL TYPE
     //  LLL: 3B of label data
     //  TOTAL =  7 Bytes min
-    packet.generate(&mut buffer);
+    packet.generate(&mut buffer).unwrap();
     let mut decapsulator = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2543,7 +2576,7 @@ fn test_get_label_or_frag_id_007() {
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2575,7 +2608,7 @@ fn test_get_label_or_frag_id_008() {
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2607,7 +2640,7 @@ fn test_get_label_or_frag_id_009() {
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2639,7 +2672,7 @@ fn test_get_label_or_frag_id_010() {
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2671,7 +2704,7 @@ fn test_get_label_or_frag_id_011() {
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2713,7 +2746,7 @@ fn test_get_label_or_frag_id_012() {
     let pdu = *b"abcdefghijklmnopqrstuvwxyz";
     let frag = GseFirstFragPacket::new(gse_len, frag_id, total_length, protocol_type, label, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
 
@@ -2753,7 +2786,7 @@ fn test_get_label_or_frag_id_013() {
     let frag_id = 12;
     let frag = GseIntermediatePacket::new(gse_len, frag_id, &pdu);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
     let status = decapsulator.get_label_or_frag_id(&buffer);
@@ -2789,7 +2822,7 @@ fn test_get_label_or_frag_id_014() {
     //  F :     1 B Frag Id
     //  TOTAL =  3 Bytes min
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     buffer_too_small.copy_from_slice(&buffer[..TOO_SMALL_SIZE]);
 
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
@@ -2825,7 +2858,7 @@ fn test_get_label_or_frag_id_015() {
     let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
     let frag = GseEndFragPacket::new(GSE_LEN as u16, frag_id, &pdu,crc);
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
     decapsulator.last_label = None;
     let status = decapsulator.get_label_or_frag_id(&buffer);
@@ -2866,7 +2899,7 @@ fn test_get_label_or_frag_id_016() {
     //  F :     1 B Frag Id
     //  TOTAL =  3 Bytes min
 
-    frag.generate(&mut buffer);
+    frag.generate(&mut buffer).unwrap();
     buffer_too_small.copy_from_slice(&buffer[..TOO_SMALL_SIZE]);
 
     let mut decapsulator: Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> = create_decapsulator(1, PDU_LEN);
@@ -2878,4 +2911,1392 @@ fn test_get_label_or_frag_id_016() {
         Ok(res) => panic!("Wrong result, expected Err got Ok {:?}", res),
         Err(e) => assert_eq!(e, exp_status, "{}", comment),
     }
-}
\ No newline at end of file
+}
+/// Decap frame tests
+///
+/// Decapsulation of all the packets of a base band frame.
+/// complete packets with label re use, followed by padding
+#[test]
+fn test_decap_frame_001() {
+    const PDU_LEN: usize = 26;
+    const PKT1_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_6_B_LEN + PDU_LEN;
+    const PKT2_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_REUSE_LEN + PDU_LEN;
+    let comment = "complete packets and padding";
+
+    let pdu = *b"abcdefghijklmnopqrstuvwxyz";
+    let label = Label::SixBytesLabel(*b"012345");
+    let protocol_type = 0xFFFF;
+
+    let mut frame = [0; 100];
+    GseCompletePacket::new((PKT1_LEN - FIXED_HEADER_LEN) as u16, protocol_type, label, &pdu)
+        .generate(&mut frame).unwrap();
+    GseCompletePacket::new(
+        (PKT2_LEN - FIXED_HEADER_LEN) as u16,
+        protocol_type,
+        Label::ReUse,
+        &pdu,
+    )
+    .generate(&mut frame[PKT1_LEN..]).unwrap();
+
+    let mut decapsulator = create_decapsulator(2, PDU_LEN);
+    // label of a previous frame
+    decapsulator.last_label = Some(Label::SixBytesLabel(*b"543210"));
+
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(pdu),
+        DecapMetadata::new(PDU_LEN, protocol_type, label, vec![]),
+    );
+    let mut frame_decap = decapsulator.decap_frame(&frame);
+    assert_eq!(Some(Ok(exp_status.clone())), frame_decap.next(), "{}", comment);
+    assert_eq!(Some(Ok(exp_status)), frame_decap.next(), "{}", comment);
+    assert_eq!(None, frame_decap.next(), "{}", comment);
+    assert_eq!(None, frame_decap.next(), "{}", comment);
+    assert_eq!(PKT1_LEN + PKT2_LEN, frame_decap.offset(), "{}", comment);
+}
+
+/// re use label at the beginning of a frame, the label of the previous frame is not used
+#[test]
+fn test_decap_frame_002() {
+    const PDU_LEN: usize = 26;
+    const PKT_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_REUSE_LEN + PDU_LEN;
+    let comment = "re use label at the beginning of a frame";
+
+    let pdu = *b"abcdefghijklmnopqrstuvwxyz";
+    let label = Label::Broadcast;
+    let protocol_type = 0xFFFF;
+
+    let mut frame = [0; PKT_LEN * 2];
+    GseCompletePacket::new((PKT_LEN - FIXED_HEADER_LEN) as u16, protocol_type, Label::ReUse, &pdu)
+        .generate(&mut frame).unwrap();
+    GseCompletePacket::new((PKT_LEN - FIXED_HEADER_LEN) as u16, protocol_type, label, &pdu)
+        .generate(&mut frame[PKT_LEN..]).unwrap();
+
+    let mut decapsulator = create_decapsulator(2, PDU_LEN);
+    decapsulator.last_label = Some(Label::SixBytesLabel(*b"012345"));
+
+    let obs_status: Vec<_> = decapsulator.decap_frame(&frame).collect();
+    let exp_status = vec![
+        Err(DecapError::ErrorNoLabelSaved),
+        Ok(DecapStatus::CompletedPkt(
+            Box::new(pdu),
+            DecapMetadata::new(PDU_LEN, protocol_type, label, vec![]),
+        )),
+    ];
+    assert_eq!(exp_status, obs_status, "{}", comment);
+}
+
+/// fragmented packets and a single byte of padding
+#[test]
+fn test_decap_frame_003() {
+    const PDU_LEN: usize = 26;
+    const FIRST_PDU_LEN: usize = 10;
+    const PKT1_LEN: usize =
+        FIXED_HEADER_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN + PROTOCOL_LEN + LABEL_3_B_LEN + FIRST_PDU_LEN;
+    const PKT2_LEN: usize = FIXED_HEADER_LEN + FRAG_ID_LEN + PDU_LEN - FIRST_PDU_LEN + CRC_LEN;
+    let comment = "fragmented packets";
+
+    let pdu = *b"abcdefghijklmnopqrstuvwxyz";
+    let label = Label::ThreeBytesLabel(*b"012");
+    let protocol_type = 0x1234;
+    let frag_id = 7;
+    let total_len = (PDU_LEN + PROTOCOL_LEN + LABEL_3_B_LEN) as u16;
+    let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, label.get_bytes());
+
+    let mut frame = [0; PKT1_LEN + PKT2_LEN + 1];
+    GseFirstFragPacket::new(
+        (PKT1_LEN - FIXED_HEADER_LEN) as u16,
+        frag_id,
+        total_len,
+        protocol_type,
+        label,
+        &pdu[..FIRST_PDU_LEN],
+    )
+    .generate(&mut frame).unwrap();
+    GseEndFragPacket::new(
+        (PKT2_LEN - FIXED_HEADER_LEN) as u16,
+        frag_id,
+        &pdu[FIRST_PDU_LEN..],
+        crc,
+    )
+    .generate(&mut frame[PKT1_LEN..]).unwrap();
+
+    let mut decapsulator = create_decapsulator(2, PDU_LEN);
+    let obs_status: Vec<_> = decapsulator.decap_frame(&frame).collect();
+    let exp_status = vec![
+        Ok(DecapStatus::FragmentedPkt(DecapMetadata::new(
+            0,
+            protocol_type,
+            label,
+            vec![],
+        ))),
+        Ok(DecapStatus::CompletedPkt(
+            Box::new(pdu),
+            DecapMetadata::new(PDU_LEN, protocol_type, label, vec![]),
+        )),
+    ];
+    assert_eq!(exp_status, obs_status, "{}", comment);
+}
+
+/// truncated packet at the end of the frame, the iteration stops after the error
+#[test]
+fn test_decap_frame_004() {
+    const PDU_LEN: usize = 26;
+    const PKT_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_BROADCAST_LEN + PDU_LEN;
+    let comment = "truncated packet";
+
+    let pdu = *b"abcdefghijklmnopqrstuvwxyz";
+    let mut frame = [0; PKT_LEN];
+    GseCompletePacket::new((PKT_LEN - FIXED_HEADER_LEN) as u16, 0xFFFF, Label::Broadcast, &pdu)
+        .generate(&mut frame).unwrap();
+
+    let mut decapsulator = create_decapsulator(2, PDU_LEN);
+    let mut frame_decap = decapsulator.decap_frame(&frame[..PKT_LEN - 1]);
+    assert_eq!(
+        Some(Err(DecapError::ErrorSizeBuffer)),
+        frame_decap.next(),
+        "{}",
+        comment
+    );
+    assert_eq!(None, frame_decap.next(), "{}", comment);
+    assert_eq!(PKT_LEN - 1, frame_decap.offset(), "{}", comment);
+}
+
+/// header extensions longer than their packet, only the malformed packets are dropped
+#[test]
+fn test_decap_frame_005() {
+    const PDU_LEN: usize = 26;
+    const PKT_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_BROADCAST_LEN + PDU_LEN;
+    let comment = "header extensions longer than their packet";
+
+    // the extension 0x0300 promises 4 bytes of data, but the packets end after 1 byte
+    let complete = [0xE0, 0x03, 0x03, 0x00, 0xAA];
+    let first_frag = [0xA0, 0x06, 0x01, 0x00, 0x10, 0x03, 0x00, 0xAA];
+    let pdu = *b"abcdefghijklmnopqrstuvwxyz";
+
+    let mut frame = [0; 5 + 8 + PKT_LEN];
+    frame[..5].copy_from_slice(&complete);
+    frame[5..13].copy_from_slice(&first_frag);
+    GseCompletePacket::new((PKT_LEN - FIXED_HEADER_LEN) as u16, 0xFFFF, Label::Broadcast, &pdu)
+        .generate(&mut frame[13..])
+        .unwrap();
+
+    let mut decapsulator = create_decapsulator(2, PDU_LEN);
+    let obs_status: Vec<_> = decapsulator.decap_frame(&frame).collect();
+    let exp_status = vec![
+        Err(DecapError::ErrorSizePduBuffer),
+        Err(DecapError::ErrorSizePduBuffer),
+        Ok(DecapStatus::CompletedPkt(
+            Box::new(pdu),
+            DecapMetadata::new(PDU_LEN, 0xFFFF, Label::Broadcast, vec![]),
+        )),
+    ];
+    assert_eq!(exp_status, obs_status, "{}", comment);
+}
+
+/// Tick test
+///
+/// The end fragment of an expired context is dropped and the buffer can be reused.
+#[test]
+fn test_decap_tick_001() {
+    const PDU_LEN: usize = 26;
+    const FIRST_PDU_LEN: usize = 10;
+    const PKT1_LEN: usize =
+        FIXED_HEADER_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN + PROTOCOL_LEN + FIRST_PDU_LEN;
+    const PKT2_LEN: usize = FIXED_HEADER_LEN + FRAG_ID_LEN + PDU_LEN - FIRST_PDU_LEN + CRC_LEN;
+    let comment = "expired context";
+
+    let pdu = *b"abcdefghijklmnopqrstuvwxyz";
+    let protocol_type = 0x1234;
+    let frag_id = 3;
+    let total_len = (PDU_LEN + PROTOCOL_LEN) as u16;
+    let crc = DefaultCrc {}.calculate_crc32(&pdu, protocol_type, total_len, &[]);
+
+    let mut first_pkt = [0; PKT1_LEN];
+    GseFirstFragPacket::new(
+        (PKT1_LEN - FIXED_HEADER_LEN) as u16,
+        frag_id,
+        total_len,
+        protocol_type,
+        Label::Broadcast,
+        &pdu[..FIRST_PDU_LEN],
+    )
+    .generate(&mut first_pkt).unwrap();
+    let mut end_pkt = [0; PKT2_LEN];
+    GseEndFragPacket::new(
+        (PKT2_LEN - FIXED_HEADER_LEN) as u16,
+        frag_id,
+        &pdu[FIRST_PDU_LEN..],
+        crc,
+    )
+    .generate(&mut end_pkt).unwrap();
+
+    let mut memory = SimpleGseMemory::new(4, PDU_LEN, 1, 0);
+    memory
+        .provision_storage(vec![0; PDU_LEN].into_boxed_slice())
+        .unwrap();
+    let mut decapsulator = Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+    );
+
+    decapsulator.decap(&first_pkt).unwrap();
+    assert_eq!(Vec::<DecapContext>::new(), decapsulator.tick(1), "{}", comment);
+    let mut exp_context = DecapContext::new(
+        Label::Broadcast,
+        protocol_type,
+        frag_id,
+        total_len,
+        FIRST_PDU_LEN as u16,
+        false,
+        vec![],
+    );
+    let exp_crc = DefaultCrc {}.init_crc32(protocol_type, total_len, &[]);
+    exp_context.crc = DefaultCrc {}.update_crc32(exp_crc, &pdu[..FIRST_PDU_LEN]);
+    assert_eq!(vec![exp_context], decapsulator.tick(1), "{}", comment);
+
+    let obs = decapsulator.decap(&end_pkt);
+    let exp = Err((
+        DecapError::ErrorMemory(DecapMemoryError::UndefinedId),
+        PKT2_LEN,
+    ));
+    assert_eq!(exp, obs, "{}", comment);
+
+    // the buffer has been released
+    decapsulator.decap(&first_pkt).unwrap();
+    let (status, _) = decapsulator.decap(&end_pkt).unwrap();
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(pdu),
+        DecapMetadata::new(PDU_LEN, protocol_type, Label::Broadcast, vec![]),
+    );
+    assert_eq!(exp_status, status, "{}", comment);
+}
+
+/// Mandatory header extension manager of the randomized tests
+///
+/// The ids 0x0000 to 0x0007 are non final extensions and the ids 0x0008 to 0x000F final extensions,
+/// their data length being the 3 lower bits of the id.
+struct FuzzMandatoryExtensionHeaderManager {}
+impl MandatoryHeaderExtensionManager for FuzzMandatoryExtensionHeaderManager {
+    fn is_mandatory_header_id_known(&self, id: u16) -> MandatoryHeaderExt {
+        match id {
+            0x0000..=0x0007 => MandatoryHeaderExt::NonFinal((id & 0x7) as u8),
+            0x0008..=0x000F => MandatoryHeaderExt::Final((id & 0x7) as u8),
+            _ => MandatoryHeaderExt::Unknown,
+        }
+    }
+}
+
+/// Pseudo random generator of the randomized tests
+struct FuzzRng(u32);
+impl FuzzRng {
+    fn next(&mut self) -> u32 {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        self.0 >> 8
+    }
+
+    fn below(&mut self, max: u32) -> u32 {
+        self.next() % max
+    }
+}
+
+/// Write a random gse packet at the end of the frame
+///
+/// The fields are chosen among small values, so that the packets are mostly well formed:
+/// few frag ids, short gse lengths and protocol types often replaced by extension ids.
+fn push_random_packet(rng: &mut FuzzRng, frame: &mut Vec<u8>) {
+    let pkt_type = rng.below(4) as u16;
+    let label_type = rng.below(4) as u16;
+    let gse_len = match rng.below(8) {
+        0 => rng.below(0x1000) as u16,
+        _ => rng.below(48) as u16,
+    };
+    let header = (pkt_type << 14) | (label_type << 12) | gse_len;
+    frame.extend_from_slice(&header.to_be_bytes());
+
+    let mut body: Vec<u8> = (0..gse_len).map(|_| rng.next() as u8).collect();
+    let ptype_offset = match pkt_type {
+        // first fragment: frag id and total length before the protocol type
+        2 => Some(FRAG_ID_LEN + TOTAL_LENGTH_LEN),
+        3 => Some(0),
+        _ => None,
+    };
+    if let Some(frag_id) = body.first_mut() {
+        if pkt_type != 3 {
+            *frag_id &= 0x03;
+        }
+    }
+    if let Some(offset) = ptype_offset {
+        let ptype: u16 = match rng.below(5) {
+            0 => rng.below(0x10) as u16,
+            1 => (rng.below(6) as u16) << 8 | rng.below(4) as u16,
+            2 => NCR_PROTOCOL_ID,
+            _ => rng.next() as u16,
+        };
+        if let Some(field) = body.get_mut(offset..offset + PROTOCOL_LEN) {
+            field.copy_from_slice(&ptype.to_be_bytes());
+        }
+    }
+    if pkt_type == 2 {
+        if let Some(field) = body.get_mut(FRAG_ID_LEN..FRAG_ID_LEN + TOTAL_LENGTH_LEN) {
+            field.copy_from_slice(&(rng.below(64) as u16).to_be_bytes());
+        }
+    }
+
+    // the body may be shorter or longer than the gse length
+    let body_len = match rng.below(8) {
+        0 => rng.below(gse_len as u32 + 1) as usize,
+        1 => gse_len as usize + rng.below(4) as usize,
+        _ => gse_len as usize,
+    };
+    body.resize(body_len, 0);
+    frame.extend_from_slice(&body);
+}
+
+/// Decapsulate all the packets of the frame, the pdus received are given back to the memory
+///
+/// The storage is refilled with a new buffer before each packet, so that it is often full when a buffer is released.
+fn decap_random_frame<T: GseDecapMemory, MHEM: MandatoryHeaderExtensionManager>(
+    decapsulator: &mut Decapsulator<T, DefaultCrc, MHEM>,
+    frame: &[u8],
+    pdu_size: usize,
+) {
+    let mut offset = 0;
+    while offset < frame.len() {
+        let _ = decapsulator.provision_storage(vec![0; pdu_size].into_boxed_slice());
+        match decapsulator.decap(&frame[offset..]) {
+            Ok((DecapStatus::CompletedPkt(pdu, _), pkt_len)) => {
+                let _ = decapsulator.provision_storage(pdu);
+                offset += pkt_len;
+            }
+            Ok((DecapStatus::Padding, _)) => break,
+            Ok((_, pkt_len)) | Err((_, pkt_len)) => {
+                if pkt_len == 0 {
+                    break;
+                }
+                offset += pkt_len;
+            }
+        }
+    }
+
+    let _ = decapsulator.decap_frame(frame).count();
+}
+
+/// Randomized test: the decapsulation of arbitrary frames never panics
+#[test]
+fn test_decap_no_panic_001() {
+    let mut rng = FuzzRng(0xDEC0_DE42);
+
+    for pdu_size in [0, 8, 64, 4096] {
+        let mut memory = SimpleGseMemory::new(4, pdu_size, 0, 2);
+        let mut stream_memory = StreamGseMemory::new(4, pdu_size, 0, 2);
+        for _ in 0..4 {
+            memory
+                .provision_storage(vec![0; pdu_size].into_boxed_slice())
+                .unwrap();
+            stream_memory
+                .provision_storage(vec![0; pdu_size].into_boxed_slice())
+                .unwrap();
+        }
+        let mut decapsulator =
+            Decapsulator::new(memory, DefaultCrc {}, FuzzMandatoryExtensionHeaderManager {});
+        let mut stream_decapsulator = Decapsulator::new(
+            stream_memory,
+            DefaultCrc {},
+            SignalisationMandatoryExtensionHeaderManager {},
+        );
+
+        for _ in 0..5000 {
+            let mut frame = vec![];
+            for _ in 0..rng.below(6) {
+                push_random_packet(&mut rng, &mut frame);
+            }
+            decap_random_frame(&mut decapsulator, &frame, pdu_size);
+            decap_random_frame(&mut stream_decapsulator, &frame, pdu_size);
+        }
+    }
+}
+
+#[test]
+fn test_decap_no_panic_003() {
+    let comment = "end fragment too long for the buffer of its context while the storage is full";
+    let mut decapsulator = create_decapsulator(1, 8);
+    let first = [0xA0, 0x08, 0x00, 0x00, 0x0A, 0x12, 0x34, 0x61, 0x62, 0x63];
+    let end = [
+        0x70, 0x0B, 0x00, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x6B, 0x6C, 0x6D,
+    ];
+
+    assert_eq!(
+        Ok((
+            DecapStatus::FragmentedPkt(DecapMetadata::new(0, 0x1234, Label::Broadcast, vec![])),
+            first.len()
+        )),
+        decapsulator.decap(&first),
+        "{}",
+        comment
+    );
+    while decapsulator
+        .provision_storage(vec![0; 8].into_boxed_slice())
+        .is_ok()
+    {}
+    assert_eq!(
+        Err((DecapError::ErrorSizePduBuffer, end.len())),
+        decapsulator.decap(&end),
+        "{}",
+        comment
+    );
+}
+
+/// Exhaustive test: the decapsulation of every header followed by a few bytes never panics
+#[test]
+fn test_decap_no_panic_002() {
+    let mut decapsulator = create_decapsulator(4, 16);
+
+    for header in 0..=u16::MAX {
+        for len in FIXED_HEADER_LEN..FIXED_HEADER_LEN + 12 {
+            let mut buffer = [0x01; FIXED_HEADER_LEN + 12];
+            buffer[..FIXED_HEADER_LEN].copy_from_slice(&header.to_be_bytes());
+            decapsulator.last_label = Some(Label::ThreeBytesLabel(*b"abc"));
+            if let Ok((DecapStatus::CompletedPkt(pdu, _), _)) = decapsulator.decap(&buffer[..len])
+            {
+                let _ = decapsulator.provision_storage(pdu);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decap_borrowed_001() {
+    let comment = "complete packets borrowed from the input buffer without memory, the re-used label is resolved";
+    let mut decapsulator = create_decapsulator(0, 0);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let mut buffer = [0; 100];
+    let Ok(EncapStatus::CompletedPkt(len1)) =
+        encapsulator.encap(b"hello", 0, metadata, &mut buffer)
+    else {
+        panic!("{}", comment)
+    };
+    let len1 = len1 as usize;
+    encapsulator
+        .encap(b"world", 0, metadata, &mut buffer[len1..])
+        .unwrap();
+
+    let exp_metadata = DecapMetadata::new(5, 0x1234, Label::ThreeBytesLabel(*b"abc"), vec![]);
+    let (status, pkt_len) = decapsulator.decap_borrowed(&buffer).unwrap();
+    assert_eq!(
+        DecapBorrowedStatus::CompletedPkt(b"hello", exp_metadata.clone()),
+        status,
+        "{}",
+        comment
+    );
+    assert_eq!(len1, pkt_len, "{}", comment);
+    let (status, pkt_len) = decapsulator.decap_borrowed(&buffer[len1..]).unwrap();
+    assert_eq!(
+        DecapBorrowedStatus::CompletedPkt(b"world", exp_metadata),
+        status,
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Ok((DecapBorrowedStatus::Padding, 100 - len1 - pkt_len)),
+        decapsulator.decap_borrowed(&buffer[len1 + pkt_len..]),
+        "{}",
+        comment
+    );
+
+    // the copy requires a buffer of the memory
+    assert_eq!(
+        Err((
+            DecapError::ErrorMemory(DecapMemoryError::StorageUnderflow),
+            len1
+        )),
+        decapsulator.decap(&buffer),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_borrowed_002() {
+    let comment = "fragmented pdu reassembled in the memory";
+    let mut decapsulator = create_decapsulator(1, 26);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut buffer = [0; 100];
+    let Ok(EncapStatus::FragmentedPkt(len1, context)) =
+        encapsulator.encap(pdu, 7, metadata, &mut buffer[..20])
+    else {
+        panic!("{}", comment)
+    };
+    let len1 = len1 as usize;
+    encapsulator
+        .encap_frag(pdu, &context, &mut buffer[len1..])
+        .unwrap();
+
+    let exp_metadata = DecapMetadata::new(26, 0x1234, Label::Broadcast, vec![]);
+    let (status, pkt_len) = decapsulator.decap_borrowed(&buffer).unwrap();
+    assert_eq!(
+        DecapBorrowedStatus::FragmentedPkt(DecapMetadata::new(
+            0,
+            0x1234,
+            Label::Broadcast,
+            vec![]
+        )),
+        status,
+        "{}",
+        comment
+    );
+    assert_eq!(len1, pkt_len, "{}", comment);
+    let (status, _) = decapsulator.decap_borrowed(&buffer[len1..]).unwrap();
+    assert_eq!(
+        DecapBorrowedStatus::ReassembledPkt(Box::new(*pdu), exp_metadata),
+        status,
+        "{}",
+        comment
+    );
+
+    let invalid_label = [0xC0, 10, 0x12, 0x34, 0, 0, 0, 0, 0, 0, b'a', b'b'];
+    assert_eq!(
+        Err((DecapError::ErrorInvalidLabel, 12)),
+        decapsulator.decap_borrowed(&invalid_label),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_label_re_use_001() {
+    let comment = "re use label on a first fragment after a memory error";
+    let label = Label::ThreeBytesLabel(*b"abc");
+    let mut decapsulator = create_decapsulator(1, 26);
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut buffer = [0; 100];
+
+    // the label is re used by the second first fragment
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let Ok(EncapStatus::FragmentedPkt(len1, _)) = encapsulator.encap(
+        pdu,
+        1,
+        EncapMetadata::new(0x1234, label),
+        &mut buffer[..20],
+    ) else {
+        panic!("{}", comment)
+    };
+    let len1 = len1 as usize;
+    let Ok(EncapStatus::FragmentedPkt(len2, context)) = encapsulator.encap(
+        pdu,
+        2,
+        EncapMetadata::new(0x1234, label),
+        &mut buffer[len1..len1 + 20],
+    ) else {
+        panic!("{}", comment)
+    };
+    let len2 = len2 as usize;
+    encapsulator
+        .encap_frag(pdu, &context, &mut buffer[len1 + len2..])
+        .unwrap();
+    assert_eq!(
+        LABEL_REUSE,
+        u16::from_be_bytes([buffer[len1], buffer[len1 + 1]]) & LABEL_REUSE,
+        "{}",
+        comment
+    );
+
+    // no storage for the first context, its label is kept
+    let storage = decapsulator.new_pdu().unwrap();
+    assert_eq!(
+        Err((
+            DecapError::ErrorMemory(DecapMemoryError::StorageUnderflow),
+            len1
+        )),
+        decapsulator.decap(&buffer),
+        "{}",
+        comment
+    );
+    assert_eq!(Some(label), decapsulator.last_label(), "{}", comment);
+    decapsulator.provision_storage(storage).unwrap();
+
+    let exp_metadata = DecapMetadata::new(0, 0x1234, label, vec![]);
+    assert_eq!(
+        Ok((DecapStatus::FragmentedPkt(exp_metadata), len2)),
+        decapsulator.decap(&buffer[len1..]),
+        "{}",
+        comment
+    );
+    let (status, _) = decapsulator.decap(&buffer[len1 + len2..]).unwrap();
+    let exp_metadata = DecapMetadata::new(26, 0x1234, label, vec![]);
+    assert_eq!(
+        DecapStatus::CompletedPkt(Box::new(*pdu), exp_metadata),
+        status,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_label_re_use_002() {
+    let comment = "last label kept for each input stream";
+    let mut decapsulator = create_decapsulator(1, 26);
+    let label_pkt = [0xD0, 6, 0x12, 0x34, b'a', b'b', b'c', b'd'];
+    let re_use_pkt = [0xF0, 3, 0x12, 0x34, b'e'];
+
+    decapsulator.set_stream(1);
+    let (status, pkt_len) = decapsulator.decap(&label_pkt).unwrap();
+    let DecapStatus::CompletedPkt(pdu, _) = status else {
+        panic!("{}", comment)
+    };
+    decapsulator.provision_storage(pdu).unwrap();
+    assert_eq!(label_pkt.len(), pkt_len, "{}", comment);
+
+    decapsulator.set_stream(2);
+    assert_eq!(None, decapsulator.last_label(), "{}", comment);
+    assert_eq!(
+        Err((DecapError::ErrorNoLabelSaved, re_use_pkt.len())),
+        decapsulator.decap(&re_use_pkt),
+        "{}",
+        comment
+    );
+
+    decapsulator.set_stream(1);
+    assert_eq!(1, decapsulator.stream(), "{}", comment);
+    let (status, _) = decapsulator.decap(&re_use_pkt).unwrap();
+    let DecapStatus::CompletedPkt(_, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(
+        Label::ThreeBytesLabel(*b"abc"),
+        metadata.label(),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_stream_memory_001() {
+    let comment = "stream given to the memory, same frag id used on two streams";
+    let pdus = [b"abcdefghijklmnopqrstuvwxyz", b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"];
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+
+    let mut memory = StreamGseMemory::new(2, 26, 0, 0);
+    for _ in 0..2 {
+        memory
+            .provision_storage(vec![0; 26].into_boxed_slice())
+            .unwrap();
+    }
+    let mut decapsulator = Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+    );
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+
+    let mut contexts = vec![];
+    for (stream, pdu) in [1, 2].into_iter().zip(pdus) {
+        let mut buffer = [0; 20];
+        let Ok(EncapStatus::FragmentedPkt(_, context)) =
+            encapsulator.encap(pdu, 0, metadata, &mut buffer)
+        else {
+            panic!("{}", comment)
+        };
+        contexts.push(context);
+        decapsulator.set_stream(stream);
+        let (status, _) = decapsulator.decap(&buffer).unwrap();
+        assert!(
+            matches!(status, DecapStatus::FragmentedPkt(_)),
+            "{}",
+            comment
+        );
+    }
+
+    for (i, stream) in [1, 2].into_iter().enumerate() {
+        let mut buffer = [0; 100];
+        encapsulator
+            .encap_frag(pdus[i], &contexts[i], &mut buffer)
+            .unwrap();
+        decapsulator.set_stream(stream);
+        let (status, _) = decapsulator.decap(&buffer).unwrap();
+        let exp_status = DecapStatus::CompletedPkt(
+            Box::new(*pdus[i]),
+            DecapMetadata::new(26, 0x1234, Label::Broadcast, vec![]),
+        );
+        assert_eq!(exp_status, status, "{}: stream {}", comment, stream);
+    }
+}
+
+#[test]
+fn test_decap_label_re_use_003() {
+    let comment = "re use label on first fragments after a broadcast label and a wrong extension";
+    let mut decapsulator = create_decapsulator(1, 26);
+    // first fragment with a 6 bytes label and an unknown mandatory extension
+    let unknown_ext = [
+        0x80, 14, 3, 0, 30, 0x00, 0x05, b'0', b'1', b'2', b'3', b'4', b'5', 0x12, 0x34, b'a',
+    ];
+    // first fragment with a re use label
+    let re_use_first = [0xB0, 7, 4, 0, 26, 0x12, 0x34, b'a', b'b'];
+
+    decapsulator.set_last_label(Some(Label::Broadcast));
+    assert_eq!(
+        Err((DecapError::ErrorLabelBroadcastSaved, re_use_first.len())),
+        decapsulator.decap(&re_use_first),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Some(Label::Broadcast),
+        decapsulator.last_label(),
+        "{}",
+        comment
+    );
+
+    assert_eq!(
+        Err((DecapError::ErrorUnkownMandatoryHeader, unknown_ext.len())),
+        decapsulator.decap(&unknown_ext),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Some(Label::SixBytesLabel(*b"012345")),
+        decapsulator.last_label(),
+        "{}",
+        comment
+    );
+
+    let exp_metadata = DecapMetadata::new(0, 0x1234, Label::SixBytesLabel(*b"012345"), vec![]);
+    assert_eq!(
+        Ok((DecapStatus::FragmentedPkt(exp_metadata), re_use_first.len())),
+        decapsulator.decap(&re_use_first),
+        "{}",
+        comment
+    );
+}
+
+/// First and end fragments of the alphabet sent with the 3 bytes label "abc", the protocol type 0x0800
+/// and the optional header extension 0x0300, its total length and its crc covering the header extension (ETSI TS 102 606)
+///
+/// The crc 0x3016B561 was computed outside the crate, with a bit by bit Python implementation of the
+/// CRC-32/MPEG-2 of the crc catalogue (poly 0x04C11DB7, init 0xFFFFFFFF, check value 0x0376E6E7),
+/// over the total length 0x0025, the extension 0x0300, the label, the extension data, the protocol type and the pdu.
+const EXT_FIRST_FRAG: [u8; 30] = [
+    0x90, 0x1C, 0x07, 0x00, 0x25, 0x03, 0x00, 0x61, 0x62, 0x63, 0x01, 0x02, 0x03, 0x04, 0x08, 0x00,
+    0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E,
+];
+const EXT_END_FRAG: [u8; 19] = [
+    0x70, 0x11, 0x07, 0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x30,
+    0x16, 0xB5, 0x61,
+];
+
+#[test]
+fn test_decap_ext_coverage_001() {
+    let comment =
+        "fragments with a header extension counted in the total length and covered by the crc";
+    let mut decapsulator = create_decapsulator(1, 100);
+    let exp_metadata = DecapMetadata::new(
+        26,
+        0x0800,
+        Label::ThreeBytesLabel(*b"abc"),
+        vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()],
+    );
+
+    decapsulator.decap(&EXT_FIRST_FRAG).unwrap();
+    let (status, _) = decapsulator.decap(&EXT_END_FRAG).unwrap();
+
+    let DecapStatus::CompletedPkt(pdu, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(exp_metadata, metadata, "{}", comment);
+    assert_eq!(b"abcdefghijklmnopqrstuvwxyz", &pdu[..26], "{}", comment);
+}
+
+#[test]
+fn test_decap_ext_coverage_002() {
+    let comment = "final mandatory header extension, the total length counts no protocol type behind it";
+    let mut decapsulator = create_decapsulator_signalisation(1, 100);
+    let first = [
+        0xA0, 0x13, 0x07, 0x00, 0x1C, 0x00, 0x81, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+        0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E,
+    ];
+    // the crc 0x0AB909EC was computed outside the crate, with a bit by bit Python implementation of the
+    // CRC-32/MPEG-2 of the crc catalogue (poly 0x04C11DB7, init 0xFFFFFFFF, check value 0x0376E6E7),
+    // over the total length 0x001C, the extension 0x0081 and the pdu
+    let end = [
+        0x70, 0x11, 0x07, 0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A,
+        0x0A, 0xB9, 0x09, 0xEC,
+    ];
+
+    decapsulator.decap(&first).unwrap();
+    let (status, _) = decapsulator.decap(&end).unwrap();
+
+    let DecapStatus::CompletedPkt(pdu, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(NCR_PROTOCOL_ID, metadata.protocol_type, "{}", comment);
+    assert_eq!(b"abcdefghijklmnopqrstuvwxyz", &pdu[..26], "{}", comment);
+}
+
+#[test]
+fn test_decap_ext_coverage_003() {
+    let comment = "standard fragments with a header extension rejected by the legacy coverage";
+    let mut decapsulator = create_decapsulator(1, 100);
+    decapsulator.set_extension_coverage(ExtensionCoverage::Legacy);
+
+    decapsulator.decap(&EXT_FIRST_FRAG).unwrap();
+    assert_eq!(
+        Err((DecapError::ErrorTotalLength, EXT_END_FRAG.len())),
+        decapsulator.decap(&EXT_END_FRAG),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_ext_coverage_004() {
+    let comment =
+        "legacy fragments with a header extension, left out of the total length and of the crc";
+    let mut decapsulator = create_decapsulator(1, 100);
+    decapsulator.set_extension_coverage(ExtensionCoverage::Legacy);
+    let mut first = EXT_FIRST_FRAG;
+    first[4] = 31;
+    let mut end = EXT_END_FRAG;
+    end[15..].copy_from_slice(&[0xBD, 0x40, 0xA1, 0x46]);
+
+    decapsulator.decap(&first).unwrap();
+    let (status, _) = decapsulator.decap(&end).unwrap();
+    let DecapStatus::CompletedPkt(pdu, _) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(b"abcdefghijklmnopqrstuvwxyz", &pdu[..26], "{}", comment);
+    decapsulator.memory.provision_storage(pdu).unwrap();
+
+    decapsulator.set_extension_coverage(ExtensionCoverage::Standard);
+    decapsulator.decap(&first).unwrap();
+    assert_eq!(
+        Err((DecapError::ErrorTotalLength, end.len())),
+        decapsulator.decap(&end),
+        "{}",
+        comment
+    );
+}
+
+/// Non final mandatory extension whose data starts with the length of the rest of its data
+struct LengthPrefixed;
+
+impl ExtensionHandler for LengthPrefixed {
+    fn decode_len(&self, data: &[u8]) -> MandatoryHeaderExt {
+        match data.first() {
+            Some(&len) if len < u8::MAX => MandatoryHeaderExt::NonFinal(len + 1),
+            _ => MandatoryHeaderExt::Unknown,
+        }
+    }
+}
+
+/// Optional extension giving the number of bytes, not zero, to strip at the end of the pdu
+struct Trailer;
+
+impl ExtensionHandler for Trailer {
+    fn validate(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::Data2([0, 0]) => Err(ExtensionHandlerError::InvalidData),
+            _ => Ok(()),
+        }
+    }
+
+    fn decode_pdu(
+        &self,
+        extension: &Extension,
+        pdu: &[u8],
+    ) -> Result<Range<usize>, ExtensionHandlerError> {
+        let ExtensionData::Data2(data) = extension.data() else {
+            return Err(ExtensionHandlerError::InvalidData);
+        };
+        match pdu.len().checked_sub(u16::from_be_bytes(*data) as usize) {
+            Some(len) => Ok(0..len),
+            None => Err(ExtensionHandlerError::InvalidPdu),
+        }
+    }
+}
+
+fn create_decapsulator_registry(
+    max_frag_id: usize,
+    max_pdu_size: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, ExtensionRegistry> {
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, 0, 0);
+    for _ in 0..max_frag_id {
+        let storage = vec![0; max_pdu_size].into_boxed_slice();
+        memory.provision_storage(storage).unwrap();
+    }
+
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0010, LengthPrefixed);
+    registry.register(0x0200, Trailer);
+    Decapsulator::new(memory, DefaultCrc {}, registry)
+}
+
+/// Complete packet with the variable size mandatory extension 0x0010 then the trailer extension 0x0200 stripping 2 bytes
+const REGISTRY_COMPLETE_PKT: [u8; 19] = [
+    0xE0, 0x11, 0x00, 0x10, 0x02, 0x05, 0x06, 0x02, 0x00, 0x00, 0x02, 0x08, 0x00, 0x61, 0x62, 0x63,
+    0x64, 0x78, 0x79,
+];
+
+#[test]
+fn test_decap_registry_001() {
+    let comment = "complete packet with a variable size mandatory extension and a pdu transformed by its handler";
+    let mut decapsulator = create_decapsulator_registry(1, 100);
+    let exp_metadata = DecapMetadata::new(
+        4,
+        0x0800,
+        Label::Broadcast,
+        vec![
+            Extension::new(0x0010, &[2, 5, 6]).unwrap(),
+            Extension::new(0x0200, &[0, 2]).unwrap(),
+        ],
+    );
+
+    let (status, pkt_len) = decapsulator.decap(&REGISTRY_COMPLETE_PKT).unwrap();
+    let DecapStatus::CompletedPkt(pdu, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(REGISTRY_COMPLETE_PKT.len(), pkt_len, "{}", comment);
+    assert_eq!(exp_metadata, metadata, "{}", comment);
+    assert_eq!(b"abcd", &pdu[..4], "{}", comment);
+
+    let (status, _) = decapsulator.decap_borrowed(&REGISTRY_COMPLETE_PKT).unwrap();
+    assert_eq!(
+        DecapBorrowedStatus::CompletedPkt(b"abcd", exp_metadata),
+        status,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_registry_002() {
+    let comment = "extension rejected by its handler, pdu which can not be decoded by the handler";
+    let mut decapsulator = create_decapsulator_registry(1, 100);
+    let mut invalid_extension = REGISTRY_COMPLETE_PKT;
+    invalid_extension[10] = 0;
+    let mut invalid_pdu = REGISTRY_COMPLETE_PKT;
+    invalid_pdu[10] = 7;
+
+    assert_eq!(
+        Err((
+            DecapError::ErrorInvalidHeaderExtension,
+            invalid_extension.len()
+        )),
+        decapsulator.decap(&invalid_extension),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err((DecapError::ErrorInvalidHeaderExtension, invalid_pdu.len())),
+        decapsulator.decap(&invalid_pdu),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err((
+            DecapError::ErrorUnkownMandatoryHeader,
+            REGISTRY_COMPLETE_PKT.len()
+        )),
+        create_decapsulator(1, 100).decap(&REGISTRY_COMPLETE_PKT),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_registry_003() {
+    let comment = "fragmented pdu transformed by the handler of its extension once reassembled";
+    let mut decapsulator = create_decapsulator_registry(1, 100);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let metadata = EncapMetadata::new(0x0800, Label::ThreeBytesLabel(*b"abc"));
+    let extensions = vec![Extension::new(0x0200, &[0, 3]).unwrap()];
+    let mut first = [0; 20];
+    let Ok(EncapStatus::FragmentedPkt(first_len, context)) =
+        encapsulator.encap_ext(pdu, 7, metadata, &mut first, extensions)
+    else {
+        panic!("{}", comment)
+    };
+    let mut end = [0; 100];
+    let Ok(EncapStatus::CompletedPkt(end_len)) = encapsulator.encap_frag(pdu, &context, &mut end)
+    else {
+        panic!("{}", comment)
+    };
+
+    decapsulator.decap(&first[..first_len as usize]).unwrap();
+    let (status, _) = decapsulator.decap(&end[..end_len as usize]).unwrap();
+
+    let DecapStatus::CompletedPkt(pdu, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(23, metadata.pdu_len, "{}", comment);
+    assert_eq!(b"abcdefghijklmnopqrstuvw", &pdu[..23], "{}", comment);
+}
+
+/// Complete packet carrying an ethernet frame with the bridged frame extension
+const BRIDGED_FRAME_PKT: [u8; 22] = [
+    0xE0, 0x14, 0x00, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16,
+    0x08, 0x00, 0x61, 0x62, 0x63, 0x64,
+];
+
+fn create_decapsulator_bridged_frame(
+    max_frag_id: usize,
+    max_pdu_size: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, BridgedFrameMandatoryExtensionHeaderManager> {
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, 0, 0);
+    for _ in 0..max_frag_id {
+        let storage = vec![0; max_pdu_size].into_boxed_slice();
+        memory.provision_storage(storage).unwrap();
+    }
+    Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        BridgedFrameMandatoryExtensionHeaderManager {},
+    )
+}
+
+#[test]
+fn test_decap_bridged_frame_001() {
+    let comment =
+        "complete bridged frame, the ether type is the protocol type and the payload is delivered";
+    let mut decapsulator = create_decapsulator_bridged_frame(1, 100);
+    let exp_header = EthernetHeader {
+        destination: [0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+        source: [0x11, 0x12, 0x13, 0x14, 0x15, 0x16],
+        ether_type: 0x0800,
+    };
+
+    let (status, _) = decapsulator.decap(&BRIDGED_FRAME_PKT).unwrap();
+    let DecapStatus::CompletedPkt(pdu, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(0x0800, metadata.protocol_type(), "{}", comment);
+    assert_eq!(Some(exp_header), metadata.bridged_frame(), "{}", comment);
+    assert_eq!(4, metadata.pdu_len(), "{}", comment);
+    assert_eq!(b"abcd", &pdu[..4], "{}", comment);
+
+    let (status, _) = decapsulator.decap_borrowed(&BRIDGED_FRAME_PKT).unwrap();
+    let DecapBorrowedStatus::CompletedPkt(pdu, _) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(b"abcd", pdu, "{}", comment);
+}
+
+#[test]
+fn test_decap_bridged_frame_002() {
+    let comment =
+        "bridged frame too short to hold an ethernet header, unknown without the extension";
+    let mut decapsulator = create_decapsulator_bridged_frame(1, 100);
+    let mut pkt = BRIDGED_FRAME_PKT[..15].to_vec();
+    pkt[1] = 13;
+
+    assert_eq!(
+        Err((DecapError::ErrorInvalidHeaderExtension, pkt.len())),
+        decapsulator.decap(&pkt),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err((
+            DecapError::ErrorUnkownMandatoryHeader,
+            BRIDGED_FRAME_PKT.len()
+        )),
+        create_decapsulator(1, 100).decap(&BRIDGED_FRAME_PKT),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_bridged_frame_003() {
+    let comment = "fragmented bridged frame received with the handler of a registry";
+    let mut memory = SimpleGseMemory::new(1, 100, 0, 0);
+    memory
+        .provision_storage(vec![0; 100].into_boxed_slice())
+        .unwrap();
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0001, BridgedFrameHandler);
+    let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, registry);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let frame = &BRIDGED_FRAME_PKT[4..];
+    let mut first = [0; 12];
+    let Ok(EncapStatus::FragmentedPkt(first_len, context)) =
+        encapsulator.encap_bridged_frame(frame, 3, Label::Broadcast, &mut first)
+    else {
+        panic!("{}", comment)
+    };
+    let mut end = [0; 100];
+    let Ok(EncapStatus::CompletedPkt(end_len)) = encapsulator.encap_frag(frame, &context, &mut end)
+    else {
+        panic!("{}", comment)
+    };
+
+    let (status, _) = decapsulator.decap(&first[..first_len as usize]).unwrap();
+    let DecapStatus::FragmentedPkt(metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(0x0001, metadata.protocol_type(), "{}", comment);
+    let (status, _) = decapsulator.decap(&end[..end_len as usize]).unwrap();
+    let DecapStatus::CompletedPkt(pdu, metadata) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(0x0800, metadata.protocol_type(), "{}", comment);
+    assert_eq!(
+        Some([0x11, 0x12, 0x13, 0x14, 0x15, 0x16]),
+        metadata.bridged_frame().map(|header| header.source),
+        "{}",
+        comment
+    );
+    assert_eq!(b"abcd", &pdu[..metadata.pdu_len()], "{}", comment);
+}
+
+fn create_decapsulator_pdu_concat(
+    max_frag_id: usize,
+    max_pdu_size: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, ExtensionRegistry> {
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, 0, 0);
+    for _ in 0..max_frag_id {
+        let storage = vec![0; max_pdu_size].into_boxed_slice();
+        memory.provision_storage(storage).unwrap();
+    }
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0003, PduConcatHandler);
+    Decapsulator::new(memory, DefaultCrc {}, registry)
+}
+
+const CONCAT_PDUS: [&[u8]; 3] = [b"abc", b"defgh", b"ij"];
+
+#[test]
+fn test_decap_pdu_concat_001() {
+    let comment = "complete packet of concatenated pdus split back, each pdu counted";
+    let mut decapsulator = create_decapsulator_pdu_concat(1, 100);
+    decapsulator.enable_stats();
+    let mut buffer = [0; 100];
+    let Ok(EncapStatus::CompletedPkt(pkt_len)) = Encapsulator::new(DefaultCrc {}).encap_ext_concat(
+        &CONCAT_PDUS,
+        0,
+        EncapMetadata::new(0x0800, Label::Broadcast),
+        &mut buffer,
+        vec![],
+    ) else {
+        panic!("{}", comment)
+    };
+    let pkt = &buffer[..pkt_len as usize];
+
+    let (status, _) = decapsulator.decap(pkt).unwrap();
+    let DecapStatus::ConcatenatedPkt(pdu, metadata, pdus) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(0x0800, metadata.protocol_type(), "{}", comment);
+    assert_eq!(16, metadata.pdu_len(), "{}", comment);
+    assert_eq!(
+        CONCAT_PDUS.to_vec(),
+        pdus.into_iter()
+            .map(|range| &pdu[range])
+            .collect::<Vec<_>>(),
+        "{}",
+        comment
+    );
+
+    let (status, _) = decapsulator.decap_borrowed(pkt).unwrap();
+    let DecapBorrowedStatus::ConcatenatedPkt(pdus, _) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(CONCAT_PDUS.to_vec(), pdus, "{}", comment);
+    assert_eq!(
+        6,
+        decapsulator.stats().unwrap().protocol_type_count(0x0800),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_pdu_concat_002() {
+    let comment = "fragmented packet of concatenated pdus split back once reassembled";
+    let mut decapsulator = create_decapsulator_pdu_concat(1, 100);
+    let encapsulator = &mut Encapsulator::new(DefaultCrc {});
+    let mut first = [0; 16];
+    let Ok(EncapStatus::FragmentedPkt(first_len, context)) = encapsulator.encap_ext_concat(
+        &CONCAT_PDUS,
+        5,
+        EncapMetadata::new(0x0800, Label::ThreeBytesLabel(*b"abc")),
+        &mut first,
+        vec![],
+    ) else {
+        panic!("{}", comment)
+    };
+    let mut end = [0; 100];
+    let Ok(EncapStatus::CompletedPkt(end_len)) =
+        encapsulator.encap_frag_concat(&CONCAT_PDUS, &context, &mut end)
+    else {
+        panic!("{}", comment)
+    };
+
+    decapsulator.decap(&first[..first_len as usize]).unwrap();
+    let (status, _) = decapsulator
+        .decap_borrowed(&end[..end_len as usize])
+        .unwrap();
+
+    let DecapBorrowedStatus::ReassembledConcatenatedPkt(pdu, _, pdus) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(
+        CONCAT_PDUS.to_vec(),
+        pdus.into_iter()
+            .map(|range| &pdu[range])
+            .collect::<Vec<_>>(),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_pdu_concat_003() {
+    let comment = "concatenated pdus whose lengths exceed the packet";
+    let mut decapsulator = create_decapsulator_pdu_concat(1, 100);
+    let pkt = [
+        0xE0, 0x0B, 0x00, 0x03, 0x08, 0x00, 0x00, 0x02, 0x61, 0x62, 0x00, 0x02, 0x63,
+    ];
+
+    assert_eq!(
+        Err((DecapError::ErrorInvalidHeaderExtension, pkt.len())),
+        decapsulator.decap(&pkt),
+        "{}",
+        comment
+    );
+}
+
+fn create_decapsulator_ts_concat(
+    max_frag_id: usize,
+    max_pdu_size: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, TsConcatMandatoryExtensionHeaderManager> {
+    let mut memory = SimpleGseMemory::new(max_frag_id, max_pdu_size, 0, 0);
+    for _ in 0..max_frag_id {
+        let storage = vec![0; max_pdu_size].into_boxed_slice();
+        memory.provision_storage(storage).unwrap();
+    }
+    Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        TsConcatMandatoryExtensionHeaderManager {},
+    )
+}
+
+fn ts_packet(continuity: u8) -> [u8; 188] {
+    let mut packet = [continuity; 188];
+    packet[0] = 0x47;
+    packet
+}
+
+#[test]
+fn test_decap_ts_concat_001() {
+    let comment = "complete packet of ts packets, each ts packet delivered apart";
+    let mut decapsulator = create_decapsulator_ts_concat(1, 400);
+    decapsulator.enable_stats();
+    let packets = [ts_packet(1), ts_packet(2)];
+    let packets: Vec<&[u8]> = packets.iter().map(|packet| &packet[..]).collect();
+    let mut buffer = [0; 400];
+    let Ok(EncapStatus::CompletedPkt(pkt_len)) = Encapsulator::new(DefaultCrc {}).encap_ts_concat(
+        &packets,
+        0,
+        Label::Broadcast,
+        &mut buffer,
+    ) else {
+        panic!("{}", comment)
+    };
+    let pkt = &buffer[..pkt_len as usize];
+
+    let (status, _) = decapsulator.decap(pkt).unwrap();
+    let DecapStatus::ConcatenatedPkt(pdu, metadata, ranges) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(0x0002, metadata.protocol_type(), "{}", comment);
+    assert_eq!(376, metadata.pdu_len(), "{}", comment);
+    assert_eq!(vec![0..188, 188..376], ranges, "{}", comment);
+    assert_eq!(packets[1], &pdu[188..376], "{}", comment);
+
+    let (status, _) = decapsulator.decap_borrowed(pkt).unwrap();
+    let DecapBorrowedStatus::ConcatenatedPkt(pdus, _) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(packets, pdus, "{}", comment);
+    assert_eq!(
+        4,
+        decapsulator.stats().unwrap().protocol_type_count(0x0002),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_ts_concat_002() {
+    let comment =
+        "fragmented packet of ts packets received with the registry, split once reassembled";
+    let mut memory = SimpleGseMemory::new(1, 400, 0, 0);
+    memory
+        .provision_storage(vec![0; 400].into_boxed_slice())
+        .unwrap();
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0002, TsConcatHandler);
+    let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, registry);
+    let packets = [ts_packet(1), ts_packet(2)];
+    let packets: Vec<&[u8]> = packets.iter().map(|packet| &packet[..]).collect();
+    let encapsulator = &mut Encapsulator::new(DefaultCrc {});
+    let mut first = [0; 200];
+    let Ok(EncapStatus::FragmentedPkt(first_len, context)) =
+        encapsulator.encap_ts_concat(&packets, 3, Label::Broadcast, &mut first)
+    else {
+        panic!("{}", comment)
+    };
+    let mut end = [0; 400];
+    let Ok(EncapStatus::CompletedPkt(end_len)) =
+        encapsulator.encap_frag_vectored(&packets, &context, &mut end)
+    else {
+        panic!("{}", comment)
+    };
+
+    decapsulator.decap(&first[..first_len as usize]).unwrap();
+    let (status, _) = decapsulator
+        .decap_borrowed(&end[..end_len as usize])
+        .unwrap();
+
+    let DecapBorrowedStatus::ReassembledConcatenatedPkt(pdu, _, ranges) = status else {
+        panic!("{}", comment)
+    };
+    assert_eq!(
+        packets,
+        ranges
+            .into_iter()
+            .map(|range| &pdu[range])
+            .collect::<Vec<_>>(),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_decap_ts_concat_003() {
+    let comment = "ts packet without sync byte, truncated ts packet";
+    let mut decapsulator = create_decapsulator_ts_concat(1, 400);
+    let mut unsynced = vec![0xE0, 0xBE, 0x00, 0x02];
+    unsynced.extend_from_slice(&ts_packet(1));
+    unsynced[4] = 0x00;
+    let mut truncated = vec![0xE0, 0x66, 0x00, 0x02];
+    truncated.extend_from_slice(&ts_packet(1)[..100]);
+
+    assert_eq!(
+        Err((DecapError::ErrorInvalidHeaderExtension, unsynced.len())),
+        decapsulator.decap(&unsynced),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err((DecapError::ErrorInvalidHeaderExtension, truncated.len())),
+        decapsulator.decap(&truncated),
+        "{}",
+        comment
+    );
+}
@@ -0,0 +1,352 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the encapsulation in place
+//!
+//! [`Encapsulator::encap`] copies the pdu into the buffer of the packet.
+//! When the pdu is already in a buffer owned by the user, an [`EncapBuffer`] reserves some room before the pdu (headroom)
+//! and after it (tailroom), so that the packets are built around the pdu without copying it,
+//! in the same way as the virtual fragments of the C GSE library.
+//!
+//! * [`Encapsulator::encap_in_place`] writes the header of a complete packet or of a first fragment in the headroom.
+//! * [`Encapsulator::encap_frag_in_place`] writes the next fragments. The header of a fragment is written over the end
+//!   of the previous one, which has to be sent or copied before, and the crc of the end fragment is written in the tailroom.
+//!
+//! The packet built is given by [`EncapBuffer::packet`].
+//! A headroom of [`MAX_HEADER_LEN`] bytes, plus the length of the header extensions, is always enough.
+//!
+//! # Example
+//!
+//! ```
+//! use dvb_gse_rust::crc::DefaultCrc;
+//! use dvb_gse_rust::label::Label;
+//! use dvb_gse_rust::gse_encap::{Encapsulator, EncapBuffer, EncapMetadata, EncapStatus, MAX_HEADER_LEN};
+//!
+//! let pdu = b"abcdefghijklmnopqrstuvwxyz";
+//! let metadata = EncapMetadata::new(0xFFFF, Label::SixBytesLabel(*b"012345"));
+//! let mut encapsulator = Encapsulator::new(DefaultCrc {});
+//!
+//! // the pdu is received after the headroom of the buffer
+//! let mut buffer = EncapBuffer::new(vec![0; MAX_HEADER_LEN + pdu.len() + 4].into_boxed_slice(), MAX_HEADER_LEN, pdu.len()).unwrap();
+//! buffer.pdu_mut().copy_from_slice(pdu);
+//!
+//! // the header is written in front of the pdu
+//! let status = encapsulator.encap_in_place(&mut buffer, 0, metadata, &[], 1000);
+//! assert_eq!(status, Ok(EncapStatus::CompletedPkt(2 + 2 + 6 + 26)));
+//! assert_eq!(&buffer.packet()[10..], pdu);
+//! ```
+#[cfg(test)]
+mod tests;
+
+use crate::crc::CrcCalculator;
+use crate::gse_encap::{
+    check_extensions, generate_gse_header, write_header_fields, ContextFrag, EncapError,
+    EncapMetadata, EncapStatus, Encapsulator, FragCrc,
+};
+use crate::gse_standard::{
+    CRC_LEN, FIRST_FRAG_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, GSE_LEN_MAX, LABEL_6_B_LEN,
+    MAX_MANDATORY_VAL_PTYPE, PROTOCOL_LEN, SECOND_RANGE_PTYPE, TOTAL_LENGTH_LEN, TOTAL_LEN_MAX,
+};
+use crate::header_extension::Extension;
+use crate::label::{Label, LabelType};
+use crate::pkt_type::PktType;
+
+/// Length of the longest header without header extension, the one of a first fragment with a 6 bytes label
+pub const MAX_HEADER_LEN: usize = FIRST_FRAG_LEN + LABEL_6_B_LEN;
+
+/// Length of the header of an intermediate or an end fragment
+const FRAG_HEADER_LEN: usize = FIXED_HEADER_LEN + FRAG_ID_LEN;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Structure EncapBuffer
+///
+/// Buffer owning a pdu with some room before and after it, in which the packets are built in place.
+pub struct EncapBuffer {
+    data: Box<[u8]>,
+    pdu_start: usize,
+    pdu_end: usize,
+    pkt_start: usize,
+    pkt_end: usize,
+}
+
+impl EncapBuffer {
+    /// EncapBuffer constructor, the pdu is placed after `headroom` bytes and the rest of the buffer is the tailroom
+    ///
+    /// Return `ErrorSizeBuffer` if the buffer can not contain the headroom and the pdu.
+    pub fn new(
+        data: Box<[u8]>,
+        headroom: usize,
+        pdu_len: usize,
+    ) -> Result<EncapBuffer, EncapError> {
+        if data.len() < headroom + pdu_len {
+            return Err(EncapError::ErrorSizeBuffer);
+        }
+        Ok(EncapBuffer {
+            data,
+            pdu_start: headroom,
+            pdu_end: headroom + pdu_len,
+            pkt_start: headroom,
+            pkt_end: headroom,
+        })
+    }
+
+    /// Return the room available before the pdu
+    pub fn headroom(&self) -> usize {
+        self.pdu_start
+    }
+
+    /// Return the room available after the pdu
+    pub fn tailroom(&self) -> usize {
+        self.data.len() - self.pdu_end
+    }
+
+    /// Return the pdu
+    ///
+    /// Once fragmented in place, the bytes of the pdu covered by the header of a fragment are overwritten.
+    pub fn pdu(&self) -> &[u8] {
+        &self.data[self.pdu_start..self.pdu_end]
+    }
+
+    /// Return the pdu to write it in place
+    pub fn pdu_mut(&mut self) -> &mut [u8] {
+        &mut self.data[self.pdu_start..self.pdu_end]
+    }
+
+    /// Return the last packet built in the buffer, empty if none has been built
+    pub fn packet(&self) -> &[u8] {
+        &self.data[self.pkt_start..self.pkt_end]
+    }
+
+    /// Return the whole buffer
+    pub fn into_inner(self) -> Box<[u8]> {
+        self.data
+    }
+}
+
+impl<C: CrcCalculator> Encapsulator<C> {
+    /// GSE encapsulation in place of the pdu of an [`EncapBuffer`]
+    ///
+    /// The header is written in the headroom, in front of the pdu, and the packet is given by [`EncapBuffer::packet`].
+    /// If the packet fits into `max_pkt_len` bytes, the pdu is completely encapsulated and the function returns the status completed packet.
+    /// Else, the first fragment is built and a context of fragmentation is returned with the status fragmented packet,
+    /// the next fragments are built by [`Self::encap_frag_in_place`].
+    ///
+    /// The header extensions follow the same rules as [`Self::encap_ext`], `extensions` may be empty as with [`Self::encap`].
+    /// If the headroom is too small for the header or `max_pkt_len` too small for a first fragment, it returns `ErrorSizeBuffer`.
+    pub fn encap_in_place(
+        &mut self,
+        buffer: &mut EncapBuffer,
+        frag_id: u8,
+        metadata: EncapMetadata,
+        extensions: &[Extension],
+        max_pkt_len: usize,
+    ) -> Result<EncapStatus, EncapError> {
+        let result = self.write_in_place(buffer, frag_id, metadata, extensions, max_pkt_len);
+        self.count(Some(&metadata), &result);
+        result
+    }
+
+    fn write_in_place(
+        &mut self,
+        buffer: &mut EncapBuffer,
+        frag_id: u8,
+        metadata: EncapMetadata,
+        extensions: &[Extension],
+        max_pkt_len: usize,
+    ) -> Result<EncapStatus, EncapError> {
+        let protocol_type = metadata.protocol_type;
+
+        // check label
+        if metadata.label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
+            return Err(EncapError::ErrorInvalidLabel);
+        }
+        let extensions_len = if extensions.is_empty() {
+            // check protocol_type is valid, i.e. not in range [SECOND_RANGE_PTYPE, MAX_MANDATORY_VAL_PTYPE] = [256, 1535]
+            if (MAX_MANDATORY_VAL_PTYPE..SECOND_RANGE_PTYPE).contains(&protocol_type) {
+                return Err(EncapError::ErrorProtocolType);
+            }
+            0
+        } else {
+            check_extensions(protocol_type, extensions)?
+        };
+
+        let label = self.check_label_re_use(metadata.label);
+        let label_len = label.len();
+        let pdu_len = buffer.pdu_end - buffer.pdu_start;
+        let fields_len = PROTOCOL_LEN + label_len + extensions_len;
+        let total_len = self
+            .extension_coverage
+            .total_len(pdu_len, label_len, extensions_len);
+
+        let (pkt_type, header_len, pdu_len_encapsulated) =
+            if FIXED_HEADER_LEN + fields_len + pdu_len <= max_pkt_len
+                && fields_len + pdu_len <= GSE_LEN_MAX
+            {
+                (PktType::CompletePkt, FIXED_HEADER_LEN + fields_len, pdu_len)
+            } else {
+                let header_len = FIXED_HEADER_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN + fields_len;
+                // check the room for the header and the length of the pdu
+                if max_pkt_len < header_len {
+                    return Err(EncapError::ErrorSizeBuffer);
+                }
+                if TOTAL_LEN_MAX < total_len {
+                    return Err(EncapError::ErrorPduLength);
+                }
+                let pdu_len_available = (max_pkt_len - header_len)
+                    .min((GSE_LEN_MAX + FIXED_HEADER_LEN).saturating_sub(header_len));
+                (
+                    PktType::FirstFragPkt,
+                    header_len,
+                    pdu_len_available.min(pdu_len),
+                )
+            };
+        if buffer.pdu_start < header_len {
+            return Err(EncapError::ErrorSizeBuffer);
+        }
+
+        // write the header in the headroom
+        let pkt_start = buffer.pdu_start - header_len;
+        let gse_len = header_len - FIXED_HEADER_LEN + pdu_len_encapsulated;
+        let header = &mut buffer.data[pkt_start..buffer.pdu_start];
+        header[..FIXED_HEADER_LEN].copy_from_slice(
+            &generate_gse_header(&pkt_type, &label.get_type(), gse_len as u16).to_be_bytes(),
+        );
+        let mut offset = FIXED_HEADER_LEN;
+
+        if pkt_type == PktType::FirstFragPkt {
+            header[offset] = frag_id;
+            offset += FRAG_ID_LEN;
+            header[offset..offset + TOTAL_LENGTH_LEN]
+                .copy_from_slice(&(total_len as u16).to_be_bytes());
+            offset += TOTAL_LENGTH_LEN;
+        }
+        let header = &mut buffer.data[pkt_start + offset..buffer.pdu_start];
+        write_header_fields(header, protocol_type, &label, extensions);
+
+        let encap_status = match pkt_type {
+            PktType::FirstFragPkt => {
+                // the crc of the whole pdu starts with the header fields written, the header extensions follow the label
+                let extensions_start = pkt_start + offset + PROTOCOL_LEN + label_len;
+                let crc = self.extension_coverage.init_crc32(
+                    &self.crc_calculator,
+                    extensions.first().map_or(protocol_type, Extension::id),
+                    protocol_type,
+                    total_len as u16,
+                    label.get_bytes(),
+                    &buffer.data[extensions_start..buffer.pdu_start],
+                );
+                let pdu = &buffer.data[buffer.pdu_start..buffer.pdu_end];
+                let context_frag = ContextFrag {
+                    frag_id,
+                    crc: FragCrc::Final(FragCrc::Partial(crc).finalize(
+                        &self.crc_calculator,
+                        &[pdu],
+                        0,
+                        pdu_len,
+                    )),
+                    len_pdu_frag: pdu_len_encapsulated as u16,
+                };
+                EncapStatus::FragmentedPkt((header_len + pdu_len_encapsulated) as u16, context_frag)
+            }
+            _ => EncapStatus::CompletedPkt((header_len + pdu_len_encapsulated) as u16),
+        };
+
+        buffer.pkt_start = pkt_start;
+        buffer.pkt_end = buffer.pdu_start + pdu_len_encapsulated;
+        Ok(encap_status)
+    }
+
+    /// GSE encapsulation in place of the next fragment of the pdu of an [`EncapBuffer`]
+    ///
+    /// The header of the fragment is written over the end of the previous packet, which must have been sent or copied before.
+    /// If the rest of the pdu fits into `max_pkt_len` bytes, the end fragment is built with its crc in the tailroom
+    /// and the function returns the status completed packet.
+    /// Else, an intermediate fragment is built and a new context of fragmentation is returned with the status fragmented packet.
+    pub fn encap_frag_in_place(
+        &self,
+        buffer: &mut EncapBuffer,
+        context: &ContextFrag,
+        max_pkt_len: usize,
+    ) -> Result<EncapStatus, EncapError> {
+        let result = self.write_frag_in_place(buffer, context, max_pkt_len);
+        self.count(None, &result);
+        result
+    }
+
+    fn write_frag_in_place(
+        &self,
+        buffer: &mut EncapBuffer,
+        context: &ContextFrag,
+        max_pkt_len: usize,
+    ) -> Result<EncapStatus, EncapError> {
+        let len_pdu_frag = context.len_pdu_frag as usize;
+        let pdu_len = buffer.pdu_end - buffer.pdu_start;
+        if len_pdu_frag > pdu_len {
+            return Err(EncapError::ErrorPduLength);
+        }
+        let fragment_start = buffer.pdu_start + len_pdu_frag;
+        let pdu_len_remaining = pdu_len - len_pdu_frag;
+        if fragment_start < FRAG_HEADER_LEN || max_pkt_len <= FRAG_HEADER_LEN {
+            return Err(EncapError::ErrorSizeBuffer);
+        }
+
+        let gse_end_len = FRAG_ID_LEN + pdu_len_remaining + CRC_LEN;
+        let (pkt_type, pdu_len_encapsulated, encap_status) =
+            if FIXED_HEADER_LEN + gse_end_len <= max_pkt_len {
+                // end packet, the crc is written in the tailroom
+                if buffer.data.len() - buffer.pdu_end < CRC_LEN {
+                    return Err(EncapError::ErrorSizeBuffer);
+                }
+                let crc = context.crc.finalize(
+                    &self.crc_calculator,
+                    &[&buffer.data[fragment_start..buffer.pdu_end]],
+                    0,
+                    pdu_len_remaining,
+                );
+                buffer.data[buffer.pdu_end..buffer.pdu_end + CRC_LEN]
+                    .copy_from_slice(&crc.to_be_bytes());
+                (
+                    PktType::EndFragPkt,
+                    pdu_len_remaining,
+                    EncapStatus::CompletedPkt((FIXED_HEADER_LEN + gse_end_len) as u16),
+                )
+            } else {
+                let pdu_len_encapsulated = (max_pkt_len - FRAG_HEADER_LEN)
+                    .min(GSE_LEN_MAX - FRAG_ID_LEN)
+                    .min(pdu_len_remaining);
+                let fragment_end = fragment_start + pdu_len_encapsulated;
+                let new_context = ContextFrag {
+                    frag_id: context.frag_id,
+                    crc: context.crc.update(
+                        &self.crc_calculator,
+                        &[&buffer.data[fragment_start..fragment_end]],
+                        0,
+                        pdu_len_encapsulated,
+                    ),
+                    len_pdu_frag: (len_pdu_frag + pdu_len_encapsulated) as u16,
+                };
+                (
+                    PktType::IntermediateFragPkt,
+                    pdu_len_encapsulated,
+                    EncapStatus::FragmentedPkt(
+                        (FRAG_HEADER_LEN + pdu_len_encapsulated) as u16,
+                        new_context,
+                    ),
+                )
+            };
+
+        // write the header over the end of the previous packet
+        let pkt_start = fragment_start - FRAG_HEADER_LEN;
+        let gse_len = match pkt_type {
+            PktType::EndFragPkt => gse_end_len,
+            _ => FRAG_ID_LEN + pdu_len_encapsulated,
+        };
+        let header = generate_gse_header(&pkt_type, &LabelType::ReUse, gse_len as u16);
+        buffer.data[pkt_start..pkt_start + FIXED_HEADER_LEN].copy_from_slice(&header.to_be_bytes());
+        buffer.data[pkt_start + FIXED_HEADER_LEN] = context.frag_id;
+
+        buffer.pkt_start = pkt_start;
+        buffer.pkt_end = pkt_start + FIXED_HEADER_LEN + gse_len;
+        Ok(encap_status)
+    }
+}
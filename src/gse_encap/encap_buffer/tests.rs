@@ -0,0 +1,246 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::DefaultCrc;
+use crate::gse_decap::{DecapMetadata, DecapStatus, Decapsulator, GseDecapMemory, SimpleGseMemory};
+use crate::gse_encap::{
+    EncapBuffer, EncapError, EncapMetadata, EncapStatus, Encapsulator, MAX_HEADER_LEN,
+};
+use crate::header_extension::{
+    Extension, ExtensionCoverage, SimpleMandatoryExtensionHeaderManager,
+};
+use crate::label::Label;
+
+const PDU: &[u8; 52] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn new_buffer(headroom: usize, tailroom: usize) -> EncapBuffer {
+    let data = vec![0; headroom + PDU.len() + tailroom].into_boxed_slice();
+    let mut buffer = EncapBuffer::new(data, headroom, PDU.len()).unwrap();
+    buffer.pdu_mut().copy_from_slice(PDU);
+    buffer
+}
+
+/// Build all the packets of the pdu in place, return them back to back
+fn encap_all_in_place(
+    metadata: EncapMetadata,
+    extensions: &[Extension],
+    buffer: &mut EncapBuffer,
+    max_pkt_len: usize,
+) -> Result<Vec<u8>, EncapError> {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packets = vec![];
+    let mut status = encapsulator.encap_in_place(buffer, 5, metadata, extensions, max_pkt_len)?;
+    loop {
+        packets.extend_from_slice(buffer.packet());
+        match status {
+            EncapStatus::CompletedPkt(len) => {
+                assert_eq!(len as usize, buffer.packet().len());
+                return Ok(packets);
+            }
+            EncapStatus::FragmentedPkt(len, context) => {
+                assert_eq!(len as usize, buffer.packet().len());
+                status = encapsulator.encap_frag_in_place(buffer, &context, max_pkt_len)?;
+            }
+        }
+    }
+}
+
+/// Decapsulate all the packets of the buffer, return the last status
+fn decap_all(buffer: &[u8]) -> DecapStatus {
+    let mut memory = SimpleGseMemory::new(1, PDU.len(), 0, 0);
+    memory
+        .provision_storage(vec![0; PDU.len()].into_boxed_slice())
+        .unwrap();
+    let mut decapsulator = Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+    );
+    let mut offset = 0;
+    loop {
+        let (status, pkt_len) = decapsulator.decap(&buffer[offset..]).unwrap();
+        offset += pkt_len;
+        if offset == buffer.len() {
+            return status;
+        }
+    }
+}
+
+#[test]
+fn test_encap_in_place_001() {
+    let comment = "complete packet built in place is the same as the one copied";
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let mut buffer = new_buffer(MAX_HEADER_LEN, 0);
+
+    let obs = encap_all_in_place(metadata, &[], &mut buffer, 100).unwrap();
+
+    let mut exp = [0; 59];
+    Encapsulator::new(DefaultCrc {})
+        .encap(PDU, 5, metadata, &mut exp)
+        .unwrap();
+    assert_eq!(exp.to_vec(), obs, "{}", comment);
+    assert_eq!(PDU, buffer.pdu(), "{}", comment);
+}
+
+#[test]
+fn test_encap_in_place_002() {
+    let comment = "complete packet with header extensions built in place";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let extensions = vec![
+        Extension::new(0x0300, &[1, 2, 3, 4]).unwrap(),
+        Extension::new(0x0100, &[]).unwrap(),
+    ];
+    let mut buffer = new_buffer(MAX_HEADER_LEN + 8, 0);
+
+    let obs = encap_all_in_place(metadata, &extensions, &mut buffer, 100).unwrap();
+
+    let mut exp = [0; 70];
+    let status = Encapsulator::new(DefaultCrc {})
+        .encap_ext(PDU, 5, metadata, &mut exp, extensions)
+        .unwrap();
+    assert_eq!(EncapStatus::CompletedPkt(70), status, "{}", comment);
+    assert_eq!(exp.to_vec(), obs, "{}", comment);
+}
+
+#[test]
+fn test_encap_in_place_003() {
+    let comment = "pdu fragmented in place, the crc is written in the tailroom";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let mut buffer = new_buffer(MAX_HEADER_LEN, 4);
+
+    let packets = encap_all_in_place(metadata, &[], &mut buffer, 20).unwrap();
+
+    // first fragment of 20 bytes, 2 intermediate fragments of 20 bytes and an end fragment
+    assert_eq!(20 + 20 + 20 + 3 + 11 + 4, packets.len(), "{}", comment);
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::SixBytesLabel(*b"012345"), vec![]),
+    );
+    assert_eq!(exp_status, decap_all(&packets), "{}", comment);
+}
+
+#[test]
+fn test_encap_in_place_004() {
+    let comment = "packets built in place are the same as the ones copied";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    for max_pkt_len in [10, 17, 33, 55, 56, 57] {
+        let mut buffer = new_buffer(MAX_HEADER_LEN, 4);
+        let obs = encap_all_in_place(metadata, &[], &mut buffer, max_pkt_len).unwrap();
+
+        let encapsulator = &mut Encapsulator::new(DefaultCrc {});
+        let mut exp = vec![];
+        let mut packet = vec![0; max_pkt_len];
+        let mut status = encapsulator.encap(PDU, 5, metadata, &mut packet);
+        while let Ok(EncapStatus::FragmentedPkt(len, context)) = status {
+            exp.extend_from_slice(&packet[..len as usize]);
+            status = encapsulator.encap_frag(PDU, &context, &mut packet);
+        }
+        let Ok(EncapStatus::CompletedPkt(len)) = status else {
+            panic!("{}", comment)
+        };
+        exp.extend_from_slice(&packet[..len as usize]);
+        assert_eq!(exp, obs, "{}: {}", comment, max_pkt_len);
+    }
+}
+
+#[test]
+fn test_encap_in_place_005() {
+    let comment = "headroom or tailroom too small";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+
+    let mut buffer = new_buffer(9, 4);
+    let obs = encap_all_in_place(metadata, &[], &mut buffer, 100);
+    assert_eq!(Err(EncapError::ErrorSizeBuffer), obs, "{}", comment);
+    let obs = encap_all_in_place(metadata, &[], &mut new_buffer(10, 0), 100);
+    assert!(obs.is_ok(), "{}", comment);
+
+    let obs = encap_all_in_place(metadata, &[], &mut new_buffer(MAX_HEADER_LEN, 3), 20);
+    assert_eq!(Err(EncapError::ErrorSizeBuffer), obs, "{}", comment);
+
+    let obs = encap_all_in_place(metadata, &[], &mut new_buffer(MAX_HEADER_LEN, 4), 12);
+    assert_eq!(Err(EncapError::ErrorSizeBuffer), obs, "{}", comment);
+
+    let data = vec![0; 10].into_boxed_slice();
+    assert_eq!(
+        Err(EncapError::ErrorSizeBuffer),
+        EncapBuffer::new(data, 5, 6),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_encap_in_place_006() {
+    let comment = "fragments with a header extension as written by encap_ext, for each coverage";
+    let metadata = EncapMetadata::new(0x0800, Label::ThreeBytesLabel(*b"abc"));
+    let extensions = [Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+
+    for coverage in [ExtensionCoverage::Standard, ExtensionCoverage::Legacy] {
+        let mut encapsulator = Encapsulator::new(DefaultCrc {});
+        encapsulator.set_extension_coverage(coverage);
+
+        let mut exp = [0; 30];
+        let Ok(EncapStatus::FragmentedPkt(_, exp_context)) =
+            encapsulator
+                .clone()
+                .encap_ext(PDU, 5, metadata, &mut exp, extensions.to_vec())
+        else {
+            panic!("{}: {:?}", comment, coverage)
+        };
+
+        let mut buffer = new_buffer(MAX_HEADER_LEN + 6, 4);
+        let obs_status = encapsulator.encap_in_place(&mut buffer, 5, metadata, &extensions, 30);
+
+        assert_eq!(
+            Ok(EncapStatus::FragmentedPkt(30, exp_context)),
+            obs_status,
+            "{}: {:?}",
+            comment,
+            coverage
+        );
+        assert_eq!(exp, buffer.packet(), "{}: {:?}", comment, coverage);
+    }
+}
+
+#[test]
+fn test_encap_in_place_007() {
+    let comment = "same header extensions accepted in place as by encap_ext";
+    let metadata = EncapMetadata::new(0x0081, Label::ThreeBytesLabel(*b"abc"));
+
+    for extensions in [
+        vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()],
+        vec![
+            Extension::new(0x0400, &[1, 2, 3, 4, 5, 6]).unwrap(),
+            Extension::new(0x0081, &[7, 8]).unwrap(),
+        ],
+    ] {
+        let mut buffer = new_buffer(MAX_HEADER_LEN + 8, 0);
+        let obs = encap_all_in_place(metadata, &extensions, &mut buffer, 100);
+
+        let mut exp = [0; 100];
+        let Ok(EncapStatus::CompletedPkt(len)) = Encapsulator::new(DefaultCrc {}).encap_ext(
+            PDU,
+            5,
+            metadata,
+            &mut exp,
+            extensions.clone(),
+        ) else {
+            panic!("{}: {:?}", comment, extensions)
+        };
+        assert_eq!(
+            Ok(exp[..len as usize].to_vec()),
+            obs,
+            "{}: {:?}",
+            comment,
+            extensions
+        );
+    }
+
+    let mut buffer = new_buffer(MAX_HEADER_LEN, 0);
+    let obs = encap_all_in_place(metadata, &[], &mut buffer, 100);
+    let mut exp = [0; 59];
+    Encapsulator::new(DefaultCrc {})
+        .encap(PDU, 5, metadata, &mut exp)
+        .unwrap();
+    assert_eq!(Ok(exp.to_vec()), obs, "{}", comment);
+}
@@ -0,0 +1,248 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for scheduling the fragmentation of several pdus
+//!
+//! A GSE receiver identifies the fragments of a pdu by their frag id, so two pdus partially sent at the same time
+//! must never share a frag id.
+//! [`FragIdAllocator`] owns the pool of the 256 frag ids and gives them out in a round robin order.
+//! [`FragScheduler`] keeps the pdus partially sent with their context of fragmentation,
+//! allowing the fragments of different pdus to be interleaved across frames.
+//! The frag id of a pdu is recycled once its end packet has been written.
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeMap;
+
+use crate::crc::CrcCalculator;
+use crate::gse_encap::{ContextFrag, EncapError, EncapMetadata, EncapStatus, Encapsulator};
+use crate::pkt_type::PktType;
+
+const FRAG_ID_COUNT: usize = u8::MAX as usize + 1;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Pool of the 256 frag ids
+///
+/// The ids are allocated in a round robin order, so that an id just released is not immediately reused.
+pub struct FragIdAllocator {
+    used: [bool; FRAG_ID_COUNT],
+    allocated: usize,
+    next: u8,
+}
+
+impl Default for FragIdAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FragIdAllocator {
+    /// FragIdAllocator constructor, all the frag ids are available
+    pub fn new() -> FragIdAllocator {
+        FragIdAllocator {
+            used: [false; FRAG_ID_COUNT],
+            allocated: 0,
+            next: 0,
+        }
+    }
+
+    /// Return the next frag id available without allocating it
+    pub fn peek(&self) -> Option<u8> {
+        if self.allocated == FRAG_ID_COUNT {
+            return None;
+        }
+        let mut frag_id = self.next;
+        while self.used[frag_id as usize] {
+            frag_id = frag_id.wrapping_add(1);
+        }
+        Some(frag_id)
+    }
+
+    /// Allocate a frag id, return None if the 256 frag ids are used
+    pub fn allocate(&mut self) -> Option<u8> {
+        let frag_id = self.peek()?;
+        self.used[frag_id as usize] = true;
+        self.allocated += 1;
+        self.next = frag_id.wrapping_add(1);
+        Some(frag_id)
+    }
+
+    /// Give back a frag id to the pool
+    ///
+    /// Return false if the frag id was not allocated.
+    pub fn release(&mut self, frag_id: u8) -> bool {
+        if !self.used[frag_id as usize] {
+            return false;
+        }
+        self.used[frag_id as usize] = false;
+        self.allocated -= 1;
+        true
+    }
+
+    pub fn is_allocated(&self, frag_id: u8) -> bool {
+        self.used[frag_id as usize]
+    }
+
+    /// Number of frag ids allocated
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+}
+
+/// Structure FragScheduler
+///
+/// The scheduler owns the frag ids and the pdus partially sent.
+/// A pdu is given to [`FragScheduler::encap`], which allocates a frag id for it.
+/// If the pdu is fragmented, the scheduler keeps it until its end packet is written by [`FragScheduler::encap_frag`]
+/// or [`FragScheduler::encap_next_frag`], then its frag id is released.
+///
+/// The fragments of the pdus in flight can be sent in any order, in the same frame or in different frames.
+#[derive(Debug, Default)]
+pub struct FragScheduler {
+    frag_ids: FragIdAllocator,
+    pdus: BTreeMap<u8, (Box<[u8]>, ContextFrag)>,
+    next: u8,
+}
+
+impl FragScheduler {
+    /// FragScheduler constructor
+    pub fn new() -> FragScheduler {
+        FragScheduler {
+            frag_ids: FragIdAllocator::new(),
+            pdus: BTreeMap::new(),
+            next: 0,
+        }
+    }
+
+    /// Number of pdus partially sent
+    pub fn len(&self) -> usize {
+        self.pdus.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pdus.is_empty()
+    }
+
+    /// Return the frag ids of the pdus partially sent, in increasing order
+    pub fn frag_ids(&self) -> Vec<u8> {
+        self.pdus.keys().copied().collect()
+    }
+
+    /// Return the context of fragmentation of a pdu partially sent
+    pub fn context(&self, frag_id: u8) -> Option<&ContextFrag> {
+        self.pdus.get(&frag_id).map(|(_, context)| context)
+    }
+
+    /// Encapsulate a new pdu in the buffer
+    ///
+    /// A frag id is allocated for the pdu, and kept with the pdu if it is fragmented.
+    /// If the pdu can not be encapsulated, the error is returned with the pdu.
+    /// If the pdu must be fragmented but the 256 frag ids are used, it returns `ErrorFragIdExhausted`.
+    ///
+    /// # Example of interleaving the fragments of two pdus
+    /// ```
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::label::Label;
+    /// use dvb_gse_rust::gse_encap::{Encapsulator, EncapMetadata, EncapStatus, FragScheduler};
+    ///
+    /// let metadata = EncapMetadata::new(0xFFFF, Label::Broadcast);
+    /// let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    /// let mut scheduler = FragScheduler::new();
+    /// let mut buffer = [0; 20];
+    ///
+    /// // first fragments
+    /// let status = scheduler.encap(&mut encapsulator, Box::new(*b"abcdefghijklmnopqrstuvwxyz"), metadata, &mut buffer);
+    /// assert!(matches!(status, Ok(EncapStatus::FragmentedPkt(_, _))));
+    /// let status = scheduler.encap(&mut encapsulator, Box::new(*b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"), metadata, &mut buffer);
+    /// assert!(matches!(status, Ok(EncapStatus::FragmentedPkt(_, _))));
+    /// assert_eq!(scheduler.frag_ids(), vec![0, 1]);
+    ///
+    /// // end fragments, in the reverse order
+    /// let status = scheduler.encap_frag(&encapsulator, 1, &mut buffer);
+    /// assert!(matches!(status, Ok(EncapStatus::CompletedPkt(_))));
+    /// let status = scheduler.encap_frag(&encapsulator, 0, &mut buffer);
+    /// assert!(matches!(status, Ok(EncapStatus::CompletedPkt(_))));
+    /// assert!(scheduler.is_empty());
+    /// ```
+    pub fn encap<C: CrcCalculator>(
+        &mut self,
+        encapsulator: &mut Encapsulator<C>,
+        pdu: Box<[u8]>,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, (EncapError, Box<[u8]>)> {
+        let frag_id = match self.frag_ids.peek() {
+            Some(frag_id) => frag_id,
+            None => match encapsulator.encap_preview(&pdu, metadata, buffer) {
+                // the frag id is not used by a complete packet
+                Ok(preview) if preview.pkt_type() == PktType::CompletePkt => 0,
+                Ok(_) => return Err((EncapError::ErrorFragIdExhausted, pdu)),
+                Err(err) => return Err((err, pdu)),
+            },
+        };
+
+        match encapsulator.encap(&pdu, frag_id, metadata, buffer) {
+            Ok(EncapStatus::FragmentedPkt(pkt_len, context)) => {
+                self.frag_ids.allocate();
+                self.pdus.insert(frag_id, (pdu, context));
+                Ok(EncapStatus::FragmentedPkt(pkt_len, context))
+            }
+            Ok(status) => Ok(status),
+            Err(err) => Err((err, pdu)),
+        }
+    }
+
+    /// Encapsulate the next fragment of the pdu identified by `frag_id` in the buffer
+    ///
+    /// When the end packet is written, the pdu is dropped and its frag id released.
+    /// If no pdu is partially sent with this frag id, it returns `ErrorUnknownFragId`.
+    pub fn encap_frag<C: CrcCalculator>(
+        &mut self,
+        encapsulator: &Encapsulator<C>,
+        frag_id: u8,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let Some((pdu, context)) = self.pdus.get_mut(&frag_id) else {
+            return Err(EncapError::ErrorUnknownFragId);
+        };
+
+        let status = encapsulator.encap_frag(pdu, context, buffer)?;
+        match status {
+            EncapStatus::FragmentedPkt(_, new_context) => *context = new_context,
+            EncapStatus::CompletedPkt(_) => {
+                self.pdus.remove(&frag_id);
+                self.frag_ids.release(frag_id);
+            }
+        }
+        Ok(status)
+    }
+
+    /// Encapsulate the next fragment of the pdus partially sent, in a round robin order
+    ///
+    /// Return None if there is no pdu partially sent.
+    pub fn encap_next_frag<C: CrcCalculator>(
+        &mut self,
+        encapsulator: &Encapsulator<C>,
+        buffer: &mut [u8],
+    ) -> Option<Result<EncapStatus, EncapError>> {
+        let frag_id = self
+            .pdus
+            .range(self.next..)
+            .next()
+            .or_else(|| self.pdus.iter().next())
+            .map(|(frag_id, _)| *frag_id)?;
+
+        let status = self.encap_frag(encapsulator, frag_id, buffer);
+        if status.is_ok() {
+            self.next = frag_id.wrapping_add(1);
+        }
+        Some(status)
+    }
+
+    /// Stop the fragmentation of a pdu, its frag id is released and the pdu returned
+    pub fn abort(&mut self, frag_id: u8) -> Option<Box<[u8]>> {
+        let (pdu, _) = self.pdus.remove(&frag_id)?;
+        self.frag_ids.release(frag_id);
+        Some(pdu)
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::{CrcCalculator, DefaultCrc};
+use crate::gse_encap::{EncapError, EncapMetadata, EncapStatus, Encapsulator};
+use crate::gse_encap::{FragIdAllocator, FragScheduler};
+use crate::gse_standard::{CRC_LEN, FIRST_FRAG_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, PROTOCOL_LEN};
+use crate::label::Label;
+use crate::utils::{GseEndFragPacket, GseFirstFragPacket, GseIntermediatePacket, Serialisable};
+
+const PDU1: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+const PDU2: &[u8; 26] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const PROTOCOL_TYPE: u16 = 0xFFFF;
+
+fn metadata() -> EncapMetadata {
+    EncapMetadata::new(PROTOCOL_TYPE, Label::Broadcast)
+}
+
+/// Allocator test: the frag ids are given in a round robin order
+#[test]
+fn test_frag_id_allocator_001() {
+    let mut allocator = FragIdAllocator::new();
+
+    assert_eq!(Some(0), allocator.allocate());
+    assert_eq!(Some(1), allocator.allocate());
+    assert!(allocator.release(0));
+    assert!(!allocator.release(0), "released twice");
+
+    // the released id is not reused immediately
+    assert_eq!(Some(2), allocator.allocate());
+    assert!(allocator.is_allocated(1));
+    assert!(!allocator.is_allocated(0));
+    assert_eq!(2, allocator.allocated());
+}
+
+/// Allocator test: the pool is exhausted after 256 allocations
+#[test]
+fn test_frag_id_allocator_002() {
+    let mut allocator = FragIdAllocator::new();
+
+    for frag_id in 0..=u8::MAX {
+        assert_eq!(Some(frag_id), allocator.allocate());
+    }
+    assert_eq!(None, allocator.peek());
+    assert_eq!(None, allocator.allocate());
+
+    // the only id available is given even if it is behind the round robin position
+    allocator.release(42);
+    assert_eq!(Some(42), allocator.peek());
+    assert_eq!(Some(42), allocator.allocate());
+    assert_eq!(256, allocator.allocated());
+}
+
+/// Scheduler test: the fragments of two pdus are interleaved and the frag ids recycled
+#[test]
+fn test_frag_scheduler_001() {
+    const FIRST_LEN: usize = 20;
+    const INTER_LEN: usize = 10;
+    const PDU1_FIRST: usize = FIRST_LEN - FIRST_FRAG_LEN;
+    const PDU1_INTER: usize = INTER_LEN - FIXED_HEADER_LEN - FRAG_ID_LEN;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut scheduler = FragScheduler::new();
+
+    let mut buffer = [0; FIRST_LEN];
+    let status = scheduler.encap(&mut encapsulator, Box::new(*PDU1), metadata(), &mut buffer);
+    assert!(matches!(status, Ok(EncapStatus::FragmentedPkt(20, _))));
+    let mut exp_buffer = [0; FIRST_LEN];
+    GseFirstFragPacket::new(
+        (FIRST_LEN - FIXED_HEADER_LEN) as u16,
+        0,
+        (PDU1.len() + PROTOCOL_LEN) as u16,
+        PROTOCOL_TYPE,
+        Label::Broadcast,
+        &PDU1[..PDU1_FIRST],
+    )
+    .generate(&mut exp_buffer).unwrap();
+    assert_eq!(exp_buffer, buffer);
+
+    let status = scheduler.encap(&mut encapsulator, Box::new(*PDU2), metadata(), &mut buffer);
+    assert!(matches!(status, Ok(EncapStatus::FragmentedPkt(20, _))));
+    assert_eq!(vec![0, 1], scheduler.frag_ids());
+    assert_eq!(
+        Some(PDU1_FIRST),
+        scheduler.context(0).map(|c| c.len_pdu_frag() as usize)
+    );
+
+    // intermediate packet of the first pdu
+    let mut buffer = [0; INTER_LEN];
+    let status = scheduler.encap_next_frag(&encapsulator, &mut buffer);
+    assert!(matches!(
+        status,
+        Some(Ok(EncapStatus::FragmentedPkt(10, _)))
+    ));
+    let mut exp_buffer = [0; INTER_LEN];
+    GseIntermediatePacket::new(
+        (INTER_LEN - FIXED_HEADER_LEN) as u16,
+        0,
+        &PDU1[PDU1_FIRST..PDU1_FIRST + PDU1_INTER],
+    )
+    .generate(&mut exp_buffer).unwrap();
+    assert_eq!(exp_buffer, buffer);
+
+    // end packet of the second pdu, then of the first one
+    let mut buffer = [0; 100];
+    let status = scheduler.encap_next_frag(&encapsulator, &mut buffer);
+    assert!(matches!(status, Some(Ok(EncapStatus::CompletedPkt(_)))));
+    assert_eq!(vec![0], scheduler.frag_ids());
+
+    let mut buffer = [0; 100];
+    let status = scheduler.encap_next_frag(&encapsulator, &mut buffer);
+    let Some(Ok(EncapStatus::CompletedPkt(pkt_len))) = status else {
+        panic!("unexpected status {:?}", status);
+    };
+    let crc = DefaultCrc {}.calculate_crc32(
+        PDU1,
+        PROTOCOL_TYPE,
+        (PDU1.len() + PROTOCOL_LEN) as u16,
+        Label::Broadcast.get_bytes(),
+    );
+    let data = &PDU1[PDU1_FIRST + PDU1_INTER..];
+    let mut exp_buffer = [0; 100];
+    GseEndFragPacket::new((FRAG_ID_LEN + data.len() + CRC_LEN) as u16, 0, data, crc)
+        .generate(&mut exp_buffer).unwrap();
+    assert_eq!(
+        FIXED_HEADER_LEN + FRAG_ID_LEN + data.len() + CRC_LEN,
+        pkt_len as usize
+    );
+    assert_eq!(exp_buffer, buffer);
+
+    assert!(scheduler.is_empty());
+    assert_eq!(None, scheduler.encap_next_frag(&encapsulator, &mut buffer));
+}
+
+/// Scheduler test: a complete packet doesn't keep a frag id
+#[test]
+fn test_frag_scheduler_002() {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut scheduler = FragScheduler::new();
+    let mut buffer = [0; 100];
+
+    let status = scheduler.encap(&mut encapsulator, Box::new(*PDU1), metadata(), &mut buffer);
+    assert!(matches!(status, Ok(EncapStatus::CompletedPkt(_))));
+    assert!(scheduler.is_empty());
+
+    let mut buffer = [0; 20];
+    scheduler
+        .encap(&mut encapsulator, Box::new(*PDU2), metadata(), &mut buffer)
+        .unwrap();
+    assert_eq!(vec![0], scheduler.frag_ids());
+}
+
+/// Scheduler test: errors give back the pdu, unknown frag id and abort
+#[test]
+fn test_frag_scheduler_003() {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut scheduler = FragScheduler::new();
+
+    let mut buffer = [0; FIRST_FRAG_LEN - 1];
+    let status = scheduler.encap(&mut encapsulator, Box::new(*PDU1), metadata(), &mut buffer);
+    assert_eq!(
+        Err((EncapError::ErrorSizeBuffer, Box::from(&PDU1[..]))),
+        status
+    );
+    assert!(scheduler.is_empty());
+
+    let mut buffer = [0; 20];
+    assert_eq!(
+        Err(EncapError::ErrorUnknownFragId),
+        scheduler.encap_frag(&encapsulator, 0, &mut buffer)
+    );
+
+    scheduler
+        .encap(&mut encapsulator, Box::new(*PDU1), metadata(), &mut buffer)
+        .unwrap();
+    assert_eq!(Some(Box::from(&PDU1[..])), scheduler.abort(0));
+    assert_eq!(None, scheduler.abort(0));
+    assert!(scheduler.is_empty());
+}
+
+/// Scheduler test: when the 256 frag ids are used, only complete packets are accepted
+#[test]
+fn test_frag_scheduler_004() {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut scheduler = FragScheduler::new();
+    let mut buffer = [0; 20];
+
+    for _ in 0..=u8::MAX {
+        scheduler
+            .encap(&mut encapsulator, Box::new(*PDU1), metadata(), &mut buffer)
+            .unwrap();
+    }
+    assert_eq!(256, scheduler.len());
+
+    let status = scheduler.encap(&mut encapsulator, Box::new(*PDU2), metadata(), &mut buffer);
+    assert_eq!(
+        Err((EncapError::ErrorFragIdExhausted, Box::from(&PDU2[..]))),
+        status
+    );
+
+    let status = scheduler.encap(
+        &mut encapsulator,
+        Box::new(*b"abc"),
+        metadata(),
+        &mut buffer,
+    );
+    assert!(matches!(status, Ok(EncapStatus::CompletedPkt(_))));
+}
+
+/// Scheduler test: when the 256 frag ids are used, a pdu that fits in a complete packet thanks to the re use label is accepted
+#[test]
+fn test_frag_scheduler_005() {
+    const LABEL: Label = Label::SixBytesLabel(*b"012345");
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut scheduler = FragScheduler::new();
+    let mut buffer = [0; 20];
+
+    for _ in 0..=u8::MAX {
+        scheduler
+            .encap(&mut encapsulator, Box::new(*PDU1), metadata(), &mut buffer)
+            .unwrap();
+    }
+    assert_eq!(256, scheduler.len());
+
+    let metadata = EncapMetadata::new(PROTOCOL_TYPE, LABEL);
+    let status = scheduler.encap(&mut encapsulator, Box::new(*b"abc"), metadata, &mut buffer);
+    assert!(matches!(status, Ok(EncapStatus::CompletedPkt(_))));
+
+    // too large with the label, but not with the re use label
+    let pdu = Box::from(&PDU2[..16]);
+    let status = scheduler.encap(&mut encapsulator, pdu, metadata, &mut buffer);
+    assert_eq!(Ok(EncapStatus::CompletedPkt(20)), status);
+}
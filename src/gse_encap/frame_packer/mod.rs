@@ -0,0 +1,314 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for packing GSE packets into a base band frame
+//!
+//! This module fills the whole data field of a base band frame with GSE packets.
+//! The pdus waiting to be sent are queued in a [`FramePacker`] and written one after the other with the
+//! [`Encapsulator`], the last pdu being fragmented if it doesn't fit in the remaining space.
+//! The fragmentation context is kept by the packer and the next fragments are sent first in the following frames.
+//! The end of the data field is filled with padding.
+#[cfg(test)]
+mod tests;
+
+use std::collections::VecDeque;
+
+use crate::crc::CrcCalculator;
+use crate::gse_encap::{
+    ContextFrag, EncapError, EncapMetadata, EncapStatus, Encapsulator, FragIdAllocator,
+};
+use crate::gse_standard::{
+    FIXED_HEADER_LEN, GSE_LEN_MAX, MAX_MANDATORY_VAL_PTYPE, PROTOCOL_LEN, SECOND_RANGE_PTYPE,
+    TOTAL_LEN_MAX,
+};
+use crate::label::Label;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Define the status of the packing of a base band frame.
+///
+/// *   Pkt count describe the number of gse packets written in the frame
+/// *   Pdu count describe the number of pdus whose last packet has been written in the frame
+/// *   Data len describe the number of bytes used by the gse packets
+/// *   Padding len describe the number of padding bytes written at the end of the frame
+pub struct FrameStatus {
+    pkt_count: usize,
+    pdu_count: usize,
+    data_len: usize,
+    padding_len: usize,
+}
+
+impl FrameStatus {
+    pub fn pkt_count(&self) -> usize {
+        self.pkt_count
+    }
+    pub fn pdu_count(&self) -> usize {
+        self.pdu_count
+    }
+    pub fn data_len(&self) -> usize {
+        self.data_len
+    }
+    pub fn padding_len(&self) -> usize {
+        self.padding_len
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+/// Define the error returned by [`FramePacker::pack`].
+///
+/// *   Error describe why the packing of the frame stopped
+/// *   Status describe the packets written in the frame before the error, the rest of the frame is padded
+/// *   Pdu is the pdu that could not be encapsulated, if any
+pub struct PackError {
+    error: EncapError,
+    status: FrameStatus,
+    pdu: Option<Box<[u8]>>,
+}
+
+impl PackError {
+    pub fn error(&self) -> &EncapError {
+        &self.error
+    }
+    pub fn status(&self) -> FrameStatus {
+        self.status
+    }
+    pub fn into_pdu(self) -> Option<Box<[u8]>> {
+        self.pdu
+    }
+}
+
+/// Structure FramePacker
+///
+/// The packer stores the pdus waiting to be encapsulated and the pdus partially sent.
+/// At each call of [`FramePacker::pack`], the re use label of the encapsulator is reset,
+/// the pending fragments are written first, then the queued pdus in their order of arrival.
+///
+/// The frag ids are given by a [`FragIdAllocator`], the packer never uses twice the same frag id for two pdus partially sent.
+#[derive(Debug, Default)]
+pub struct FramePacker {
+    queue: VecDeque<(Box<[u8]>, EncapMetadata)>,
+    pending: VecDeque<(Box<[u8]>, ContextFrag)>,
+    frag_ids: FragIdAllocator,
+}
+
+impl FramePacker {
+    /// FramePacker constructor
+    pub fn new() -> FramePacker {
+        FramePacker {
+            queue: VecDeque::new(),
+            pending: VecDeque::new(),
+            frag_ids: FragIdAllocator::new(),
+        }
+    }
+
+    /// Add a pdu at the end of the queue
+    ///
+    /// The metadata are checked before the pdu is queued, so that the packing of a frame never fails on them.
+    pub fn push(&mut self, pdu: Box<[u8]>, metadata: EncapMetadata) -> Result<(), EncapError> {
+        if metadata.label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
+            return Err(EncapError::ErrorInvalidLabel);
+        }
+        if (MAX_MANDATORY_VAL_PTYPE..SECOND_RANGE_PTYPE).contains(&metadata.protocol_type) {
+            return Err(EncapError::ErrorProtocolType);
+        }
+        if TOTAL_LEN_MAX < pdu.len() + PROTOCOL_LEN + metadata.label.len() {
+            return Err(EncapError::ErrorPduLength);
+        }
+
+        self.queue.push_back((pdu, metadata));
+        Ok(())
+    }
+
+    /// Number of pdus waiting to be encapsulated, partially sent pdus excluded
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Number of pdus partially sent
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Return true if there is no more pdu to send
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() && self.pending.is_empty()
+    }
+
+    /// Fill the data field of a base band frame
+    ///
+    /// `dfl` is the length of the data field in bytes, the packets are written in `buffer[..dfl]`.
+    /// The pdus are written until the frame is full or the queue is empty, and the rest of the data field is padded with zeros.
+    /// If the buffer is smaller than `dfl`, it returns `ErrorSizeBuffer` and nothing is written.
+    /// If a pdu can not be encapsulated, the packing stops and the error is returned with the status of the frame and the pdu,
+    /// the rest of the data field is still padded and the other pdus are kept for the next frames.
+    ///
+    /// # Example of packing two pdus in a frame
+    /// ```
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::label::Label;
+    /// use dvb_gse_rust::gse_encap::{Encapsulator, EncapMetadata, FramePacker};
+    ///
+    /// let metadata = EncapMetadata::new(0xFFFF, Label::SixBytesLabel(*b"012345"));
+    /// let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    /// let mut packer = FramePacker::new();
+    /// packer.push(Box::new(*b"abcdefghijklmnopqrstuvwxyz"), metadata).unwrap();
+    /// packer.push(Box::new(*b"abcdefghijklmnopqrstuvwxyz"), metadata).unwrap();
+    ///
+    /// let mut frame = [0xFF; 100];
+    /// let status = packer.pack(&mut encapsulator, &mut frame, 80).unwrap();
+    ///
+    /// // The second packet re uses the label of the first one
+    /// assert_eq!(status.pkt_count(), 2);
+    /// assert_eq!(status.data_len(), (2 + 2 + 6 + 26) + (2 + 2 + 26));
+    /// assert_eq!(status.padding_len(), 80 - status.data_len());
+    /// assert!(packer.is_empty());
+    /// ```
+    pub fn pack<C: CrcCalculator>(
+        &mut self,
+        encapsulator: &mut Encapsulator<C>,
+        buffer: &mut [u8],
+        dfl: usize,
+    ) -> Result<FrameStatus, PackError> {
+        let mut status = FrameStatus {
+            pkt_count: 0,
+            pdu_count: 0,
+            data_len: 0,
+            padding_len: 0,
+        };
+
+        if buffer.len() < dfl {
+            return Err(PackError {
+                error: EncapError::ErrorSizeBuffer,
+                status,
+                pdu: None,
+            });
+        }
+
+        // new base band frame
+        encapsulator.reset_last_label();
+
+        let mut is_frame_full = false;
+        let mut error = None;
+
+        // send the pending fragments first
+        let mut pending = VecDeque::with_capacity(self.pending.len());
+        while let Some((pdu, context)) = self.pending.pop_front() {
+            if is_frame_full {
+                pending.push_back((pdu, context));
+                continue;
+            }
+            match Self::encap_frags(encapsulator, &pdu, context, buffer, dfl, &mut status) {
+                Ok(Some(context)) => {
+                    pending.push_back((pdu, context));
+                    is_frame_full = true;
+                }
+                Ok(None) => {
+                    self.frag_ids.release(context.frag_id());
+                }
+                Err(err) => {
+                    self.frag_ids.release(context.frag_id());
+                    error = Some((err, pdu));
+                    is_frame_full = true;
+                }
+            }
+        }
+        self.pending = pending;
+
+        // then the new pdus
+        while !is_frame_full && status.data_len < dfl {
+            let Some((pdu, metadata)) = self.queue.pop_front() else {
+                break;
+            };
+
+            let Some(frag_id) = self.frag_ids.peek() else {
+                // all frag ids are used by partially sent pdus
+                self.queue.push_front((pdu, metadata));
+                break;
+            };
+
+            let offset = status.data_len;
+            let pkt_buffer = &mut buffer[offset..Self::pkt_end(offset, dfl)];
+            match encapsulator.encap(&pdu, frag_id, metadata, pkt_buffer) {
+                Ok(EncapStatus::CompletedPkt(pkt_len)) => {
+                    status.data_len += pkt_len as usize;
+                    status.pkt_count += 1;
+                    status.pdu_count += 1;
+                }
+                Ok(EncapStatus::FragmentedPkt(pkt_len, context)) => {
+                    status.data_len += pkt_len as usize;
+                    status.pkt_count += 1;
+                    match Self::encap_frags(encapsulator, &pdu, context, buffer, dfl, &mut status) {
+                        Ok(Some(context)) => {
+                            self.frag_ids.allocate();
+                            self.pending.push_back((pdu, context));
+                            is_frame_full = true;
+                        }
+                        Ok(None) => (),
+                        Err(err) => {
+                            error = Some((err, pdu));
+                            is_frame_full = true;
+                        }
+                    }
+                }
+                Err(EncapError::ErrorSizeBuffer) => {
+                    self.queue.push_front((pdu, metadata));
+                    is_frame_full = true;
+                }
+                Err(err) => {
+                    error = Some((err, pdu));
+                    is_frame_full = true;
+                }
+            }
+        }
+
+        // padding
+        buffer[status.data_len..dfl].fill(0);
+        status.padding_len = dfl - status.data_len;
+
+        match error {
+            Some((error, pdu)) => Err(PackError {
+                error,
+                status,
+                pdu: Some(pdu),
+            }),
+            None => Ok(status),
+        }
+    }
+
+    /// Write the next fragments of a pdu until the pdu is completely sent or the frame is full
+    ///
+    /// Return the context of fragmentation if the pdu is not completely sent.
+    /// A lack of room ends the writing, the other errors are returned.
+    fn encap_frags<C: CrcCalculator>(
+        encapsulator: &mut Encapsulator<C>,
+        pdu: &[u8],
+        mut context: ContextFrag,
+        buffer: &mut [u8],
+        dfl: usize,
+        status: &mut FrameStatus,
+    ) -> Result<Option<ContextFrag>, EncapError> {
+        loop {
+            let offset = status.data_len;
+            let pkt_buffer = &mut buffer[offset..Self::pkt_end(offset, dfl)];
+            match encapsulator.encap_frag(pdu, &context, pkt_buffer) {
+                Ok(EncapStatus::CompletedPkt(pkt_len)) => {
+                    status.data_len += pkt_len as usize;
+                    status.pkt_count += 1;
+                    status.pdu_count += 1;
+                    return Ok(None);
+                }
+                Ok(EncapStatus::FragmentedPkt(pkt_len, new_context)) => {
+                    status.data_len += pkt_len as usize;
+                    status.pkt_count += 1;
+                    context = new_context;
+                }
+                Err(EncapError::ErrorSizeBuffer) => return Ok(Some(context)),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Return the end of the buffer available for the next packet, limited by the maximum gse length
+    fn pkt_end(offset: usize, dfl: usize) -> usize {
+        dfl.min(offset + FIXED_HEADER_LEN + GSE_LEN_MAX)
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::{CrcCalculator, DefaultCrc};
+use crate::gse_encap::{
+    EncapError, EncapMetadata, Encapsulator, FramePacker, FrameStatus, PackError,
+};
+use crate::gse_standard::{
+    CRC_LEN, FIRST_FRAG_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, GSE_LEN_MAX, LABEL_6_B_LEN,
+    PROTOCOL_LEN, TOTAL_LENGTH_LEN,
+};
+use crate::label::Label;
+use crate::utils::{
+    GseCompletePacket, GseEndFragPacket, GseFirstFragPacket, GseIntermediatePacket, Serialisable,
+};
+
+const PDU: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+const LABEL: Label = Label::SixBytesLabel(*b"012345");
+const PROTOCOL_TYPE: u16 = 0xFFFF;
+
+fn metadata() -> EncapMetadata {
+    EncapMetadata::new(PROTOCOL_TYPE, LABEL)
+}
+
+/// Pack test: complete packets followed by padding
+#[test]
+fn test_pack_complete_001() {
+    const DFL: usize = 100;
+    const PKT1_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_6_B_LEN + 26;
+    const PKT2_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + 26;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+
+    let mut obs_frame = [0xFF; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+
+    let mut exp_frame = [0; DFL];
+    GseCompletePacket::new(
+        (PKT1_LEN - FIXED_HEADER_LEN) as u16,
+        PROTOCOL_TYPE,
+        LABEL,
+        PDU,
+    )
+    .generate(&mut exp_frame).unwrap();
+    GseCompletePacket::new(
+        (PKT2_LEN - FIXED_HEADER_LEN) as u16,
+        PROTOCOL_TYPE,
+        Label::ReUse,
+        PDU,
+    )
+    .generate(&mut exp_frame[PKT1_LEN..]).unwrap();
+
+    assert_eq!(exp_frame, obs_frame);
+    assert_eq!(2, status.pkt_count());
+    assert_eq!(2, status.pdu_count());
+    assert_eq!(PKT1_LEN + PKT2_LEN, status.data_len());
+    assert_eq!(DFL - PKT1_LEN - PKT2_LEN, status.padding_len());
+    assert!(packer.is_empty());
+}
+
+/// Pack test: empty queue, the frame only contains padding
+#[test]
+fn test_pack_complete_002() {
+    const DFL: usize = 100;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+
+    let mut obs_frame = [0xFF; DFL + 10];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+
+    assert_eq!([0; DFL], obs_frame[..DFL]);
+    assert_eq!([0xFF; 10], obs_frame[DFL..]);
+    assert_eq!(0, status.pkt_count());
+    assert_eq!(0, status.data_len());
+    assert_eq!(DFL, status.padding_len());
+}
+
+/// Pack test: the label re use is reset at the beginning of each frame
+#[test]
+fn test_pack_complete_003() {
+    const DFL: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_6_B_LEN + 26;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+
+    let mut exp_frame = [0; DFL];
+    GseCompletePacket::new((DFL - FIXED_HEADER_LEN) as u16, PROTOCOL_TYPE, LABEL, PDU)
+        .generate(&mut exp_frame).unwrap();
+
+    for _ in 0..2 {
+        let mut obs_frame = [0; DFL];
+        let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+        assert_eq!(exp_frame, obs_frame);
+        assert_eq!(0, status.padding_len());
+    }
+    assert!(packer.is_empty());
+}
+
+/// Pack test: a pdu is fragmented and its end is sent first in the next frame
+#[test]
+fn test_pack_frag_001() {
+    const DFL: usize = 30;
+    const FIRST_PDU_LEN: usize = DFL - FIRST_FRAG_LEN - LABEL_6_B_LEN;
+    const END_PKT_LEN: usize = FIXED_HEADER_LEN + FRAG_ID_LEN + (26 - FIRST_PDU_LEN) + CRC_LEN;
+    const TOTAL_LEN: u16 = (26 + PROTOCOL_LEN + LABEL_6_B_LEN) as u16;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+
+    // first frame: first fragment of the first pdu
+    let mut obs_frame = [0; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+
+    let mut exp_frame = [0; DFL];
+    GseFirstFragPacket::new(
+        (DFL - FIXED_HEADER_LEN) as u16,
+        0,
+        TOTAL_LEN,
+        PROTOCOL_TYPE,
+        LABEL,
+        &PDU[..FIRST_PDU_LEN],
+    )
+    .generate(&mut exp_frame).unwrap();
+
+    assert_eq!(exp_frame, obs_frame);
+    assert_eq!(1, status.pkt_count());
+    assert_eq!(0, status.pdu_count());
+    assert_eq!(0, status.padding_len());
+    assert_eq!(1, packer.pending());
+    assert_eq!(1, packer.queued());
+
+    // second frame: end of the first pdu, then first fragment of the second pdu
+    let mut obs_frame = [0; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+
+    let crc = DefaultCrc {}.calculate_crc32(PDU, PROTOCOL_TYPE, TOTAL_LEN, LABEL.get_bytes());
+    let mut exp_frame = [0; DFL];
+    GseEndFragPacket::new(
+        (END_PKT_LEN - FIXED_HEADER_LEN) as u16,
+        0,
+        &PDU[FIRST_PDU_LEN..],
+        crc,
+    )
+    .generate(&mut exp_frame).unwrap();
+    let second_pdu_len = DFL - END_PKT_LEN - FIRST_FRAG_LEN - LABEL_6_B_LEN;
+    GseFirstFragPacket::new(
+        (DFL - END_PKT_LEN - FIXED_HEADER_LEN) as u16,
+        1,
+        TOTAL_LEN,
+        PROTOCOL_TYPE,
+        LABEL,
+        &PDU[..second_pdu_len],
+    )
+    .generate(&mut exp_frame[END_PKT_LEN..]).unwrap();
+
+    assert_eq!(exp_frame, obs_frame);
+    assert_eq!(2, status.pkt_count());
+    assert_eq!(1, status.pdu_count());
+    assert_eq!(1, packer.pending());
+    assert_eq!(0, packer.queued());
+}
+
+/// Pack test: a pdu larger than the maximum gse length is split in several packets of the same frame
+#[test]
+fn test_pack_frag_002() {
+    const PDU_LEN: usize = 6000;
+    const DFL: usize = 7274;
+    const FIRST_PKT_LEN: usize = FIXED_HEADER_LEN + GSE_LEN_MAX;
+    const FIRST_PDU_LEN: usize = FIRST_PKT_LEN - FIRST_FRAG_LEN - LABEL_6_B_LEN;
+    const END_PKT_LEN: usize = FIXED_HEADER_LEN + FRAG_ID_LEN + (PDU_LEN - FIRST_PDU_LEN) + CRC_LEN;
+
+    let pdu: Vec<u8> = (0..PDU_LEN).map(|i| i as u8).collect();
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer
+        .push(pdu.clone().into_boxed_slice(), metadata())
+        .unwrap();
+
+    let mut obs_frame = vec![0; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+
+    let total_len = (PDU_LEN + PROTOCOL_LEN + LABEL_6_B_LEN) as u16;
+    let crc = DefaultCrc {}.calculate_crc32(&pdu, PROTOCOL_TYPE, total_len, LABEL.get_bytes());
+    let mut exp_frame = vec![0; DFL];
+    GseFirstFragPacket::new(
+        GSE_LEN_MAX as u16,
+        0,
+        total_len,
+        PROTOCOL_TYPE,
+        LABEL,
+        &pdu[..FIRST_PDU_LEN],
+    )
+    .generate(&mut exp_frame).unwrap();
+    GseEndFragPacket::new(
+        (END_PKT_LEN - FIXED_HEADER_LEN) as u16,
+        0,
+        &pdu[FIRST_PDU_LEN..],
+        crc,
+    )
+    .generate(&mut exp_frame[FIRST_PKT_LEN..]).unwrap();
+
+    assert_eq!(exp_frame, obs_frame);
+    assert_eq!(2, status.pkt_count());
+    assert_eq!(1, status.pdu_count());
+    assert_eq!(FIRST_PKT_LEN + END_PKT_LEN, status.data_len());
+    assert!(packer.is_empty());
+}
+
+/// Pack test: a pdu sent over three frames uses an intermediate packet
+#[test]
+fn test_pack_frag_003() {
+    const DFL: usize = 20;
+    const FIRST_PDU_LEN: usize = DFL - FIRST_FRAG_LEN - LABEL_6_B_LEN;
+    const INTERMEDIATE_PDU_LEN: usize = DFL - FIXED_HEADER_LEN - FRAG_ID_LEN;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+
+    let mut frame = [0; DFL];
+    packer.pack(&mut encapsulator, &mut frame, DFL).unwrap();
+
+    let mut obs_frame = [0; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+    let mut exp_frame = [0; DFL];
+    GseIntermediatePacket::new(
+        (DFL - FIXED_HEADER_LEN) as u16,
+        0,
+        &PDU[FIRST_PDU_LEN..FIRST_PDU_LEN + INTERMEDIATE_PDU_LEN],
+    )
+    .generate(&mut exp_frame).unwrap();
+    assert_eq!(exp_frame, obs_frame);
+    assert_eq!(1, status.pkt_count());
+    assert_eq!(0, status.pdu_count());
+
+    let mut obs_frame = [0; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+    let end_pdu_len = 26 - FIRST_PDU_LEN - INTERMEDIATE_PDU_LEN;
+    assert_eq!(1, status.pdu_count());
+    assert_eq!(
+        FIXED_HEADER_LEN + FRAG_ID_LEN + end_pdu_len + CRC_LEN,
+        status.data_len()
+    );
+    assert!(packer.is_empty());
+}
+
+/// Pack test: the remaining space is too small for a new packet and is padded
+#[test]
+fn test_pack_padding_001() {
+    const PKT_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_6_B_LEN + 26;
+    const DFL: usize = PKT_LEN + FIRST_FRAG_LEN - TOTAL_LENGTH_LEN;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+    packer
+        .push(
+            Box::new(*PDU),
+            EncapMetadata::new(PROTOCOL_TYPE, Label::Broadcast),
+        )
+        .unwrap();
+
+    let mut obs_frame = [0xFF; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+
+    assert_eq!([0; DFL - PKT_LEN], obs_frame[PKT_LEN..]);
+    assert_eq!(1, status.pkt_count());
+    assert_eq!(DFL - PKT_LEN, status.padding_len());
+    assert_eq!(1, packer.queued());
+}
+
+/// Pack test: the buffer is smaller than the data field length
+#[test]
+fn test_pack_error_001() {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+
+    let mut frame = [0; 50];
+    let status = packer.pack(&mut encapsulator, &mut frame, 51);
+
+    let exp_status = FrameStatus {
+        pkt_count: 0,
+        pdu_count: 0,
+        data_len: 0,
+        padding_len: 0,
+    };
+    let exp_error = PackError {
+        error: EncapError::ErrorSizeBuffer,
+        status: exp_status,
+        pdu: None,
+    };
+    assert_eq!(Err(exp_error), status);
+    assert_eq!(1, packer.queued());
+}
+
+/// Pack test: a pdu that can not be encapsulated is returned with the error, and the frame is still padded
+#[test]
+fn test_pack_error_002() {
+    const PKT_LEN: usize = FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_6_B_LEN + 26;
+    const DFL: usize = 200;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+    // bypass the checks of push
+    let invalid_label = EncapMetadata::new(PROTOCOL_TYPE, Label::SixBytesLabel([0; 6]));
+    packer
+        .queue
+        .push_back((Box::new(*b"invalid"), invalid_label));
+    packer.push(Box::new(*PDU), metadata()).unwrap();
+
+    let mut obs_frame = [0xFF; DFL];
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL);
+
+    let exp_status = FrameStatus {
+        pkt_count: 1,
+        pdu_count: 1,
+        data_len: PKT_LEN,
+        padding_len: DFL - PKT_LEN,
+    };
+    let exp_error = PackError {
+        error: EncapError::ErrorInvalidLabel,
+        status: exp_status,
+        pdu: Some(Box::new(*b"invalid")),
+    };
+    assert_eq!(Err(exp_error), status);
+    assert_eq!([0; DFL - PKT_LEN], obs_frame[PKT_LEN..]);
+    assert_eq!(1, packer.queued());
+
+    // the next pdus are sent in the next frame
+    let status = packer.pack(&mut encapsulator, &mut obs_frame, DFL).unwrap();
+    assert_eq!(1, status.pkt_count());
+    assert!(packer.is_empty());
+}
+
+/// Push test: the metadata are checked before the pdu is queued
+#[test]
+fn test_push_error_001() {
+    let mut packer = FramePacker::new();
+
+    let invalid_label = EncapMetadata::new(PROTOCOL_TYPE, Label::SixBytesLabel([0; 6]));
+    assert_eq!(
+        Err(EncapError::ErrorInvalidLabel),
+        packer.push(Box::new(*PDU), invalid_label)
+    );
+
+    let invalid_protocol_type = EncapMetadata::new(0x0200, LABEL);
+    assert_eq!(
+        Err(EncapError::ErrorProtocolType),
+        packer.push(Box::new(*PDU), invalid_protocol_type)
+    );
+
+    let too_large_pdu = vec![0; 0xFFFF].into_boxed_slice();
+    assert_eq!(
+        Err(EncapError::ErrorPduLength),
+        packer.push(too_large_pdu, metadata())
+    );
+
+    assert!(packer.is_empty());
+}
@@ -7,20 +7,35 @@
 //! It supports complete packet, first fragment packet, intermediate fragment packet, end fragment packet and padding.
 //! It also allows you to manage any type of label including the re-use label.
 
+pub use self::encap_buffer::{EncapBuffer, MAX_HEADER_LEN};
+pub use self::frag_scheduler::{FragIdAllocator, FragScheduler};
+pub use self::frame_packer::{FramePacker, FrameStatus, PackError};
 use crate::crc::CrcCalculator;
 use crate::gse_standard::{
-    COMPLETE_PKT, CRC_LEN, END_PKT, FIRST_FRAG_LEN, FIRST_PKT, FIXED_HEADER_LEN, FRAG_ID_LEN,
-    GSE_LEN_MASK, GSE_LEN_MAX, INTERMEDIATE_PKT, LABEL_3_B, LABEL_6_B, LABEL_BROADCAST,
-    LABEL_REUSE, LABEL_TYPE_MASK, MAX_MANDATORY_VAL_PTYPE, PROTOCOL_LEN, SECOND_RANGE_PTYPE,
-    START_END_MASK, TOTAL_LENGTH_LEN, TOTAL_LEN_MAX,
+    BRIDGED_FRAME_PROTOCOL_ID, COMPLETE_PKT, CRC_LEN, END_PKT, FIRST_PKT, FIXED_HEADER_LEN,
+    FRAG_ID_LEN, GSE_LEN_MASK, GSE_LEN_MAX, INTERMEDIATE_PKT, LABEL_3_B, LABEL_6_B,
+    LABEL_BROADCAST, LABEL_REUSE, LABEL_TYPE_MASK, MAX_MANDATORY_VAL_PTYPE, PROTOCOL_LEN,
+    SECOND_RANGE_PTYPE, START_END_MASK, TOTAL_LENGTH_LEN, TOTAL_LEN_MAX, TS_CONCAT_PROTOCOL_ID,
 };
 
 use crate::label::Label;
 use crate::label::LabelType;
 use crate::pkt_type::PktType;
+use crate::stats::EncapStats;
+use std::cell::RefCell;
+
+use crate::header_extension::bridged_frame::bridged_frame_extension;
+use crate::header_extension::pdu_concat::pdu_concat_extension;
+use crate::header_extension::ts_concat::{is_ts_packet, ts_concat_extension};
+use crate::header_extension::{
+    EthernetHeader, Extension, ExtensionCoverage, ExtensionData, MandatoryHeaderExtensionManager,
+    PduConcat,
+};
 
-use crate::header_extension::{Extension, ExtensionData};
-
+pub mod encap_buffer;
+pub mod frag_scheduler;
+pub mod frame_packer;
+mod refrag;
 #[cfg(test)]
 mod tests;
 
@@ -42,22 +57,80 @@ impl EncapMetadata {
         }
     }
 }
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Cyclic redundancy check of a [`ContextFrag`]
+pub enum FragCrc {
+    /// Crc of the whole pdu, written as it is in the end fragment
+    Final(u32),
+    /// State of the crc computed over the bytes already written, not finalized,
+    /// see [`CrcCalculator::update_crc32`] and [`CrcCalculator::finalize_crc32`]
+    Partial(u32),
+}
+
+impl FragCrc {
+    /// Update a partial crc with `len` bytes of the pdu from the offset `start`, a final crc is kept
+    fn update<C: CrcCalculator>(
+        self,
+        crc_calculator: &C,
+        pdu: &[&[u8]],
+        start: usize,
+        len: usize,
+    ) -> Self {
+        match self {
+            Self::Final(_) => self,
+            Self::Partial(crc) => {
+                Self::Partial(update_crc32_segments(crc_calculator, crc, pdu, start, len))
+            }
+        }
+    }
+
+    /// Return the crc of the end fragment, once updated with its `len` bytes of the pdu from the offset `start`
+    fn finalize<C: CrcCalculator>(
+        self,
+        crc_calculator: &C,
+        pdu: &[&[u8]],
+        start: usize,
+        len: usize,
+    ) -> u32 {
+        match self {
+            Self::Final(crc) => crc,
+            Self::Partial(_) => match self.update(crc_calculator, pdu, start, len) {
+                Self::Partial(crc) => crc_calculator.finalize_crc32(crc),
+                Self::Final(crc) => crc,
+            },
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 /// Define the context of the fragmentation
 /// *   Frag Id describe the fragment id
 /// *   Crc describe the cyclic redundancy check
 /// *   Len pdu frag describe the len of the pdu already written
+///
+/// The encapsulator returns contexts with the crc of the whole pdu. A context can also carry the state of
+/// the crc computed over the bytes already written, see [`ContextFrag::with_partial_crc`] and [`FragCrc`].
 pub struct ContextFrag {
     frag_id: u8,
-    crc: u32,
+    crc: FragCrc,
     len_pdu_frag: u16,
 }
 
 impl ContextFrag {
+    /// Create a context with the crc of the whole pdu
     pub fn new(frag_id: u8, crc: u32, len_pdu_frag: u16) -> Self {
         Self {
             frag_id,
-            crc,
+            crc: FragCrc::Final(crc),
+            len_pdu_frag,
+        }
+    }
+
+    /// Create a context with the state of the crc computed over the `len_pdu_frag` bytes already written
+    pub fn with_partial_crc(frag_id: u8, crc: u32, len_pdu_frag: u16) -> Self {
+        Self {
+            frag_id,
+            crc: FragCrc::Partial(crc),
             len_pdu_frag,
         }
     }
@@ -65,7 +138,15 @@ impl ContextFrag {
     pub fn frag_id(&self) -> u8 {
         self.frag_id
     }
+    /// Return the crc of the whole pdu, or the state of the crc not finalized for a context created by
+    /// [`ContextFrag::with_partial_crc`]
     pub fn crc(&self) -> u32 {
+        match self.crc {
+            FragCrc::Final(crc) | FragCrc::Partial(crc) => crc,
+        }
+    }
+    /// Return the crc with its state, final or partial
+    pub fn crc_state(&self) -> FragCrc {
         self.crc
     }
     pub fn len_pdu_frag(&self) -> u16 {
@@ -90,7 +171,7 @@ impl EncapStatus {
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 /// Error returned by [`Encapsulator::encap`], [`Encapsulator::encap_frag`], or [`Encapsulator::encap_ext`] functions function during failure.
 ///
 /// This enum is used as the `Err` variant in a `Result` type.
@@ -98,7 +179,8 @@ pub enum EncapError {
     /// Indicates that the output buffer is too small to accommodate the encapsulated packet.
     ErrorSizeBuffer,
 
-    /// Indicates that the packet size exceeds the maximum allowed value for the GSE protocol (65535 bytes).
+    /// Indicates that the packet size exceeds the maximum allowed value for the GSE protocol (65535 bytes),
+    /// or that the batch of pdus given to [`Encapsulator::encap_ext_concat`] is empty.
     ErrorPduLength,
 
     /// Indicates that the provided protocol type is invalid.
@@ -112,6 +194,20 @@ pub enum EncapError {
 
     /// Indicates that a final mandatory extension was provided, but the protocol type differs from the final extension ID. This extension should replace the protocol type.
     ErrorFinalMandatoryExtensionHeader,
+
+    /// Indicates that the pdu must be fragmented but the 256 frag ids are used by pdus partially sent.
+    ErrorFragIdExhausted,
+
+    /// Indicates that no pdu is partially sent with the frag id provided.
+    ErrorUnknownFragId,
+
+    /// Indicates that the packet given to [`Encapsulator::refrag`] is not a valid GSE packet.
+    ErrorInvalidPacket,
+
+    /// Indicates that a header extension given to [`Encapsulator::encap_ext_with_manager`] can not be encoded by the manager,
+    /// or that the frame given to [`Encapsulator::encap_bridged_frame`] is too short to hold an Ethernet header,
+    /// or that the packets given to [`Encapsulator::encap_ts_concat`] are not TS packets.
+    ErrorInvalidHeaderExtension,
 }
 
 impl EncapError {
@@ -124,6 +220,10 @@ impl EncapError {
             Self::ErrorNoExtensionFound => "Use of encap_ext without header extension to add",
             Self::ErrorFinalMandatoryExtensionHeader => "in encap_ext, if protocol type corresponds to mandatory header extension, it should \
             be equal to the id of the last extension that must be a mandatory header extension",
+            Self::ErrorFragIdExhausted => "All the frag ids are used by pdus partially sent",
+            Self::ErrorUnknownFragId => "No pdu is partially sent with this frag id",
+            Self::ErrorInvalidPacket => "The packet to refragment can not be parsed",
+            Self::ErrorInvalidHeaderExtension => "Header extension can not be encoded",
         }
     }
 }
@@ -139,6 +239,12 @@ impl EncapError {
 /// The last label has to be reset by the user at the begining of each new base band frame.
 /// Or is optionally reset after `re_max_consecutive` Re Use Labels have been emitted, unless
 /// this attribute is set to 0 (default).
+///
+/// When the statistics are enabled, `stats` counts the packets written and the errors returned, see the [`crate::stats`] module.
+/// They are kept in a `RefCell` as the fragments are written by functions borrowing the encapsulator immutably.
+///
+/// The header extensions of the fragmented pdus are counted in the total length and covered by the crc as specified by the standard,
+/// unless `extension_coverage` is set to [`ExtensionCoverage::Legacy`].
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Encapsulator<C: CrcCalculator> {
     crc_calculator: C,
@@ -146,6 +252,8 @@ pub struct Encapsulator<C: CrcCalculator> {
     re_max_consecutive: u8,
     re_current_consecutive: u8,
     last_label: Option<Label>,
+    stats: Option<RefCell<EncapStats>>,
+    extension_coverage: ExtensionCoverage,
 }
 
 impl<C: CrcCalculator> Encapsulator<C> {
@@ -157,6 +265,8 @@ impl<C: CrcCalculator> Encapsulator<C> {
             re_use_activated: true,
             re_max_consecutive: 0,
             re_current_consecutive: 0,
+            stats: None,
+            extension_coverage: ExtensionCoverage::Standard,
         }
     }
 
@@ -195,15 +305,78 @@ impl<C: CrcCalculator> Encapsulator<C> {
         self.re_use_activated
     }
 
+    /// Set how the header extensions of the fragmented pdus are counted in the total length and covered by the crc
+    pub fn set_extension_coverage(&mut self, coverage: ExtensionCoverage) {
+        self.extension_coverage = coverage;
+    }
+
+    pub fn extension_coverage(&self) -> ExtensionCoverage {
+        self.extension_coverage
+    }
+
+    /// Start counting the packets and the errors, the counters already enabled are kept
+    pub fn enable_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(RefCell::default());
+        }
+    }
+
+    /// Stop counting and drop the counters
+    pub fn disable_stats(&mut self) {
+        self.stats = None;
+    }
+
+    /// Return a snapshot of the counters, or `None` if the statistics are disabled
+    pub fn stats(&self) -> Option<EncapStats> {
+        self.stats.as_ref().map(|stats| stats.borrow().clone())
+    }
+
+    /// Set all the counters back to 0, if the statistics are enabled
+    pub fn reset_stats(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            *stats.get_mut() = EncapStats::default();
+        }
+    }
+
+    /// Count the packet written or the error returned
+    ///
+    /// `metadata` is given for the packets starting a pdu (complete packet or first fragment), `None` for the next fragments.
+    fn count(
+        &self,
+        metadata: Option<&EncapMetadata>,
+        result: &Result<EncapStatus, EncapError>,
+    ) {
+        let Some(stats) = &self.stats else {
+            return;
+        };
+        let mut stats = stats.borrow_mut();
+        match (result, metadata) {
+            (Err(error), _) => stats.count_error(error),
+            (Ok(status), Some(metadata)) => {
+                stats.count_pkt(match status {
+                    EncapStatus::CompletedPkt(_) => PktType::CompletePkt,
+                    EncapStatus::FragmentedPkt(_, _) => PktType::FirstFragPkt,
+                });
+                stats.count_pdu(metadata.label, metadata.protocol_type);
+            }
+            (Ok(status), None) => stats.count_pkt(match status {
+                EncapStatus::CompletedPkt(_) => PktType::EndFragPkt,
+                EncapStatus::FragmentedPkt(_, _) => PktType::IntermediateFragPkt,
+            }),
+        }
+    }
+
     fn check_label_re_use(&mut self, next_label: Label) -> Label {
         if self.re_use_activated {
             // check label reuse
             if Some(next_label) == self.last_label {
                 if self.re_max_consecutive == 0u8 {
+                    self.count_re_use_label();
                     return Label::ReUse;
                 } else {
                     if self.re_current_consecutive < self.re_max_consecutive {
                         self.re_current_consecutive += 1;
+                        self.count_re_use_label();
                         return Label::ReUse;
                     } else {
                         self.re_current_consecutive = 0;
@@ -221,6 +394,24 @@ impl<C: CrcCalculator> Encapsulator<C> {
         return next_label;
     }
 
+    /// Return the label sent by the next packet of a pdu with `next_label`, without updating the re use state
+    fn peek_label_re_use(&self, next_label: Label) -> Label {
+        if self.re_use_activated
+            && Some(next_label) == self.last_label
+            && (self.re_max_consecutive == 0
+                || self.re_current_consecutive < self.re_max_consecutive)
+        {
+            return Label::ReUse;
+        }
+        next_label
+    }
+
+    fn count_re_use_label(&self) {
+        if let Some(stats) = &self.stats {
+            stats.borrow_mut().count_re_use_label();
+        }
+    }
+
     /// GSE encapsulation of a gse header and the payload in a buffer
     ///
     /// The metadata and the pdu are written in the buffer next the gse header and the function returns the size of the packet if the encapsulation succeed.
@@ -324,6 +515,53 @@ impl<C: CrcCalculator> Encapsulator<C> {
         frag_id: u8,
         metadata: EncapMetadata,
         buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        self.encap_vectored(&[pdu], frag_id, metadata, buffer)
+    }
+
+    /// GSE encapsulation of a gse header and a payload given as a list of segments in a buffer
+    ///
+    /// The segments are encapsulated as if they were a single contiguous pdu, without being coalesced before,
+    /// the function behaves as [`Self::encap`].
+    ///
+    /// # Example
+    /// ```
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::label::Label;
+    /// use dvb_gse_rust::gse_encap::{Encapsulator, EncapMetadata, EncapStatus};
+    ///
+    /// let metadata = EncapMetadata::new(0xFFFF, Label::Broadcast);
+    /// let header = b"abcdefghij";
+    /// let payload = b"klmnopqrstuvwxyz";
+    ///
+    /// let mut buffer = [0; 1000];
+    /// let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    /// let encap_status = encapsulator.encap_vectored(&[header, payload], 1, metadata, &mut buffer);
+    ///
+    /// // The packet is the same as if the segments were encapsulated as a single pdu
+    /// let mut exp_buffer = [0; 1000];
+    /// let exp_encap_status = encapsulator.encap(b"abcdefghijklmnopqrstuvwxyz", 1, metadata, &mut exp_buffer);
+    /// assert_eq!(encap_status, exp_encap_status);
+    /// assert_eq!(buffer, exp_buffer);
+    /// ```
+    pub fn encap_vectored(
+        &mut self,
+        pdu: &[&[u8]],
+        frag_id: u8,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let result = self.write_vectored(pdu, frag_id, metadata, buffer);
+        self.count(Some(&metadata), &result);
+        result
+    }
+
+    fn write_vectored(
+        &mut self,
+        pdu: &[&[u8]],
+        frag_id: u8,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
     ) -> Result<EncapStatus, EncapError> {
         let mut label = metadata.label;
         let protocol_type = metadata.protocol_type;
@@ -340,46 +578,13 @@ impl<C: CrcCalculator> Encapsulator<C> {
 
         label = self.check_label_re_use(label);
         let label_len = label.len();
-        let pdu_len = pdu.len();
-        let gse_len_min = pdu_len + label_len + PROTOCOL_LEN;
-
-        // if it fits into a complete package
-        let min_header_len = FIXED_HEADER_LEN + PROTOCOL_LEN + label_len;
-        let buffer_len = buffer.len();
-
-        let pdu_len_encapsulated: usize;
-        let pkt_type: PktType;
-        let gse_len: u16;
-
-        // if all the data and metadata will fit in the buffer, and
-        // if the protocol can handle the size of the packer
-        if (buffer_len >= min_header_len + pdu_len) && (GSE_LEN_MAX >= gse_len_min) {
-            // complet packet
-            pkt_type = PktType::CompletePkt;
-            pdu_len_encapsulated = pdu_len;
-            gse_len = gse_len_min as u16;
-        } else {
-            // first packet
-            let min_header_len = min_header_len + FRAG_ID_LEN + TOTAL_LENGTH_LEN;
-
-            // check the buffer size
-            // if it cannot write at least more than the header
-            if buffer_len < min_header_len {
-                return Err(EncapError::ErrorSizeBuffer);
-            }
-
-            // check the metadata len
-            // if the protocol cannot handle such large amounts of data
-            if TOTAL_LEN_MAX < pdu_len + PROTOCOL_LEN + label_len {
-                return Err(EncapError::ErrorPduLength);
-            }
-
-            pkt_type = PktType::FirstFragPkt;
-            pdu_len_encapsulated = buffer_len - min_header_len;
-            gse_len =
-                (FRAG_ID_LEN + TOTAL_LENGTH_LEN + PROTOCOL_LEN + label_len + pdu_len_encapsulated)
-                    as u16;
-        }
+        let pdu_len = segments_len(pdu);
+        let (pkt_type, pdu_len_encapsulated, gse_len) = start_pkt_layout(
+            PROTOCOL_LEN + label_len,
+            PROTOCOL_LEN + label_len,
+            pdu_len,
+            buffer.len(),
+        )?;
 
         // write gse fixed header
         let header = generate_gse_header(&pkt_type, &label.get_type(), gse_len);
@@ -400,18 +605,19 @@ impl<C: CrcCalculator> Encapsulator<C> {
                 // define context frag
                 let context_frag = ContextFrag {
                     frag_id,
-                    crc: self.crc_calculator.calculate_crc32(
-                        &pdu[..pdu_len],
-                        protocol_type,
-                        total_len,
-                        label.get_bytes(),
+                    crc: FragCrc::Final(
+                        FragCrc::Partial(self.crc_calculator.init_crc32(
+                            protocol_type,
+                            total_len,
+                            label.get_bytes(),
+                        ))
+                        .finalize(&self.crc_calculator, pdu, 0, pdu_len),
                     ),
                     len_pdu_frag: pdu_len_encapsulated as u16,
                 };
 
                 // define encap status
-                let pkt_len = FIRST_FRAG_LEN + label_len + pdu_len_encapsulated;
-                EncapStatus::FragmentedPkt(pkt_len as u16, context_frag)
+                EncapStatus::FragmentedPkt(gse_len + FIXED_HEADER_LEN as u16, context_frag)
             }
             _ => EncapStatus::CompletedPkt(gse_len + FIXED_HEADER_LEN as u16),
         };
@@ -425,7 +631,7 @@ impl<C: CrcCalculator> Encapsulator<C> {
         offset += label_len;
 
         // write pdu
-        buffer[offset..offset + pdu_len_encapsulated].copy_from_slice(&pdu[..pdu_len_encapsulated]);
+        copy_segments(pdu, 0, &mut buffer[offset..offset + pdu_len_encapsulated]);
 
         // return status
         Ok(encap_status)
@@ -514,12 +720,36 @@ impl<C: CrcCalculator> Encapsulator<C> {
         pdu: &[u8],
         context: &ContextFrag,
         buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        self.encap_frag_vectored(&[pdu], context, buffer)
+    }
+
+    /// GSE encapsulation of a fragment of a PDU given as a list of segments in a buffer
+    ///
+    /// The segments must be the same as the ones given to [`Self::encap_vectored`], even if they are not cut at the same offsets as the fragments.
+    /// The function behaves as [`Self::encap_frag`].
+    pub fn encap_frag_vectored(
+        &self,
+        pdu: &[&[u8]],
+        context: &ContextFrag,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let result = self.write_frag_vectored(pdu, context, buffer);
+        self.count(None, &result);
+        result
+    }
+
+    fn write_frag_vectored(
+        &self,
+        pdu: &[&[u8]],
+        context: &ContextFrag,
+        buffer: &mut [u8],
     ) -> Result<EncapStatus, EncapError> {
         let len_pdu_frag = context.len_pdu_frag as usize;
         let frag_id = context.frag_id;
         let crc = context.crc;
         let buffer_len = buffer.len();
-        let pdu_len = pdu.len();
+        let pdu_len = segments_len(pdu);
 
         // Metadata error
         if len_pdu_frag > pdu_len {
@@ -539,6 +769,12 @@ impl<C: CrcCalculator> Encapsulator<C> {
                 generate_gse_header(&PktType::EndFragPkt, &LabelType::ReUse, gse_end_len as u16);
             pdu_len_encapsulated = pdu_len_remaining;
 
+            let crc = crc.finalize(
+                &self.crc_calculator,
+                pdu,
+                len_pdu_frag,
+                pdu_len_encapsulated,
+            );
             let mut buffer_offset = FIXED_HEADER_LEN + FRAG_ID_LEN + pdu_len_encapsulated;
             buffer[buffer_offset..buffer_offset + CRC_LEN].copy_from_slice(&crc.to_be_bytes());
             buffer_offset += CRC_LEN;
@@ -568,7 +804,12 @@ impl<C: CrcCalculator> Encapsulator<C> {
             let buffer_offset = FIXED_HEADER_LEN + gse_len;
             let new_context = ContextFrag {
                 frag_id,
-                crc,
+                crc: crc.update(
+                    &self.crc_calculator,
+                    pdu,
+                    len_pdu_frag,
+                    pdu_len_encapsulated,
+                ),
                 len_pdu_frag: (len_pdu_frag + pdu_len_encapsulated) as u16,
             };
             encap_status = EncapStatus::FragmentedPkt(buffer_offset as u16, new_context);
@@ -584,8 +825,11 @@ impl<C: CrcCalculator> Encapsulator<C> {
         let buffer_offset = FIXED_HEADER_LEN + FRAG_ID_LEN;
 
         // Write the fragment
-        buffer[buffer_offset..buffer_offset + pdu_len_encapsulated]
-            .copy_from_slice(&pdu[len_pdu_frag..len_pdu_frag + pdu_len_encapsulated]);
+        copy_segments(
+            pdu,
+            len_pdu_frag,
+            &mut buffer[buffer_offset..buffer_offset + pdu_len_encapsulated],
+        );
 
         Ok(encap_status)
     }
@@ -598,38 +842,153 @@ impl<C: CrcCalculator> Encapsulator<C> {
         buffer: &mut [u8],
         extensions: Vec<Extension>,
     ) -> Result<EncapStatus, EncapError> {
-        if extensions.is_empty() {
-            return Err(EncapError::ErrorNoExtensionFound);
+        self.encap_ext_vectored(&[pdu], frag_id, metadata, buffer, extensions)
+    }
+
+    /// GSE encapsulation with header extensions of a payload given as a list of segments in a buffer
+    ///
+    /// The function behaves as [`Self::encap_ext`], the segments are not coalesced before.
+    pub fn encap_ext_vectored(
+        &mut self,
+        pdu: &[&[u8]],
+        frag_id: u8,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
+        extensions: Vec<Extension>,
+    ) -> Result<EncapStatus, EncapError> {
+        let result = self.write_ext_vectored(pdu, frag_id, metadata, buffer, extensions);
+        self.count(Some(&metadata), &result);
+        result
+    }
+
+    /// GSE encapsulation with header extensions encoded by a manager
+    ///
+    /// Each extension is replaced by the one returned by [`MandatoryHeaderExtensionManager::encode_extension`],
+    /// e.g. by the handlers of an [`crate::header_extension::ExtensionRegistry`], then the function behaves as [`Self::encap_ext`].
+    pub fn encap_ext_with_manager<M: MandatoryHeaderExtensionManager>(
+        &mut self,
+        pdu: &[u8],
+        frag_id: u8,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
+        extensions: Vec<Extension>,
+        manager: &M,
+    ) -> Result<EncapStatus, EncapError> {
+        let encoded: Result<Vec<Extension>, _> = extensions
+            .iter()
+            .map(|extension| manager.encode_extension(extension))
+            .collect();
+        let Ok(extensions) = encoded else {
+            let result = Err(EncapError::ErrorInvalidHeaderExtension);
+            self.count(Some(&metadata), &result);
+            return result;
         };
+        self.encap_ext(pdu, frag_id, metadata, buffer, extensions)
+    }
 
-        let mut label = metadata.label;
-        let protocol_type = metadata.protocol_type;
-        let mut is_there_final_mandatory_extension = false;
-        let mut total_len_extensions: usize = 0;
-
-        if protocol_type < MAX_MANDATORY_VAL_PTYPE {
-            // the mandatory header extension replaces the protocol type
-            // checking if the last extension id corresponds to this protocol type
-            if extensions.last().unwrap().id() != protocol_type
-                && matches!(
-                    extensions.last().unwrap().data(),
-                    ExtensionData::MandatoryData(..)
-                )
-            {
-                return Err(EncapError::ErrorFinalMandatoryExtensionHeader);
-            }
-            is_there_final_mandatory_extension = true;
-        } else if protocol_type < SECOND_RANGE_PTYPE {
-            return Err(EncapError::ErrorProtocolType);
+    /// GSE encapsulation of an Ethernet frame with the Bridged Frame extension
+    ///
+    /// The frame, without its frame check sequence, is the pdu of the packet and the extension replaces its protocol type,
+    /// the next fragments are written by [`Self::encap_frag`] with the frame as pdu.
+    /// See [`crate::header_extension::bridged_frame`].
+    pub fn encap_bridged_frame(
+        &mut self,
+        frame: &[u8],
+        frag_id: u8,
+        label: Label,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let metadata = EncapMetadata::new(BRIDGED_FRAME_PROTOCOL_ID, label);
+        if EthernetHeader::decode(frame).is_none() {
+            let result = Err(EncapError::ErrorInvalidHeaderExtension);
+            self.count(Some(&metadata), &result);
+            return result;
         }
+        self.encap_ext(
+            frame,
+            frag_id,
+            metadata,
+            buffer,
+            vec![bridged_frame_extension()],
+        )
+    }
 
-        for extension in &extensions {
-            total_len_extensions += extension.len();
-        }
+    /// GSE encapsulation with header extensions of a batch of pdus packed with the PDU-Concat extension
+    ///
+    /// The pdus have the protocol type of `metadata`, the PDU-Concat extension is added after `extensions`.
+    /// The next fragments are written by [`Self::encap_frag_concat`] with the same batch.
+    /// See [`crate::header_extension::pdu_concat`].
+    pub fn encap_ext_concat(
+        &mut self,
+        pdus: &[&[u8]],
+        frag_id: u8,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
+        mut extensions: Vec<Extension>,
+    ) -> Result<EncapStatus, EncapError> {
+        let Some(concat) = PduConcat::new(pdus) else {
+            let result = Err(EncapError::ErrorPduLength);
+            self.count(Some(&metadata), &result);
+            return result;
+        };
+        extensions.push(pdu_concat_extension());
+        self.encap_ext_vectored(&concat.segments(), frag_id, metadata, buffer, extensions)
+    }
+
+    /// GSE encapsulation of a fragment of a batch of pdus, after [`Self::encap_ext_concat`]
+    pub fn encap_frag_concat(
+        &self,
+        pdus: &[&[u8]],
+        context: &ContextFrag,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let Some(concat) = PduConcat::new(pdus) else {
+            let result = Err(EncapError::ErrorPduLength);
+            self.count(None, &result);
+            return result;
+        };
+        self.encap_frag_vectored(&concat.segments(), context, buffer)
+    }
 
-        if is_there_final_mandatory_extension {
-            total_len_extensions -= PROTOCOL_LEN; // the id of the final mandatory extension replace the protocol type len
+    /// GSE encapsulation of a sequence of MPEG-2 TS packets with the TS-Concat extension
+    ///
+    /// Each packet must have the size of a TS packet and start with the sync byte, the extension replaces the protocol type.
+    /// The next fragments are written by [`Self::encap_frag_vectored`] with the same packets.
+    /// See [`crate::header_extension::ts_concat`].
+    pub fn encap_ts_concat(
+        &mut self,
+        packets: &[&[u8]],
+        frag_id: u8,
+        label: Label,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let metadata = EncapMetadata::new(TS_CONCAT_PROTOCOL_ID, label);
+        if packets.is_empty() || !packets.iter().all(|packet| is_ts_packet(packet)) {
+            let result = Err(EncapError::ErrorInvalidHeaderExtension);
+            self.count(Some(&metadata), &result);
+            return result;
         }
+        self.encap_ext_vectored(
+            packets,
+            frag_id,
+            metadata,
+            buffer,
+            vec![ts_concat_extension()],
+        )
+    }
+
+    fn write_ext_vectored(
+        &mut self,
+        pdu: &[&[u8]],
+        frag_id: u8,
+        metadata: EncapMetadata,
+        buffer: &mut [u8],
+        extensions: Vec<Extension>,
+    ) -> Result<EncapStatus, EncapError> {
+        let mut label = metadata.label;
+        let protocol_type = metadata.protocol_type;
+        let total_len_extensions = check_extensions(protocol_type, &extensions)?;
+
         // check label
         if label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
             return Err(EncapError::ErrorInvalidLabel);
@@ -637,161 +996,181 @@ impl<C: CrcCalculator> Encapsulator<C> {
 
         label = self.check_label_re_use(label);
         let label_len = label.len();
-        let pdu_len = pdu.len();
-        let gse_len_min = pdu_len + label_len + PROTOCOL_LEN + total_len_extensions;
-
-        // if it fits into a complete package
-        let min_header_len = FIXED_HEADER_LEN + PROTOCOL_LEN + label_len + total_len_extensions;
-        let buffer_len = buffer.len();
-
-        let pdu_len_encapsulated: usize;
-        let pkt_type: PktType;
-        let gse_len: u16;
-
-        // if all the data and metadata will fit in the buffer, and
-        // if the protocol can handle the size of the packer
-        if (buffer_len >= min_header_len + pdu_len) && (GSE_LEN_MAX >= gse_len_min) {
-            // complet packet
-            pkt_type = PktType::CompletePkt;
-            pdu_len_encapsulated = pdu_len;
-            gse_len = gse_len_min as u16;
-        } else {
-            // first packet
-            let min_header_len =
-                min_header_len + FRAG_ID_LEN + TOTAL_LENGTH_LEN + total_len_extensions;
-
-            // check the buffer size
-            // if it cannot write at least more than the header
-            if buffer_len < min_header_len {
-                return Err(EncapError::ErrorSizeBuffer);
-            }
-
-            // check the metadata len
-            // if the protocol cannot handle such large amounts of data
-            if TOTAL_LEN_MAX < pdu_len + PROTOCOL_LEN + label_len {
-                return Err(EncapError::ErrorPduLength);
-            }
-
-            pkt_type = PktType::FirstFragPkt;
-            pdu_len_encapsulated = buffer_len - min_header_len;
-            gse_len = (FRAG_ID_LEN
-                + TOTAL_LENGTH_LEN
-                + PROTOCOL_LEN
-                + label_len
-                + pdu_len_encapsulated
-                + total_len_extensions) as u16;
-        }
+        let pdu_len = segments_len(pdu);
+        let (pkt_type, pdu_len_encapsulated, gse_len) = start_pkt_layout(
+            PROTOCOL_LEN + label_len + total_len_extensions,
+            self.extension_coverage
+                .total_len(0, label_len, total_len_extensions),
+            pdu_len,
+            buffer.len(),
+        )?;
         // write gse fixed header
         let header = generate_gse_header(&pkt_type, &label.get_type(), gse_len);
         let mut offset = FIXED_HEADER_LEN;
         buffer[..offset].copy_from_slice(&header.to_be_bytes());
 
-        let encap_status = match pkt_type {
-            PktType::FirstFragPkt => {
-                // write fragId
-                buffer[offset..offset + FRAG_ID_LEN].copy_from_slice(&frag_id.to_be_bytes());
-                offset += FRAG_ID_LEN;
+        let mut total_len = 0;
+        if pkt_type == PktType::FirstFragPkt {
+            // write fragId
+            buffer[offset..offset + FRAG_ID_LEN].copy_from_slice(&frag_id.to_be_bytes());
+            offset += FRAG_ID_LEN;
+
+            // write total_length, counting the header extensions or not depending on the coverage
+            total_len = self
+                .extension_coverage
+                .total_len(pdu_len, label_len, total_len_extensions) as u16;
+            buffer[offset..offset + TOTAL_LENGTH_LEN].copy_from_slice(&total_len.to_be_bytes());
+            offset += TOTAL_LENGTH_LEN;
+        }
 
-                // write total_length
-                let total_len = (pdu_len + PROTOCOL_LEN + label_len) as u16;
-                buffer[offset..offset + TOTAL_LENGTH_LEN].copy_from_slice(&total_len.to_be_bytes());
-                offset += TOTAL_LENGTH_LEN;
+        // write the first header extension id instead of protocol type, the label and the header extensions
+        let extensions_start = offset + PROTOCOL_LEN + label_len;
+        offset += write_header_fields(&mut buffer[offset..], protocol_type, &label, &extensions);
+        // write pdu
+        copy_segments(pdu, 0, &mut buffer[offset..offset + pdu_len_encapsulated]);
 
-                // define context frag
+        let encap_status = match pkt_type {
+            PktType::FirstFragPkt => {
+                // define context frag, the crc of the whole pdu starts with the header fields written
+                let crc = self.extension_coverage.init_crc32(
+                    &self.crc_calculator,
+                    extensions[0].id(),
+                    protocol_type,
+                    total_len,
+                    label.get_bytes(),
+                    &buffer[extensions_start..offset],
+                );
                 let context_frag = ContextFrag {
                     frag_id,
-                    crc: self.crc_calculator.calculate_crc32(
-                        &pdu[..pdu_len],
-                        protocol_type,
-                        total_len,
-                        label.get_bytes(),
-                    ),
+                    crc: FragCrc::Final(FragCrc::Partial(crc).finalize(
+                        &self.crc_calculator,
+                        pdu,
+                        0,
+                        pdu_len,
+                    )),
                     len_pdu_frag: pdu_len_encapsulated as u16,
                 };
-
-                // define encap status
-                let pkt_len = FIRST_FRAG_LEN + label_len + pdu_len_encapsulated;
-                EncapStatus::FragmentedPkt(pkt_len as u16, context_frag)
+                EncapStatus::FragmentedPkt(gse_len + FIXED_HEADER_LEN as u16, context_frag)
             }
             _ => EncapStatus::CompletedPkt(gse_len + FIXED_HEADER_LEN as u16),
         };
+        // return status
+        Ok(encap_status)
+    }
 
-        // write protocol type
+    /// Preview the packet written by [`Self::encap`] in `buffer`
+    ///
+    /// Unlike [`encap_preview`], the label re use state of the encapsulator is taken into account,
+    /// so that the preview matches the packet written by the next call to [`Self::encap`].
+    /// The state of the encapsulator is not updated.
+    ///
+    /// # Example
+    /// ```
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::label::Label;
+    /// use dvb_gse_rust::gse_encap::{Encapsulator, EncapMetadata, EncapStatus};
+    ///
+    /// let metadata = EncapMetadata::new(0xFFFF, Label::SixBytesLabel(*b"012345"));
+    /// let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    /// let mut buffer = [0; 1000];
+    /// let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    /// encapsulator.encap(pdu, 1, metadata, &mut buffer).unwrap();
+    ///
+    /// // The label is re used by the next packet
+    /// let preview = encapsulator.encap_preview(pdu, metadata, &buffer).unwrap();
+    /// let encap_status = encapsulator.encap(pdu, 1, metadata, &mut buffer);
+    /// assert_eq!(Ok(EncapStatus::CompletedPkt(preview.pkt_len())), encap_status);
+    /// assert_eq!(30, preview.pkt_len());
+    /// ```
+    pub fn encap_preview(
+        &self,
+        pdu: &[u8],
+        metadata: EncapMetadata,
+        buffer: &[u8],
+    ) -> Result<EncapPreview, EncapError> {
+        self.encap_preview_vectored(&[pdu], metadata, buffer)
+    }
 
-        // write first header extension id instead of protocol type
-        buffer[offset..offset + PROTOCOL_LEN].copy_from_slice(&extensions[0].id().to_be_bytes());
-        offset += PROTOCOL_LEN;
+    /// Preview the packet written by [`Self::encap_vectored`] in `buffer`
+    ///
+    /// The function behaves as [`Self::encap_preview`].
+    pub fn encap_preview_vectored(
+        &self,
+        pdu: &[&[u8]],
+        metadata: EncapMetadata,
+        buffer: &[u8],
+    ) -> Result<EncapPreview, EncapError> {
+        // check label
+        if metadata.label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
+            return Err(EncapError::ErrorInvalidLabel);
+        }
 
-        // write label
-        buffer[offset..offset + label_len].copy_from_slice(label.get_bytes());
-        offset += label_len;
+        // check protocol_type is valid, i.e. not in range [SECOND_RANGE_PTYPE, MAX_MANDATORY_VAL_PTYPE] = [256, 1535]
+        if (MAX_MANDATORY_VAL_PTYPE..SECOND_RANGE_PTYPE).contains(&metadata.protocol_type) {
+            return Err(EncapError::ErrorProtocolType);
+        }
 
-        for i in 0..extensions.len() - 1 {
-            match &extensions[i].data() {
-                //refactor?
-                ExtensionData::Data2(inner) => {
-                    buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                    offset += inner.len();
-                }
-                ExtensionData::Data4(inner) => {
-                    buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                    offset += inner.len();
-                }
-                ExtensionData::Data6(inner) => {
-                    buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                    offset += inner.len();
-                }
-                ExtensionData::Data8(inner) => {
-                    buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                    offset += inner.len();
-                }
-                ExtensionData::NoData => (),
+        let label_len = self.peek_label_re_use(metadata.label).len();
+        start_pkt_preview(
+            PROTOCOL_LEN + label_len,
+            PROTOCOL_LEN + label_len,
+            pdu,
+            buffer,
+        )
+    }
 
-                ExtensionData::MandatoryData(inner) => {
-                    buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                    offset += inner.len();
-                }
-            }
+    /// Preview the packet written by [`Self::encap_ext`] in `buffer`
+    ///
+    /// The header extensions are checked and counted in the packet length as they are by [`Self::encap_ext`],
+    /// and the label re use state of the encapsulator is taken into account.
+    /// The state of the encapsulator is not updated.
+    pub fn encap_ext_preview(
+        &self,
+        pdu: &[u8],
+        metadata: EncapMetadata,
+        buffer: &[u8],
+        extensions: &[Extension],
+    ) -> Result<EncapPreview, EncapError> {
+        self.encap_ext_preview_vectored(&[pdu], metadata, buffer, extensions)
+    }
 
-            buffer[offset..offset + PROTOCOL_LEN]
-                .copy_from_slice(&extensions[i + 1].id().to_be_bytes());
-            offset += PROTOCOL_LEN;
-        }
+    /// Preview the packet written by [`Self::encap_ext_vectored`] in `buffer`
+    ///
+    /// The function behaves as [`Self::encap_ext_preview`].
+    pub fn encap_ext_preview_vectored(
+        &self,
+        pdu: &[&[u8]],
+        metadata: EncapMetadata,
+        buffer: &[u8],
+        extensions: &[Extension],
+    ) -> Result<EncapPreview, EncapError> {
+        let total_len_extensions = check_extensions(metadata.protocol_type, extensions)?;
 
-        // writting last extension data
-        match &extensions.last().unwrap().data() {
-            //refactor?
-            ExtensionData::Data2(inner) => {
-                buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                offset += inner.len();
-            }
-            ExtensionData::Data4(inner) => {
-                buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                offset += inner.len();
-            }
-            ExtensionData::Data6(inner) => {
-                buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                offset += inner.len();
-            }
-            ExtensionData::Data8(inner) => {
-                buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                offset += inner.len();
-            }
-            ExtensionData::NoData => (),
-            ExtensionData::MandatoryData(inner) => {
-                buffer[offset..offset + inner.len()].copy_from_slice(inner);
-                offset += inner.len();
-            }
+        // check label
+        if metadata.label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
+            return Err(EncapError::ErrorInvalidLabel);
         }
 
-        if !is_there_final_mandatory_extension {
-            buffer[offset..offset + PROTOCOL_LEN].copy_from_slice(&protocol_type.to_be_bytes());
-            offset += PROTOCOL_LEN;
-        }
-        // write pdu
-        buffer[offset..offset + pdu_len_encapsulated].copy_from_slice(&pdu[..pdu_len_encapsulated]);
-        // return status
-        Ok(encap_status)
+        let label_len = self.peek_label_re_use(metadata.label).len();
+        start_pkt_preview(
+            PROTOCOL_LEN + label_len + total_len_extensions,
+            self.extension_coverage
+                .total_len(0, label_len, total_len_extensions),
+            pdu,
+            buffer,
+        )
+    }
+
+    /// Preview the packet written by [`Self::encap_frag`] in `buffer`
+    ///
+    /// The next fragments do not depend on the state of the encapsulator, the function behaves as [`encap_frag_preview`].
+    pub fn encap_frag_preview(
+        &self,
+        pdu: &[u8],
+        context: &ContextFrag,
+        buffer: &[u8],
+    ) -> Result<EncapPreview, EncapError> {
+        encap_frag_preview_vectored(&[pdu], context, buffer)
     }
 }
 
@@ -831,13 +1210,21 @@ pub fn encap_preview(
     pdu: &[u8],
     metadata: EncapMetadata,
     buffer: &[u8],
+) -> Result<EncapPreview, EncapError> {
+    encap_preview_vectored(&[pdu], metadata, buffer)
+}
+
+/// Preview the encapsulation of a pdu given as a list of segments into a GSE packet.
+///
+/// The function behaves as [`encap_preview`].
+pub fn encap_preview_vectored(
+    pdu: &[&[u8]],
+    metadata: EncapMetadata,
+    buffer: &[u8],
 ) -> Result<EncapPreview, EncapError> {
     let label = metadata.label;
     let protocol_type = metadata.protocol_type;
-
     let label_len = label.len();
-    let pdu_len = pdu.len();
-    let gse_len_min = pdu_len + label_len + PROTOCOL_LEN;
 
     // check label
     if label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
@@ -849,50 +1236,133 @@ pub fn encap_preview(
         return Err(EncapError::ErrorProtocolType);
     }
 
-    // if it fits into a complete package
-    let min_header_len = FIXED_HEADER_LEN + PROTOCOL_LEN + label_len;
-    let buffer_len = buffer.len();
+    start_pkt_preview(
+        PROTOCOL_LEN + label_len,
+        PROTOCOL_LEN + label_len,
+        pdu,
+        buffer,
+    )
+}
 
-    let pdu_len_encapsulated: usize;
-    let pkt_type: PktType;
-    let gse_len: u16;
-    let pkt_len: u16;
+/// Preview the complete packet or the first fragment starting the pdu
+///
+/// `fields_len` is the length of the protocol type, of the label sent and of the header extensions,
+/// `counted_len` the length of the fields counted in the total length with the pdu.
+fn start_pkt_preview(
+    fields_len: usize,
+    counted_len: usize,
+    pdu: &[&[u8]],
+    buffer: &[u8],
+) -> Result<EncapPreview, EncapError> {
+    let pdu_len = segments_len(pdu);
+    let (pkt_type, _, gse_len) = start_pkt_layout(fields_len, counted_len, pdu_len, buffer.len())?;
+    Ok(EncapPreview {
+        pkt_type,
+        pdu_len,
+        pkt_len: gse_len + FIXED_HEADER_LEN as u16,
+    })
+}
 
+/// Compute the layout of the complete packet or of the first fragment starting the pdu
+///
+/// `fields_len` is the length of the protocol type, of the label sent and of the header extensions,
+/// `counted_len` the length of the fields counted in the total length with the pdu.
+/// Return the type of the packet, the length of the pdu it carries and its gse length.
+fn start_pkt_layout(
+    fields_len: usize,
+    counted_len: usize,
+    pdu_len: usize,
+    buffer_len: usize,
+) -> Result<(PktType, usize, u16), EncapError> {
     // if all the data and metadata will fit in the buffer, and
-    // if the protocol can handle the size of the packer
-    if (buffer_len >= min_header_len + pdu_len) && (GSE_LEN_MAX >= gse_len_min) {
-        // complet packet
-        pkt_type = PktType::CompletePkt;
-        gse_len = gse_len_min as u16;
-        pkt_len = gse_len + FIXED_HEADER_LEN as u16;
-    } else {
-        // first packet
-        let min_header_len = min_header_len + FRAG_ID_LEN + TOTAL_LENGTH_LEN;
-
-        // check the buffer size
-        // if it cannot write at least more than the header
-        if buffer_len < min_header_len {
-            return Err(EncapError::ErrorSizeBuffer);
-        }
+    // if the protocol can handle the size of the packet
+    if FIXED_HEADER_LEN + fields_len + pdu_len <= buffer_len && fields_len + pdu_len <= GSE_LEN_MAX
+    {
+        return Ok((PktType::CompletePkt, pdu_len, (fields_len + pdu_len) as u16));
+    }
 
-        // check the metadata len
-        // if the protocol cannot handle such large amounts of data
-        if TOTAL_LEN_MAX < pdu_len + PROTOCOL_LEN + label_len {
-            return Err(EncapError::ErrorPduLength);
-        }
+    // first packet
+    let header_len = FIXED_HEADER_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN + fields_len;
 
-        pkt_type = PktType::FirstFragPkt;
-        pdu_len_encapsulated = buffer_len - min_header_len;
-        gse_len = (FRAG_ID_LEN + TOTAL_LENGTH_LEN + PROTOCOL_LEN + label_len + pdu_len_encapsulated)
-            as u16;
-        pkt_len = gse_len + (FIXED_HEADER_LEN) as u16;
+    // check the buffer size
+    // if it cannot write at least more than the header
+    if buffer_len < header_len {
+        return Err(EncapError::ErrorSizeBuffer);
     }
 
-    Ok(EncapPreview {
-        pkt_type,
-        pdu_len,
-        pkt_len,
-    })
+    // check the metadata len
+    // if the protocol cannot handle such large amounts of data
+    if TOTAL_LEN_MAX < pdu_len + counted_len {
+        return Err(EncapError::ErrorPduLength);
+    }
+
+    // the fragment is bounded by the buffer, the gse length and the pdu
+    let pdu_len_encapsulated = (buffer_len - header_len)
+        .min((GSE_LEN_MAX + FIXED_HEADER_LEN).saturating_sub(header_len))
+        .min(pdu_len);
+    let gse_len = header_len - FIXED_HEADER_LEN + pdu_len_encapsulated;
+    Ok((PktType::FirstFragPkt, pdu_len_encapsulated, gse_len as u16))
+}
+
+/// Check the header extensions given to [`Encapsulator::encap_ext`] or [`Encapsulator::encap_in_place`] and return the length they add to the header
+fn check_extensions(protocol_type: u16, extensions: &[Extension]) -> Result<usize, EncapError> {
+    let Some(last) = extensions.last() else {
+        return Err(EncapError::ErrorNoExtensionFound);
+    };
+
+    let mut total_len_extensions: usize = extensions.iter().map(Extension::len).sum();
+    if protocol_type < MAX_MANDATORY_VAL_PTYPE {
+        // the mandatory header extension replaces the protocol type
+        // checking if the last extension id corresponds to this protocol type
+        if last.id() != protocol_type && matches!(last.data(), ExtensionData::MandatoryData(..)) {
+            return Err(EncapError::ErrorFinalMandatoryExtensionHeader);
+        }
+        // the id of the final mandatory extension replace the protocol type len
+        total_len_extensions -= PROTOCOL_LEN;
+    } else if protocol_type < SECOND_RANGE_PTYPE {
+        return Err(EncapError::ErrorProtocolType);
+    }
+    Ok(total_len_extensions)
+}
+
+/// Write the protocol type, the label and the header extensions following the fixed header or the fragmentation fields
+///
+/// The first header extension id replaces the protocol type, written after the last header extension
+/// unless replaced by a final mandatory extension. Return the length written.
+fn write_header_fields(
+    buffer: &mut [u8],
+    protocol_type: u16,
+    label: &Label,
+    extensions: &[Extension],
+) -> usize {
+    let first_type = extensions.first().map_or(protocol_type, Extension::id);
+    buffer[..PROTOCOL_LEN].copy_from_slice(&first_type.to_be_bytes());
+    let mut offset = PROTOCOL_LEN;
+    buffer[offset..offset + label.len()].copy_from_slice(label.get_bytes());
+    offset += label.len();
+
+    for (i, extension) in extensions.iter().enumerate() {
+        let data: &[u8] = match extension.data() {
+            ExtensionData::Data2(data) => data,
+            ExtensionData::Data4(data) => data,
+            ExtensionData::Data6(data) => data,
+            ExtensionData::Data8(data) => data,
+            ExtensionData::NoData => &[],
+            ExtensionData::MandatoryData(data) => data,
+        };
+        buffer[offset..offset + data.len()].copy_from_slice(data);
+        offset += data.len();
+
+        // the next header extension id, or the protocol type if not replaced by a final mandatory extension
+        let next_type = match extensions.get(i + 1) {
+            Some(next) => next.id(),
+            None if protocol_type < MAX_MANDATORY_VAL_PTYPE => break,
+            None => protocol_type,
+        };
+        buffer[offset..offset + PROTOCOL_LEN].copy_from_slice(&next_type.to_be_bytes());
+        offset += PROTOCOL_LEN;
+    }
+    offset
 }
 
 /// Preview of GSE encapsulation for a PDU fragment.
@@ -915,10 +1385,21 @@ pub fn encap_frag_preview(
     pdu: &[u8],
     context: &ContextFrag,
     buffer: &[u8],
+) -> Result<EncapPreview, EncapError> {
+    encap_frag_preview_vectored(&[pdu], context, buffer)
+}
+
+/// Preview of GSE encapsulation for a fragment of a PDU given as a list of segments.
+///
+/// The function behaves as [`encap_frag_preview`].
+pub fn encap_frag_preview_vectored(
+    pdu: &[&[u8]],
+    context: &ContextFrag,
+    buffer: &[u8],
 ) -> Result<EncapPreview, EncapError> {
     let len_pdu_frag = context.len_pdu_frag as usize;
     let buffer_len = buffer.len();
-    let pdu_len = pdu.len();
+    let pdu_len = segments_len(pdu);
 
     // Metadata error
     if len_pdu_frag > pdu_len {
@@ -972,6 +1453,54 @@ pub fn encap_frag_preview(
     })
 }
 
+/// Total length of a pdu given as a list of segments
+fn segments_len(pdu: &[&[u8]]) -> usize {
+    pdu.iter().map(|segment| segment.len()).sum()
+}
+
+/// Iterate over the parts of the segments covering `len` bytes of the pdu from the offset `start`
+fn segments_range<'a>(
+    pdu: &'a [&'a [u8]],
+    start: usize,
+    len: usize,
+) -> impl Iterator<Item = &'a [u8]> {
+    let mut skip = start;
+    let mut remaining = len;
+    pdu.iter().filter_map(move |segment| {
+        if remaining == 0 {
+            return None;
+        }
+        if skip >= segment.len() {
+            skip -= segment.len();
+            return None;
+        }
+        let part = &segment[skip..segment.len().min(skip + remaining)];
+        skip = 0;
+        remaining -= part.len();
+        Some(part)
+    })
+}
+
+/// Copy the bytes of the pdu from the offset `start` into the whole buffer
+fn copy_segments(pdu: &[&[u8]], start: usize, buffer: &mut [u8]) {
+    let mut offset = 0;
+    for part in segments_range(pdu, start, buffer.len()) {
+        buffer[offset..offset + part.len()].copy_from_slice(part);
+        offset += part.len();
+    }
+}
+
+/// Update the crc with `len` bytes of the pdu from the offset `start`
+fn update_crc32_segments<C: CrcCalculator>(
+    crc_calculator: &C,
+    crc: u32,
+    pdu: &[&[u8]],
+    start: usize,
+    len: usize,
+) -> u32 {
+    segments_range(pdu, start, len).fold(crc, |crc, part| crc_calculator.update_crc32(crc, part))
+}
+
 /// Generate 16 bits gse header
 pub fn generate_gse_header(pkt_type: &PktType, label_type: &LabelType, gse_len: u16) -> u16 {
     let start_end_bits: u16 = match pkt_type {
@@ -0,0 +1,281 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the refragmentation of packets already built
+//!
+//! A packet built for a frame may have to be moved into a smaller one, e.g. after a change of MODCOD or for a retransmission.
+//! Instead of decapsulating and encapsulating the pdu again, [`Encapsulator::refrag`] splits the packet itself
+//! into a sequence of fragments fitting the new buffers, and [`Encapsulator::refrag_next`] writes the following ones.
+//!
+//! * A complete packet becomes a first fragment, some intermediate fragments and an end fragment.
+//!   The header extensions and the label are kept in the first fragment, the total length and the crc are computed.
+//! * A first fragment becomes a first fragment followed by intermediate fragments, with the same frag id and total length.
+//! * An intermediate fragment becomes several intermediate fragments.
+//! * An end fragment becomes intermediate fragments followed by an end fragment carrying the original crc.
+//!
+//! Once the whole packet has been written, the status completed packet is returned.
+#[cfg(test)]
+mod tests;
+
+use crate::crc::CrcCalculator;
+use crate::gse_encap::{
+    generate_gse_header, ContextFrag, EncapError, EncapStatus, Encapsulator, FragCrc,
+};
+use crate::gse_standard::{CRC_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, PROTOCOL_LEN, TOTAL_LENGTH_LEN};
+use crate::gse_view::GsePacketView;
+use crate::header_extension::MandatoryHeaderExtensionManager;
+use crate::label::LabelType;
+use crate::pkt_type::PktType;
+
+impl<C: CrcCalculator> Encapsulator<C> {
+    /// Refragmentation of a packet already built into a buffer smaller than the packet
+    ///
+    /// The packet is parsed with `manager` to find its payload behind the header extensions.
+    /// If the packet fits into the buffer, it is copied as is and the status completed packet is returned.
+    /// Else, the first fragment is written and a context of fragmentation is returned with the status fragmented packet,
+    /// the next fragments are written by [`Self::refrag_next`].
+    ///
+    /// `frag_id` is only used when a complete packet is fragmented, a fragment keeps its own frag id.
+    ///
+    /// # Example
+    /// ```
+    /// use dvb_gse_rust::crc::DefaultCrc;
+    /// use dvb_gse_rust::label::Label;
+    /// use dvb_gse_rust::gse_encap::{Encapsulator, EncapMetadata, EncapStatus};
+    /// use dvb_gse_rust::header_extension::SimpleMandatoryExtensionHeaderManager;
+    ///
+    /// let metadata = EncapMetadata::new(0xFFFF, Label::SixBytesLabel(*b"012345"));
+    /// let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    /// let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    /// let manager = SimpleMandatoryExtensionHeaderManager {};
+    ///
+    /// // a complete packet of 36 bytes
+    /// let mut packet = [0; 36];
+    /// encapsulator.encap(pdu, 0, metadata, &mut packet).unwrap();
+    ///
+    /// // moved into buffers of 20 bytes
+    /// let mut buffer = [0; 20];
+    /// let Ok(EncapStatus::FragmentedPkt(20, context)) = encapsulator.refrag(&packet, 1, &manager, &mut buffer) else { panic!() };
+    /// let Ok(EncapStatus::CompletedPkt(26)) = encapsulator.refrag_next(&packet, &context, &manager, &mut [0; 40]) else { panic!() };
+    /// ```
+    pub fn refrag<M: MandatoryHeaderExtensionManager>(
+        &self,
+        packet: &[u8],
+        frag_id: u8,
+        manager: &M,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let view = parse_packet(packet, manager)?;
+        let pkt_len = view.len();
+        let buffer_len = buffer.len();
+
+        // the packet does not need to be fragmented
+        if pkt_len <= buffer_len {
+            buffer[..pkt_len].copy_from_slice(view.as_bytes());
+            return Ok(EncapStatus::CompletedPkt(pkt_len as u16));
+        }
+
+        let payload = view.payload();
+        match (view.start_indicator(), view.end_indicator()) {
+            // complete packet: the header is moved in a first fragment
+            (true, true) => {
+                let header = &packet[FIXED_HEADER_LEN..pkt_len - payload.len()];
+                let min_header_len =
+                    FIXED_HEADER_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN + header.len();
+                if buffer_len < min_header_len {
+                    return Err(EncapError::ErrorSizeBuffer);
+                }
+                let pdu_len_encapsulated = buffer_len - min_header_len;
+                let label = view.label().unwrap();
+                let protocol_type = view.protocol_type().unwrap();
+                // the protocol type field is followed by the label and the header extensions
+                let protocol_type_field = u16::from_be_bytes([header[0], header[1]]);
+                let extensions = &header[PROTOCOL_LEN + label.len()..];
+                let total_len =
+                    self.extension_coverage
+                        .total_len(payload.len(), label.len(), extensions.len())
+                        as u16;
+
+                let gse_len = min_header_len - FIXED_HEADER_LEN + pdu_len_encapsulated;
+                let gse_header =
+                    generate_gse_header(&PktType::FirstFragPkt, &label.get_type(), gse_len as u16);
+                let mut offset = FIXED_HEADER_LEN;
+                buffer[..offset].copy_from_slice(&gse_header.to_be_bytes());
+                buffer[offset] = frag_id;
+                offset += FRAG_ID_LEN;
+                buffer[offset..offset + TOTAL_LENGTH_LEN].copy_from_slice(&total_len.to_be_bytes());
+                offset += TOTAL_LENGTH_LEN;
+                buffer[offset..offset + header.len()].copy_from_slice(header);
+                offset += header.len();
+                buffer[offset..offset + pdu_len_encapsulated]
+                    .copy_from_slice(&payload[..pdu_len_encapsulated]);
+
+                let context = ContextFrag {
+                    frag_id,
+                    crc: FragCrc::Final(self.crc_calculator.finalize_crc32(
+                        self.crc_calculator.update_crc32(
+                            self.extension_coverage.init_crc32(
+                                &self.crc_calculator,
+                                protocol_type_field,
+                                protocol_type,
+                                total_len,
+                                label.get_bytes(),
+                                extensions,
+                            ),
+                            payload,
+                        ),
+                    )),
+                    len_pdu_frag: pdu_len_encapsulated as u16,
+                };
+                Ok(EncapStatus::FragmentedPkt(buffer_len as u16, context))
+            }
+            // first fragment: the header is kept, only the payload is cut
+            (true, false) => {
+                let header_len = pkt_len - payload.len();
+                if buffer_len < header_len {
+                    return Err(EncapError::ErrorSizeBuffer);
+                }
+                let pdu_len_encapsulated = buffer_len - header_len;
+                let gse_header = generate_gse_header(
+                    &PktType::FirstFragPkt,
+                    &view.label_type(),
+                    (buffer_len - FIXED_HEADER_LEN) as u16,
+                );
+                buffer[..FIXED_HEADER_LEN].copy_from_slice(&gse_header.to_be_bytes());
+                buffer[FIXED_HEADER_LEN..buffer_len]
+                    .copy_from_slice(&packet[FIXED_HEADER_LEN..buffer_len]);
+
+                let context =
+                    ContextFrag::new(view.frag_id().unwrap(), 0, pdu_len_encapsulated as u16);
+                Ok(EncapStatus::FragmentedPkt(buffer_len as u16, context))
+            }
+            // intermediate or end fragment: the payload is cut in intermediate fragments
+            (false, _) => {
+                let context = ContextFrag::new(view.frag_id().unwrap(), 0, 0);
+                refrag_payload(payload, view.crc(), &context, buffer)
+            }
+        }
+    }
+
+    /// Refragmentation of the rest of a packet, after [`Self::refrag`]
+    ///
+    /// `packet` and `manager` must be the ones given to [`Self::refrag`], `context` is the last context of fragmentation returned.
+    /// If the rest of the packet fits into the buffer, the function returns the status completed packet.
+    /// Else, the rest is partially written and a new context of fragmentation is returned with the status fragmented packet.
+    pub fn refrag_next<M: MandatoryHeaderExtensionManager>(
+        &self,
+        packet: &[u8],
+        context: &ContextFrag,
+        manager: &M,
+        buffer: &mut [u8],
+    ) -> Result<EncapStatus, EncapError> {
+        let view = parse_packet(packet, manager)?;
+        let payload = view.payload();
+        if context.len_pdu_frag as usize > payload.len() {
+            return Err(EncapError::ErrorPduLength);
+        }
+
+        match (view.start_indicator(), view.end_indicator()) {
+            // complete packet: the pdu is fragmented as usual
+            (true, true) => self.write_frag_vectored(&[payload], context, buffer),
+            // fragment: the payload is cut, the crc of an end fragment is kept
+            _ => refrag_payload(payload, view.crc(), context, buffer),
+        }
+    }
+}
+
+/// Parse the packet to refragment, padding can not be refragmented
+fn parse_packet<'a, M: MandatoryHeaderExtensionManager>(
+    packet: &'a [u8],
+    manager: &'a M,
+) -> Result<GsePacketView<'a, M>, EncapError> {
+    match GsePacketView::parse(packet, manager) {
+        Ok(view) if !view.is_padding() => Ok(view),
+        _ => Err(EncapError::ErrorInvalidPacket),
+    }
+}
+
+/// Write the payload of a fragment not yet written
+///
+/// The payload is written in intermediate fragments, followed by an end fragment carrying `crc` if there is one.
+/// Return the status completed packet once the whole fragment has been written.
+fn refrag_payload(
+    payload: &[u8],
+    crc: Option<u32>,
+    context: &ContextFrag,
+    buffer: &mut [u8],
+) -> Result<EncapStatus, EncapError> {
+    let remaining = &payload[context.len_pdu_frag as usize..];
+    let Some(crc) = crc else {
+        return write_intermediate(payload, context, buffer);
+    };
+
+    let gse_len = FRAG_ID_LEN + remaining.len() + CRC_LEN;
+    if buffer.len() < FIXED_HEADER_LEN + gse_len {
+        return match write_intermediate(payload, context, buffer)? {
+            // the crc is still to be written in an end fragment
+            EncapStatus::CompletedPkt(pkt_len) => Ok(EncapStatus::FragmentedPkt(
+                pkt_len,
+                ContextFrag {
+                    len_pdu_frag: payload.len() as u16,
+                    ..*context
+                },
+            )),
+            status => Ok(status),
+        };
+    }
+
+    let gse_header = generate_gse_header(&PktType::EndFragPkt, &LabelType::ReUse, gse_len as u16);
+    let mut offset = FIXED_HEADER_LEN;
+    buffer[..offset].copy_from_slice(&gse_header.to_be_bytes());
+    buffer[offset] = context.frag_id;
+    offset += FRAG_ID_LEN;
+    buffer[offset..offset + remaining.len()].copy_from_slice(remaining);
+    offset += remaining.len();
+    buffer[offset..offset + CRC_LEN].copy_from_slice(&crc.to_be_bytes());
+    offset += CRC_LEN;
+    Ok(EncapStatus::CompletedPkt(offset as u16))
+}
+
+/// Write the payload not yet written in an intermediate fragment
+///
+/// Return the status completed packet if the whole payload has been written.
+fn write_intermediate(
+    payload: &[u8],
+    context: &ContextFrag,
+    buffer: &mut [u8],
+) -> Result<EncapStatus, EncapError> {
+    let len_pdu_frag = context.len_pdu_frag as usize;
+    let pdu_len_remaining = payload.len() - len_pdu_frag;
+
+    // an intermediate fragment carries at least one byte
+    if buffer.len() <= FIXED_HEADER_LEN + FRAG_ID_LEN || pdu_len_remaining == 0 {
+        return Err(EncapError::ErrorSizeBuffer);
+    }
+    let pdu_len_encapsulated = pdu_len_remaining.min(buffer.len() - FIXED_HEADER_LEN - FRAG_ID_LEN);
+    let gse_len = FRAG_ID_LEN + pdu_len_encapsulated;
+
+    let gse_header = generate_gse_header(
+        &PktType::IntermediateFragPkt,
+        &LabelType::ReUse,
+        gse_len as u16,
+    );
+    buffer[..FIXED_HEADER_LEN].copy_from_slice(&gse_header.to_be_bytes());
+    buffer[FIXED_HEADER_LEN] = context.frag_id;
+    let offset = FIXED_HEADER_LEN + FRAG_ID_LEN;
+    buffer[offset..offset + pdu_len_encapsulated]
+        .copy_from_slice(&payload[len_pdu_frag..len_pdu_frag + pdu_len_encapsulated]);
+
+    let pkt_len = (FIXED_HEADER_LEN + gse_len) as u16;
+    if pdu_len_encapsulated == pdu_len_remaining {
+        Ok(EncapStatus::CompletedPkt(pkt_len))
+    } else {
+        Ok(EncapStatus::FragmentedPkt(
+            pkt_len,
+            ContextFrag {
+                len_pdu_frag: (len_pdu_frag + pdu_len_encapsulated) as u16,
+                ..*context
+            },
+        ))
+    }
+}
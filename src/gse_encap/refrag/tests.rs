@@ -0,0 +1,225 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::DefaultCrc;
+use crate::gse_decap::{DecapMetadata, DecapStatus, Decapsulator, GseDecapMemory, SimpleGseMemory};
+use crate::gse_encap::{EncapError, EncapMetadata, EncapStatus, Encapsulator};
+use crate::gse_view::GsePacketView;
+use crate::header_extension::{
+    Extension, ExtensionCoverage, SimpleMandatoryExtensionHeaderManager,
+};
+use crate::label::Label;
+
+const MANAGER: SimpleMandatoryExtensionHeaderManager = SimpleMandatoryExtensionHeaderManager {};
+const PDU: &[u8; 52] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Refragment the whole packet in buffers of `buffer_len` bytes, return the packets written back to back
+fn refrag_all(packet: &[u8], buffer_len: usize) -> (Vec<u8>, usize) {
+    let encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut out = vec![];
+    let mut buffer = vec![0; buffer_len];
+    let mut status = encapsulator
+        .refrag(packet, 42, &MANAGER, &mut buffer)
+        .unwrap();
+    let mut pkt_count = 1;
+    loop {
+        match status {
+            EncapStatus::CompletedPkt(len) => {
+                out.extend_from_slice(&buffer[..len as usize]);
+                return (out, pkt_count);
+            }
+            EncapStatus::FragmentedPkt(len, context) => {
+                out.extend_from_slice(&buffer[..len as usize]);
+                status = encapsulator
+                    .refrag_next(packet, &context, &MANAGER, &mut buffer)
+                    .unwrap();
+                pkt_count += 1;
+            }
+        }
+    }
+}
+
+/// Decapsulate all the packets of the buffer, return the last status
+fn decap_all(buffer: &[u8]) -> DecapStatus {
+    let mut memory = SimpleGseMemory::new(1, PDU.len(), 0, 0);
+    memory
+        .provision_storage(vec![0; PDU.len()].into_boxed_slice())
+        .unwrap();
+    let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, MANAGER);
+    let mut offset = 0;
+    loop {
+        let (status, pkt_len) = decapsulator.decap(&buffer[offset..]).unwrap();
+        offset += pkt_len;
+        if offset == buffer.len() {
+            return status;
+        }
+    }
+}
+
+#[test]
+fn test_refrag_001() {
+    let comment = "complete packet refragmented in a first, intermediate and end fragments";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let mut packet = [0; 62];
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.encap(PDU, 0, metadata, &mut packet).unwrap();
+
+    let (fragments, pkt_count) = refrag_all(&packet, 20);
+
+    let first = GsePacketView::parse(&fragments, &MANAGER).unwrap();
+    assert_eq!(Some(42), first.frag_id(), "{}", comment);
+    assert_eq!(Some(60), first.total_length(), "{}", comment);
+    assert_eq!(4, pkt_count, "{}", comment);
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::SixBytesLabel(*b"012345"), vec![]),
+    );
+    assert_eq!(exp_status, decap_all(&fragments), "{}", comment);
+}
+
+#[test]
+fn test_refrag_002() {
+    let comment = "complete packet with header extensions refragmented";
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let extensions = vec![
+        Extension::new(0x0300, &[1, 2, 3, 4]).unwrap(),
+        Extension::new(0x0100, &[]).unwrap(),
+    ];
+    let mut packet = [0; 67];
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let status = encapsulator
+        .encap_ext(PDU, 0, metadata, &mut packet, extensions.clone())
+        .unwrap();
+    assert_eq!(EncapStatus::CompletedPkt(67), status, "{}", comment);
+
+    let (fragments, _) = refrag_all(&packet, 30);
+
+    let first = GsePacketView::parse(&fragments, &MANAGER).unwrap();
+    assert_eq!(
+        extensions,
+        first.extensions().collect::<Vec<_>>(),
+        "{}",
+        comment
+    );
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(
+            PDU.len(),
+            0x1234,
+            Label::ThreeBytesLabel(*b"abc"),
+            extensions,
+        ),
+    );
+    assert_eq!(exp_status, decap_all(&fragments), "{}", comment);
+}
+
+#[test]
+fn test_refrag_003() {
+    let comment = "first and end fragments refragmented, the frag id and the crc are kept";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let mut buffer = [0; 100];
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let Ok(EncapStatus::FragmentedPkt(len1, context)) =
+        encapsulator.encap(PDU, 7, metadata, &mut buffer[..40])
+    else {
+        panic!("{}", comment)
+    };
+    let len1 = len1 as usize;
+    let Ok(EncapStatus::CompletedPkt(len2)) =
+        encapsulator.encap_frag(PDU, &context, &mut buffer[len1..])
+    else {
+        panic!("{}", comment)
+    };
+    let len2 = len2 as usize;
+
+    let (mut fragments, count1) = refrag_all(&buffer[..len1], 12);
+    let (end, count2) = refrag_all(&buffer[len1..len1 + len2], 10);
+    fragments.extend_from_slice(&end);
+
+    assert_eq!(5, count1, "{}", comment);
+    assert_eq!(4, count2, "{}", comment);
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::Broadcast, vec![]),
+    );
+    assert_eq!(exp_status, decap_all(&fragments), "{}", comment);
+}
+
+#[test]
+fn test_refrag_004() {
+    let comment = "packet fitting into the buffer is copied as is";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let mut packet = [0; 56];
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.encap(PDU, 0, metadata, &mut packet).unwrap();
+
+    let mut buffer = [0; 100];
+    let status = encapsulator.refrag(&packet, 0, &MANAGER, &mut buffer);
+
+    assert_eq!(Ok(EncapStatus::CompletedPkt(56)), status, "{}", comment);
+    assert_eq!(packet, buffer[..56], "{}", comment);
+}
+
+#[test]
+fn test_refrag_005() {
+    let comment = "padding and buffers too small";
+    let encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packet = [0; 56];
+    let status = encapsulator.refrag(&packet, 0, &MANAGER, &mut [0; 20]);
+    assert_eq!(Err(EncapError::ErrorInvalidPacket), status, "{}", comment);
+
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    Encapsulator::new(DefaultCrc {})
+        .encap(PDU, 0, metadata, &mut packet[..20])
+        .unwrap();
+    // the header of the first fragment takes 13 bytes
+    let status = encapsulator.refrag(&packet, 0, &MANAGER, &mut [0; 12]);
+    assert_eq!(Err(EncapError::ErrorSizeBuffer), status, "{}", comment);
+
+    // an intermediate fragment carries at least one byte
+    let end = [0x50, 0x07, 0x07, b'a', b'b', 0, 0, 0, 0];
+    let status = encapsulator.refrag(&end, 0, &MANAGER, &mut [0; 3]);
+    assert_eq!(Err(EncapError::ErrorSizeBuffer), status, "{}", comment);
+}
+
+#[test]
+fn test_refrag_006() {
+    let comment =
+        "first fragment with a header extension as written by encap_ext, for each coverage";
+    let metadata = EncapMetadata::new(0x0800, Label::ThreeBytesLabel(*b"abc"));
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+
+    for coverage in [ExtensionCoverage::Standard, ExtensionCoverage::Legacy] {
+        let mut encapsulator = Encapsulator::new(DefaultCrc {});
+        encapsulator.set_extension_coverage(coverage);
+
+        let mut exp = [0; 30];
+        let Ok(EncapStatus::FragmentedPkt(_, exp_context)) =
+            encapsulator
+                .clone()
+                .encap_ext(PDU, 42, metadata, &mut exp, extensions.clone())
+        else {
+            panic!("{}: {:?}", comment, coverage)
+        };
+
+        let mut packet = [0; 100];
+        let Ok(EncapStatus::CompletedPkt(pkt_len)) =
+            encapsulator
+                .clone()
+                .encap_ext(PDU, 0, metadata, &mut packet, extensions.clone())
+        else {
+            panic!("{}: {:?}", comment, coverage)
+        };
+        let mut obs = [0; 30];
+        let obs_status = encapsulator.refrag(&packet[..pkt_len as usize], 42, &MANAGER, &mut obs);
+
+        assert_eq!(
+            Ok(EncapStatus::FragmentedPkt(30, exp_context)),
+            obs_status,
+            "{}: {:?}",
+            comment,
+            coverage
+        );
+        assert_eq!(exp, obs, "{}: {:?}", comment, coverage);
+    }
+}
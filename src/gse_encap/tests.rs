@@ -3,14 +3,19 @@
 
 use crate::crc::{CrcCalculator, DefaultCrc};
 use crate::gse_encap::{
-    encap_frag_preview, encap_preview, generate_gse_header, ContextFrag, EncapError, EncapMetadata,
-    EncapStatus, Encapsulator,
+    encap_frag_preview, encap_frag_preview_vectored, encap_preview, encap_preview_vectored,
+    generate_gse_header, ContextFrag, EncapError, EncapMetadata, EncapStatus, Encapsulator,
+    FragCrc,
 };
 use crate::gse_standard::{
     COMPLETE_PKT, CRC_LEN, END_PKT, FIRST_PKT, FIXED_HEADER_LEN, FRAG_ID_LEN, INTERMEDIATE_PKT,
     LABEL_3_B, LABEL_3_B_LEN, LABEL_6_B, LABEL_6_B_LEN, LABEL_BROADCAST, LABEL_BROADCAST_LEN,
     LABEL_REUSE, LABEL_REUSE_LEN, PROTOCOL_LEN, TOTAL_LENGTH_LEN,
 };
+use crate::header_extension::{
+    Extension, ExtensionCoverage, ExtensionData, ExtensionHandler, ExtensionHandlerError,
+    ExtensionRegistry,
+};
 use crate::label::{Label, LabelType};
 use crate::pkt_type::PktType;
 use crate::utils::{
@@ -390,12 +395,12 @@ fn test_encap_003() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -453,12 +458,12 @@ fn test_encap_004() {
         13,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: 0,
         },
     ));
@@ -581,12 +586,12 @@ fn test_encap_007() {
         (FIRST_FRAG_LEN + EXP_PDU_LEN) as u16,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: EXP_PDU_LEN as u16,
         },
     ));
@@ -688,12 +693,12 @@ fn test_encap_009() {
         (FIRST_FRAG_LEN + EXP_PDU_LEN) as u16,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: EXP_PDU_LEN as u16,
         },
     ));
@@ -792,12 +797,12 @@ fn test_encap_011() {
         (PKT_LEN - 1) as u16,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: EXP_PDU_LEN as u16,
         },
     ));
@@ -1034,12 +1039,12 @@ fn test_encap_018() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -1096,12 +1101,12 @@ fn test_encap_019() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -1245,12 +1250,12 @@ fn test_encap_022() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -1308,12 +1313,12 @@ fn test_encap_023() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -1415,12 +1420,12 @@ fn test_encap_025() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -1520,12 +1525,12 @@ fn test_encap_027() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -1626,12 +1631,12 @@ fn test_encap_029() {
         PKT_LEN as u16 - 1,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: DefaultCrc {}.calculate_crc32(
+            crc: FragCrc::Final(DefaultCrc {}.calculate_crc32(
                 b"abcdefghijklmnopqrstuvwxyz",
                 exp_protocol_type,
                 exp_total_len,
                 exp_label.get_bytes(),
-            ),
+            )),
             len_pdu_frag: (PKT_LEN
                 - (1 + FIXED_HEADER_LEN
                     + FRAG_ID_LEN
@@ -2043,7 +2048,7 @@ fn test_encap_frag_001() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; 1000];
 
@@ -2085,7 +2090,7 @@ fn test_encap_frag_002() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; PKT_LEN];
 
@@ -2125,7 +2130,7 @@ fn test_encap_frag_003() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; PKT_LEN - 1];
 
@@ -2138,7 +2143,7 @@ fn test_encap_frag_003() {
         (FIXED_HEADER_LEN + FRAG_ID_LEN + EXP_PDU_LEN) as u16,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: 88,
+            crc: FragCrc::Final(88),
             len_pdu_frag: PDU_LEN as u16,
         },
     ));
@@ -2171,7 +2176,7 @@ fn test_encap_frag_004() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: 10,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; PKT_LEN - CRC_LEN - 1];
 
@@ -2184,7 +2189,7 @@ fn test_encap_frag_004() {
         (PKT_LEN - 5) as u16,
         ContextFrag {
             frag_id: DEFAULT_FRAG_ID,
-            crc: 88,
+            crc: FragCrc::Final(88),
             len_pdu_frag: (PDU_LEN - 1) as u16,
         },
     ));
@@ -2215,7 +2220,7 @@ fn test_encap_frag_005() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; FIXED_HEADER_LEN + FRAG_ID_LEN];
 
@@ -2238,7 +2243,7 @@ fn test_encap_frag_006() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: 26,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; 1000];
 
@@ -2272,7 +2277,7 @@ fn test_encap_frag_007() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: 26 + 1,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; 1000];
 
@@ -2283,6 +2288,102 @@ fn test_encap_frag_007() {
     assert_eq!(exp_status, obs_status, "{}", comment);
 }
 
+/// test: final crc given to the context, kept by the intermediate packet and written as it is by the end packet
+#[test]
+fn test_encap_frag_008() {
+    let comment = "final crc: written unchanged in the end packet";
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.disable_re_use_label();
+
+    let pdu_in = b"----------abcdefghijklmnopqrstuvwxyz";
+    let context_frag_in = ContextFrag::new(DEFAULT_FRAG_ID, 88, 10);
+    let mut buffer_in = [0; 16];
+
+    let exp_status = Ok(EncapStatus::FragmentedPkt(
+        16,
+        ContextFrag::new(DEFAULT_FRAG_ID, 88, 23),
+    ));
+    let obs_status = encapsulator.encap_frag(pdu_in, &context_frag_in, &mut buffer_in);
+    assert_eq!(exp_status, obs_status, "{}", comment);
+
+    let EncapStatus::FragmentedPkt(_, context_frag) = obs_status.unwrap() else {
+        unreachable!()
+    };
+    let mut buffer_in = [0; 1000];
+
+    let exp_values = GseEndFragPacket::new(
+        (13 + FRAG_ID_LEN + CRC_LEN) as u16,
+        DEFAULT_FRAG_ID,
+        b"nopqrstuvwxyz",
+        88,
+    );
+    let exp_status = Ok(EncapStatus::CompletedPkt(
+        (FIXED_HEADER_LEN + FRAG_ID_LEN + 13 + CRC_LEN) as u16,
+    ));
+
+    test_encap_frag!(
+        comment,
+        encapsulator,
+        pdu_in,
+        &context_frag,
+        buffer_in,
+        exp_values,
+        exp_status,
+        GseEndFragPacket
+    );
+}
+
+/// test: partial crc given to the context, updated by the intermediate packet and finalized by the end packet
+#[test]
+fn test_encap_frag_009() {
+    let comment = "partial crc: finalized in the end packet";
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.disable_re_use_label();
+
+    let pdu_in = b"----------abcdefghijklmnopqrstuvwxyz";
+    let context_frag_in = ContextFrag::with_partial_crc(DEFAULT_FRAG_ID, 88, 10);
+    let mut buffer_in = [0; 16];
+
+    let exp_status = Ok(EncapStatus::FragmentedPkt(
+        16,
+        ContextFrag::with_partial_crc(
+            DEFAULT_FRAG_ID,
+            DefaultCrc {}.update_crc32(88, b"abcdefghijklm"),
+            23,
+        ),
+    ));
+    let obs_status = encapsulator.encap_frag(pdu_in, &context_frag_in, &mut buffer_in);
+    assert_eq!(exp_status, obs_status, "{}", comment);
+
+    let EncapStatus::FragmentedPkt(_, context_frag) = obs_status.unwrap() else {
+        unreachable!()
+    };
+    let mut buffer_in = [0; 1000];
+
+    let exp_values = GseEndFragPacket::new(
+        (13 + FRAG_ID_LEN + CRC_LEN) as u16,
+        DEFAULT_FRAG_ID,
+        b"nopqrstuvwxyz",
+        DefaultCrc {}.finalize_crc32(DefaultCrc {}.update_crc32(88, b"abcdefghijklmnopqrstuvwxyz")),
+    );
+    let exp_status = Ok(EncapStatus::CompletedPkt(
+        (FIXED_HEADER_LEN + FRAG_ID_LEN + 13 + CRC_LEN) as u16,
+    ));
+
+    test_encap_frag!(
+        comment,
+        encapsulator,
+        pdu_in,
+        &context_frag,
+        buffer_in,
+        exp_values,
+        exp_status,
+        GseEndFragPacket
+    );
+}
+
 /// Encap preview function tests
 macro_rules! test_encap_frag_preview {
     ($comment:expr, $pdu:expr, $context_frag:expr, $buffer:expr, $pkt_type:ty) => {
@@ -2324,7 +2425,7 @@ fn test_encap_frag_preview_001() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; 1000];
 
@@ -2350,7 +2451,7 @@ fn test_encap_frag_preview_002() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; PKT_LEN];
 
@@ -2376,7 +2477,7 @@ fn test_encap_frag_preview_003() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; PKT_LEN - 1];
 
@@ -2402,7 +2503,7 @@ fn test_encap_frag_preview_004() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: 10,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; PKT_LEN - CRC_LEN - 1];
 
@@ -2426,7 +2527,7 @@ fn test_encap_frag_preview_005() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: PDU_FRAG_LEN as u16,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; FIXED_HEADER_LEN + FRAG_ID_LEN];
 
@@ -2448,7 +2549,7 @@ fn test_encap_frag_preview_006() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: 26,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; 1000];
 
@@ -2470,7 +2571,7 @@ fn test_encap_frag_preview_007() {
     let context_frag_in = ContextFrag {
         frag_id: DEFAULT_FRAG_ID,
         len_pdu_frag: 26 + 1,
-        crc: 88,
+        crc: FragCrc::Final(88),
     };
     let mut buffer_in = [0; 1000];
 
@@ -2482,3 +2583,529 @@ fn test_encap_frag_preview_007() {
         GseEndFragPacket
     );
 }
+
+/// Encapsulate the whole pdu in packets of at most `max_pkt_len` bytes, return the packets back to back
+fn encap_all_vectored(pdu: &[&[u8]], metadata: EncapMetadata, max_pkt_len: usize) -> Vec<u8> {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packets = vec![];
+    let mut buffer = vec![0; max_pkt_len];
+    let mut status = encapsulator
+        .encap_vectored(pdu, DEFAULT_FRAG_ID, metadata, &mut buffer)
+        .unwrap();
+    loop {
+        match status {
+            EncapStatus::CompletedPkt(len) => {
+                packets.extend_from_slice(&buffer[..len as usize]);
+                return packets;
+            }
+            EncapStatus::FragmentedPkt(len, context) => {
+                packets.extend_from_slice(&buffer[..len as usize]);
+                status = encapsulator
+                    .encap_frag_vectored(pdu, &context, &mut buffer)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_encap_vectored_001() {
+    let comment = "segments encapsulated as the contiguous pdu, with every length of packet";
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let segments: [&[u8]; 5] = [b"abcd", b"", b"efghijklm", b"n", b"opqrstuvwxyz"];
+
+    for max_pkt_len in 12..40 {
+        let exp = encap_all_vectored(&[pdu], metadata, max_pkt_len);
+        let obs = encap_all_vectored(&segments, metadata, max_pkt_len);
+        assert_eq!(exp, obs, "{}: {}", comment, max_pkt_len);
+    }
+}
+
+#[test]
+fn test_encap_vectored_002() {
+    let comment = "segments encapsulated with header extensions as the contiguous pdu";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+    let segments: [&[u8]; 3] = [b"abcdefghij", b"klmnopq", b"rstuvwxyz"];
+
+    let mut exp = [0; 100];
+    let exp_status = Encapsulator::new(DefaultCrc {}).encap_ext(
+        b"abcdefghijklmnopqrstuvwxyz",
+        DEFAULT_FRAG_ID,
+        metadata,
+        &mut exp,
+        extensions.clone(),
+    );
+    let mut obs = [0; 100];
+    let obs_status = Encapsulator::new(DefaultCrc {}).encap_ext_vectored(
+        &segments,
+        DEFAULT_FRAG_ID,
+        metadata,
+        &mut obs,
+        extensions,
+    );
+
+    assert_eq!(exp_status, obs_status, "{}", comment);
+    assert_eq!(exp, obs, "{}", comment);
+}
+
+#[test]
+fn test_encap_preview_vectored_001() {
+    let comment = "preview of segments is the preview of the contiguous pdu";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let segments: [&[u8]; 2] = [b"abcdefghijklm", b"nopqrstuvwxyz"];
+    let context = ContextFrag::new(DEFAULT_FRAG_ID, 0, 10);
+
+    for buffer_len in 0..50 {
+        let buffer = vec![0; buffer_len];
+        assert_eq!(
+            encap_preview(pdu, metadata, &buffer),
+            encap_preview_vectored(&segments, metadata, &buffer),
+            "{}: {}",
+            comment,
+            buffer_len
+        );
+        assert_eq!(
+            encap_frag_preview(pdu, &context, &buffer),
+            encap_frag_preview_vectored(&segments, &context, &buffer),
+            "{}: {}",
+            comment,
+            buffer_len
+        );
+    }
+}
+
+/// Return the packet type and the packet length of the status of an encapsulation
+fn preview_of(status: EncapStatus) -> (PktType, u16) {
+    match status {
+        EncapStatus::CompletedPkt(pkt_len) => (PktType::CompletePkt, pkt_len),
+        EncapStatus::FragmentedPkt(pkt_len, _) => (PktType::FirstFragPkt, pkt_len),
+    }
+}
+
+#[test]
+fn test_encap_preview_state_001() {
+    let comment = "preview of the encapsulator matches the packet written with a re used label";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut buffer = [0; 100];
+    encapsulator
+        .encap(pdu, DEFAULT_FRAG_ID, metadata, &mut buffer)
+        .unwrap();
+
+    for buffer_len in 14..40 {
+        let mut buffer = vec![0; buffer_len];
+        let preview = encapsulator.encap_preview(pdu, metadata, &buffer).unwrap();
+        let mut encapsulator = encapsulator.clone();
+        let status = encapsulator
+            .encap(pdu, DEFAULT_FRAG_ID, metadata, &mut buffer)
+            .unwrap();
+
+        assert_eq!(
+            preview_of(status),
+            (preview.pkt_type(), preview.pkt_len()),
+            "{}: {}",
+            comment,
+            buffer_len
+        );
+    }
+}
+
+#[test]
+fn test_encap_preview_state_002() {
+    let comment = "preview of the encapsulator does not update the re use state";
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let buffer = [0; 100];
+
+    let exp = encapsulator.clone();
+    let first = encapsulator.encap_preview(pdu, metadata, &buffer).unwrap();
+    let second = encapsulator.encap_preview(pdu, metadata, &buffer).unwrap();
+
+    assert_eq!(exp, encapsulator, "{}", comment);
+    assert_eq!(first, second, "{}", comment);
+    assert_eq!(
+        (FIXED_HEADER_LEN + PROTOCOL_LEN + LABEL_3_B_LEN + pdu.len()) as u16,
+        first.pkt_len(),
+        "{}",
+        comment
+    );
+
+    encapsulator
+        .encap(pdu, DEFAULT_FRAG_ID, metadata, &mut [0; 100])
+        .unwrap();
+    let third = encapsulator.encap_preview(pdu, metadata, &buffer).unwrap();
+    assert_eq!(
+        first.pkt_len() - LABEL_3_B_LEN as u16,
+        third.pkt_len(),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_encap_preview_state_003() {
+    let comment = "preview with header extensions matches the packet written";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+    let encapsulator = Encapsulator::new(DefaultCrc {});
+
+    for buffer_len in 20..60 {
+        let mut buffer = vec![0; buffer_len];
+        let preview = encapsulator
+            .encap_ext_preview(pdu, metadata, &buffer, &extensions)
+            .unwrap();
+        let status = encapsulator.clone().encap_ext(
+            pdu,
+            DEFAULT_FRAG_ID,
+            metadata,
+            &mut buffer,
+            extensions.clone(),
+        );
+
+        assert_eq!(
+            preview_of(status.unwrap()),
+            (preview.pkt_type(), preview.pkt_len()),
+            "{}: {}",
+            comment,
+            buffer_len
+        );
+    }
+}
+
+#[test]
+fn test_encap_preview_state_004() {
+    let comment = "preview with header extensions returns the errors of the encapsulation";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let encapsulator = Encapsulator::new(DefaultCrc {});
+    let buffer = [0; 100];
+
+    assert_eq!(
+        Err(EncapError::ErrorNoExtensionFound),
+        encapsulator.encap_ext_preview(pdu, metadata, &buffer, &[]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(EncapError::ErrorSizeBuffer),
+        encapsulator.encap_ext_preview(
+            pdu,
+            metadata,
+            &buffer[..8],
+            &[Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()]
+        ),
+        "{}",
+        comment
+    );
+}
+
+/// Encapsulate the alphabet with the header extensions into a first fragment of 30 bytes and an end fragment
+fn encap_ext_fragments(
+    encapsulator: &mut Encapsulator<DefaultCrc>,
+    metadata: EncapMetadata,
+    extensions: Vec<Extension>,
+) -> (Vec<u8>, Vec<u8>) {
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let mut first = [0; 30];
+    let Ok(EncapStatus::FragmentedPkt(first_len, context)) =
+        encapsulator.encap_ext(pdu, 7, metadata, &mut first, extensions)
+    else {
+        panic!()
+    };
+    let mut end = [0; 100];
+    let Ok(EncapStatus::CompletedPkt(end_len)) = encapsulator.encap_frag(pdu, &context, &mut end)
+    else {
+        panic!()
+    };
+    (
+        first[..first_len as usize].to_vec(),
+        end[..end_len as usize].to_vec(),
+    )
+}
+
+#[test]
+fn test_encap_ext_coverage_001() {
+    let comment =
+        "header extension counted in the total length and covered by the crc (ETSI TS 102 606)";
+    let metadata = EncapMetadata::new(0x0800, Label::ThreeBytesLabel(*b"abc"));
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+    let exp_first = [
+        0x90, 0x1C, 0x07, 0x00, 0x25, 0x03, 0x00, 0x61, 0x62, 0x63, 0x01, 0x02, 0x03, 0x04, 0x08,
+        0x00, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E,
+    ];
+    // the crc 0x3016B561 was computed outside the crate, with a bit by bit Python implementation of the
+    // CRC-32/MPEG-2 of the crc catalogue (poly 0x04C11DB7, init 0xFFFFFFFF, check value 0x0376E6E7),
+    // over the total length 0x0025, the extension 0x0300, the label, the extension data, the protocol type and the pdu
+    let exp_end = [
+        0x70, 0x11, 0x07, 0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A,
+        0x30, 0x16, 0xB5, 0x61,
+    ];
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let (first, end) = encap_ext_fragments(&mut encapsulator, metadata, extensions);
+
+    assert_eq!(exp_first.to_vec(), first, "{}", comment);
+    assert_eq!(exp_end.to_vec(), end, "{}", comment);
+}
+
+#[test]
+fn test_encap_ext_coverage_002() {
+    let comment =
+        "final mandatory header extension, the total length counts no protocol type behind it";
+    let metadata = EncapMetadata::new(0x0081, Label::Broadcast);
+    let extensions = vec![Extension::new(0x0081, &[]).unwrap()];
+    let exp_first = [
+        0xA0, 0x13, 0x07, 0x00, 0x1C, 0x00, 0x81, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+        0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E,
+    ];
+    // the crc 0x0AB909EC was computed outside the crate, with a bit by bit Python implementation of the
+    // CRC-32/MPEG-2 of the crc catalogue (poly 0x04C11DB7, init 0xFFFFFFFF, check value 0x0376E6E7),
+    // over the total length 0x001C, the extension 0x0081 and the pdu
+    let exp_end = [
+        0x70, 0x11, 0x07, 0x6F, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A,
+        0x0A, 0xB9, 0x09, 0xEC,
+    ];
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut first = [0; 21];
+    let Ok(EncapStatus::FragmentedPkt(_, context)) = encapsulator.encap_ext(
+        b"abcdefghijklmnopqrstuvwxyz",
+        7,
+        metadata,
+        &mut first,
+        extensions,
+    ) else {
+        panic!("{}", comment)
+    };
+    let mut end = [0; 19];
+    encapsulator
+        .encap_frag(b"abcdefghijklmnopqrstuvwxyz", &context, &mut end)
+        .unwrap();
+
+    assert_eq!(exp_first, first, "{}", comment);
+    assert_eq!(exp_end, end, "{}", comment);
+}
+
+#[test]
+fn test_encap_ext_coverage_003() {
+    let comment = "legacy coverage, header extension left out of the total length and of the crc";
+    let metadata = EncapMetadata::new(0x0800, Label::ThreeBytesLabel(*b"abc"));
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.set_extension_coverage(ExtensionCoverage::Legacy);
+    let (first, end) = encap_ext_fragments(&mut encapsulator, metadata, extensions);
+
+    // total length of the pdu, the protocol type and the label
+    assert_eq!([0x00, 31], first[3..5], "{}", comment);
+    assert_eq!([0xBD, 0x40, 0xA1, 0x46], end[15..], "{}", comment);
+}
+
+/// Optional extension whose data can not be zero, replaced by one on encap
+struct NonZero;
+
+impl ExtensionHandler for NonZero {
+    fn encode(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::Data2([0, 0]) => Extension::new(extension.id(), &[0, 1])
+                .map_err(|_| ExtensionHandlerError::InvalidData),
+            _ => Ok(extension.clone()),
+        }
+    }
+}
+
+/// Optional extension never encoded
+struct Unencodable;
+
+impl ExtensionHandler for Unencodable {
+    fn encode(&self, _extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        Err(ExtensionHandlerError::InvalidData)
+    }
+}
+
+#[test]
+fn test_encap_ext_with_manager_001() {
+    let comment = "header extensions encoded by the handlers of the registry, extension without handler unchanged";
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0200, NonZero);
+    let metadata = EncapMetadata::new(0x0800, Label::Broadcast);
+    let mut buffer = [0; 100];
+    let exp_pkt = [
+        0xE0, 0x0E, 0x02, 0x00, 0x00, 0x01, 0x03, 0x00, 0x01, 0x02, 0x03, 0x04, 0x08, 0x00, 0x61,
+        0x62,
+    ];
+
+    let result = Encapsulator::new(DefaultCrc {}).encap_ext_with_manager(
+        b"ab",
+        0,
+        metadata,
+        &mut buffer,
+        vec![
+            Extension::new(0x0200, &[0, 0]).unwrap(),
+            Extension::new(0x0300, &[1, 2, 3, 4]).unwrap(),
+        ],
+        &registry,
+    );
+
+    assert_eq!(Ok(EncapStatus::CompletedPkt(16)), result, "{}", comment);
+    assert_eq!(exp_pkt, buffer[..16], "{}", comment);
+}
+
+#[test]
+fn test_encap_ext_with_manager_002() {
+    let comment = "header extension which can not be encoded, counted in the stats";
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0200, Unencodable);
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.enable_stats();
+    let metadata = EncapMetadata::new(0x0800, Label::Broadcast);
+
+    let result = encapsulator.encap_ext_with_manager(
+        b"ab",
+        0,
+        metadata,
+        &mut [0; 100],
+        vec![Extension::new(0x0200, &[0, 1]).unwrap()],
+        &registry,
+    );
+
+    assert_eq!(
+        Err(EncapError::ErrorInvalidHeaderExtension),
+        result,
+        "{}",
+        comment
+    );
+    assert_eq!(
+        1,
+        encapsulator
+            .stats()
+            .unwrap()
+            .error_count(&EncapError::ErrorInvalidHeaderExtension),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_encap_bridged_frame_001() {
+    let comment =
+        "ethernet frame sent with the bridged frame extension replacing the protocol type";
+    let frame = [
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x08, 0x00, 0x61,
+        0x62,
+    ];
+    let mut buffer = [0; 100];
+    let mut exp_pkt = vec![0xE0, 0x12, 0x00, 0x01];
+    exp_pkt.extend_from_slice(&frame);
+
+    let result = Encapsulator::new(DefaultCrc {}).encap_bridged_frame(
+        &frame,
+        0,
+        Label::Broadcast,
+        &mut buffer,
+    );
+
+    assert_eq!(Ok(EncapStatus::CompletedPkt(20)), result, "{}", comment);
+    assert_eq!(exp_pkt, buffer[..20], "{}", comment);
+}
+
+#[test]
+fn test_encap_bridged_frame_002() {
+    let comment = "frame too short to hold an ethernet header";
+
+    let result = Encapsulator::new(DefaultCrc {}).encap_bridged_frame(
+        &[0; 13],
+        0,
+        Label::Broadcast,
+        &mut [0; 100],
+    );
+
+    assert_eq!(
+        Err(EncapError::ErrorInvalidHeaderExtension),
+        result,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_encap_ext_concat_001() {
+    let comment =
+        "batch of pdus sent with the pdu concat extension, each pdu preceded by its length";
+    let mut buffer = [0; 100];
+    let exp_pkt = [
+        0xE0, 0x0B, 0x00, 0x03, 0x08, 0x00, 0x00, 0x02, b'a', b'b', 0x00, 0x01, b'c',
+    ];
+
+    let result = Encapsulator::new(DefaultCrc {}).encap_ext_concat(
+        &[b"ab", b"c"],
+        0,
+        EncapMetadata::new(0x0800, Label::Broadcast),
+        &mut buffer,
+        vec![],
+    );
+
+    assert_eq!(Ok(EncapStatus::CompletedPkt(13)), result, "{}", comment);
+    assert_eq!(exp_pkt, buffer[..13], "{}", comment);
+}
+
+#[test]
+fn test_encap_ext_concat_002() {
+    let comment = "empty batch of pdus";
+
+    let result = Encapsulator::new(DefaultCrc {}).encap_ext_concat(
+        &[],
+        0,
+        EncapMetadata::new(0x0800, Label::Broadcast),
+        &mut [0; 100],
+        vec![],
+    );
+
+    assert_eq!(Err(EncapError::ErrorPduLength), result, "{}", comment);
+}
+
+#[test]
+fn test_encap_ts_concat_001() {
+    let comment = "ts packets sent with the ts concat extension replacing the protocol type";
+    let mut packets = [[0x11; 188], [0x22; 188]];
+    packets[0][0] = 0x47;
+    packets[1][0] = 0x47;
+    let mut buffer = [0; 400];
+    let mut exp_pkt = vec![0xE1, 0x7A, 0x00, 0x02];
+    exp_pkt.extend_from_slice(&packets[0]);
+    exp_pkt.extend_from_slice(&packets[1]);
+
+    let result = Encapsulator::new(DefaultCrc {}).encap_ts_concat(
+        &[&packets[0], &packets[1]],
+        0,
+        Label::Broadcast,
+        &mut buffer,
+    );
+
+    assert_eq!(Ok(EncapStatus::CompletedPkt(380)), result, "{}", comment);
+    assert_eq!(exp_pkt, buffer[..380], "{}", comment);
+}
+
+#[test]
+fn test_encap_ts_concat_002() {
+    let comment = "no ts packet, ts packet without sync byte, truncated ts packet";
+    let mut packet = [0; 188];
+    packet[0] = 0x47;
+    let encapsulator = &mut Encapsulator::new(DefaultCrc {});
+
+    for packets in [&[][..], &[&[0; 188][..]], &[&packet[..187]]] {
+        let result = encapsulator.encap_ts_concat(packets, 0, Label::Broadcast, &mut [0; 400]);
+        assert_eq!(
+            Err(EncapError::ErrorInvalidHeaderExtension),
+            result,
+            "{}",
+            comment
+        );
+    }
+}
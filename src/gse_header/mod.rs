@@ -0,0 +1,359 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the in-place access to the header of GSE packets
+//!
+//! These functions read or modify one field of a packet already written in a buffer,
+//! e.g. to translate the label of packets forwarded by a hub or to replace a re-use label before moving a packet to another frame.
+//!
+//! Changing the label type changes the length of the header: the rest of the packet is shifted and the gse length is updated.
+//! In a first fragment, the total length is updated too, and as the crc of the pdu covers the label and the protocol type,
+//! a crc correction is returned. It has to be applied to the end fragment of the same pdu with [`correct_crc`].
+//!
+//! # Example
+//!
+//! ```
+//! use dvb_gse_rust::crc::DefaultCrc;
+//! use dvb_gse_rust::gse_encap::{Encapsulator, EncapMetadata};
+//! use dvb_gse_rust::gse_header::{read_label, set_label};
+//! use dvb_gse_rust::header_extension::SimpleMandatoryExtensionHeaderManager;
+//! use dvb_gse_rust::label::Label;
+//!
+//! let metadata = EncapMetadata::new(0xFFFF, Label::ThreeBytesLabel(*b"abc"));
+//! let mut buffer = [0; 100];
+//! Encapsulator::new(DefaultCrc {}).encap(b"pdu", 0, metadata, &mut buffer).unwrap();
+//!
+//! // translation of the 3 bytes label into a 6 bytes label, the packet grows by 3 bytes
+//! let (pkt_len, _) = set_label(&mut buffer, Label::SixBytesLabel(*b"012345"), &DefaultCrc {}, &SimpleMandatoryExtensionHeaderManager {}).unwrap();
+//! assert_eq!(pkt_len, 2 + 2 + 6 + 3);
+//! assert_eq!(read_label(&buffer), Ok(Label::SixBytesLabel(*b"012345")));
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::crc::CrcCalculator;
+use crate::gse_decap::read_gse_header;
+use crate::gse_encap::generate_gse_header;
+use crate::gse_standard::{
+    CRC_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, GSE_LEN_MAX, PROTOCOL_LEN, SECOND_RANGE_PTYPE,
+    TOTAL_LENGTH_LEN, TOTAL_LEN_MAX,
+};
+use crate::gse_view::GsePacketView;
+use crate::header_extension::{ExtensionCoverage, MandatoryHeaderExtensionManager};
+use crate::label::{Label, LabelType};
+use crate::pkt_type::PktType;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Error returned by the functions of the [`crate::gse_header`] module
+pub enum HeaderError {
+    /// Indicates that the buffer does not start with a valid packet.
+    ErrorInvalidPacket,
+
+    /// Indicates that the field is not carried by this type of packet, e.g. the label of an intermediate fragment.
+    ErrorPktType,
+
+    /// Indicates that the label is a Six-Byte Label `{0, 0, 0, 0, 0, 0}`, which should only be used for padding purposes.
+    ErrorInvalidLabel,
+
+    /// Indicates that the protocol type is invalid, or that the packet carries header extensions in place of the protocol type.
+    ErrorProtocolType,
+
+    /// Indicates that the buffer is too small to contain the packet modified.
+    ErrorSizeBuffer,
+
+    /// Indicates that the packet modified exceeds the maximum gse length or total length.
+    ErrorLength,
+}
+
+impl HeaderError {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::ErrorInvalidPacket => "Buffer does not start with a valid packet",
+            Self::ErrorPktType => "Field not carried by this type of packet",
+            Self::ErrorInvalidLabel => "Label 6B [0, 0, 0, 0, 0, 0] shall not be used",
+            Self::ErrorProtocolType => "Protocol type can not be set",
+            Self::ErrorSizeBuffer => "Buffer too small to contain the packet modified",
+            Self::ErrorLength => "Packet modified too long for the gse length or the total length",
+        }
+    }
+}
+
+/// Header of the packet at the start of a buffer
+struct Header {
+    gse_len: usize,
+    pkt_type: PktType,
+    label_type: LabelType,
+}
+
+impl Header {
+    /// Read the fixed header and check that the whole packet is in the buffer
+    fn read(buffer: &[u8]) -> Result<Header, HeaderError> {
+        let Some(bytes) = buffer.get(..FIXED_HEADER_LEN) else {
+            return Err(HeaderError::ErrorInvalidPacket);
+        };
+        let Some((gse_len, pkt_type, label_type)) =
+            read_gse_header(u16::from_be_bytes([bytes[0], bytes[1]]))
+        else {
+            return Err(HeaderError::ErrorInvalidPacket);
+        };
+        let header = Header {
+            gse_len,
+            pkt_type,
+            label_type,
+        };
+        if buffer.len() < header.pkt_len() || gse_len < header.fields_len() {
+            return Err(HeaderError::ErrorInvalidPacket);
+        }
+        Ok(header)
+    }
+
+    fn pkt_len(&self) -> usize {
+        FIXED_HEADER_LEN + self.gse_len
+    }
+
+    /// Length of the fields that the gse length must at least contain
+    fn fields_len(&self) -> usize {
+        match self.pkt_type {
+            PktType::CompletePkt => PROTOCOL_LEN + self.label_type.len(),
+            PktType::FirstFragPkt => {
+                FRAG_ID_LEN + TOTAL_LENGTH_LEN + PROTOCOL_LEN + self.label_type.len()
+            }
+            PktType::IntermediateFragPkt => FRAG_ID_LEN,
+            PktType::EndFragPkt => FRAG_ID_LEN + CRC_LEN,
+        }
+    }
+
+    /// Offset of the protocol type field in a complete or a first fragment packet
+    fn protocol_type_offset(&self) -> Result<usize, HeaderError> {
+        match self.pkt_type {
+            PktType::CompletePkt => Ok(FIXED_HEADER_LEN),
+            PktType::FirstFragPkt => Ok(FIXED_HEADER_LEN + FRAG_ID_LEN + TOTAL_LENGTH_LEN),
+            _ => Err(HeaderError::ErrorPktType),
+        }
+    }
+}
+
+/// Read the gse length of the packet at the start of the buffer
+pub fn read_gse_len(buffer: &[u8]) -> Result<usize, HeaderError> {
+    Ok(Header::read(buffer)?.gse_len)
+}
+
+/// Read the frag id of the fragment at the start of the buffer
+pub fn read_frag_id(buffer: &[u8]) -> Result<u8, HeaderError> {
+    match Header::read(buffer)?.pkt_type {
+        PktType::CompletePkt => Err(HeaderError::ErrorPktType),
+        _ => Ok(buffer[FIXED_HEADER_LEN]),
+    }
+}
+
+/// Read the total length of the first fragment at the start of the buffer
+pub fn read_total_length(buffer: &[u8]) -> Result<u16, HeaderError> {
+    match Header::read(buffer)?.pkt_type {
+        PktType::FirstFragPkt => {
+            let offset = FIXED_HEADER_LEN + FRAG_ID_LEN;
+            Ok(u16::from_be_bytes([buffer[offset], buffer[offset + 1]]))
+        }
+        _ => Err(HeaderError::ErrorPktType),
+    }
+}
+
+/// Read the protocol type field of the complete or first fragment packet at the start of the buffer
+///
+/// If the packet carries header extensions, this field is the id of the first extension.
+pub fn read_protocol_type(buffer: &[u8]) -> Result<u16, HeaderError> {
+    let offset = Header::read(buffer)?.protocol_type_offset()?;
+    Ok(u16::from_be_bytes([buffer[offset], buffer[offset + 1]]))
+}
+
+/// Read the label of the complete or first fragment packet at the start of the buffer
+pub fn read_label(buffer: &[u8]) -> Result<Label, HeaderError> {
+    let header = Header::read(buffer)?;
+    let offset = header.protocol_type_offset()? + PROTOCOL_LEN;
+    Ok(Label::new(
+        &header.label_type,
+        &buffer[offset..offset + header.label_type.len()],
+    ))
+}
+
+/// Set the frag id of the fragment at the start of the buffer
+pub fn set_frag_id(buffer: &mut [u8], frag_id: u8) -> Result<(), HeaderError> {
+    match Header::read(buffer)?.pkt_type {
+        PktType::CompletePkt => Err(HeaderError::ErrorPktType),
+        _ => {
+            buffer[FIXED_HEADER_LEN] = frag_id;
+            Ok(())
+        }
+    }
+}
+
+/// Set the protocol type of the complete or first fragment packet at the start of the buffer
+///
+/// The protocol type can only be set on a packet without header extension.
+/// Return the crc correction to apply to the end fragment with [`correct_crc`], 0 for a complete packet.
+pub fn set_protocol_type<C: CrcCalculator>(
+    buffer: &mut [u8],
+    protocol_type: u16,
+    crc_calculator: &C,
+) -> Result<u32, HeaderError> {
+    let header = Header::read(buffer)?;
+    let offset = header.protocol_type_offset()?;
+    let old_protocol_type = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
+    if old_protocol_type < SECOND_RANGE_PTYPE || protocol_type < SECOND_RANGE_PTYPE {
+        return Err(HeaderError::ErrorProtocolType);
+    }
+
+    let mut correction = 0;
+    if header.pkt_type == PktType::FirstFragPkt {
+        let label = read_label(buffer)?;
+        let total_len = read_total_length(buffer)?;
+        let old_crc = crc_calculator.init_crc32(old_protocol_type, total_len, label.get_bytes());
+        let new_crc = crc_calculator.init_crc32(protocol_type, total_len, label.get_bytes());
+        correction = crc_correction(
+            crc_calculator,
+            old_crc ^ new_crc,
+            pdu_len(total_len, &label)?,
+        );
+    }
+
+    buffer[offset..offset + PROTOCOL_LEN].copy_from_slice(&protocol_type.to_be_bytes());
+    Ok(correction)
+}
+
+/// Set the label of the complete or first fragment packet at the start of the buffer
+///
+/// If the label type changes, the rest of the packet is shifted and the gse length is updated.
+/// The packet may grow into the bytes of the buffer following it, they must be free.
+/// The crc of a first fragment is expected to cover the header extensions, see [`set_label_with_coverage`] otherwise.
+///
+/// Return the new length of the packet and the crc correction to apply to the end fragment with [`correct_crc`], 0 for a complete packet.
+pub fn set_label<C: CrcCalculator, M: MandatoryHeaderExtensionManager>(
+    buffer: &mut [u8],
+    label: Label,
+    crc_calculator: &C,
+    manager: &M,
+) -> Result<(usize, u32), HeaderError> {
+    set_label_with_coverage(
+        buffer,
+        label,
+        crc_calculator,
+        manager,
+        ExtensionCoverage::Standard,
+    )
+}
+
+/// Set the label of the complete or first fragment packet at the start of the buffer, as [`set_label`]
+///
+/// `coverage` tells how the header extensions of a first fragment are covered by its total length and its crc.
+/// With [`ExtensionCoverage::Legacy`], the protocol type covered by the crc is read behind the header extensions with `manager`.
+pub fn set_label_with_coverage<C: CrcCalculator, M: MandatoryHeaderExtensionManager>(
+    buffer: &mut [u8],
+    label: Label,
+    crc_calculator: &C,
+    manager: &M,
+    coverage: ExtensionCoverage,
+) -> Result<(usize, u32), HeaderError> {
+    if label == Label::SixBytesLabel([0, 0, 0, 0, 0, 0]) {
+        return Err(HeaderError::ErrorInvalidLabel);
+    }
+    let header = Header::read(buffer)?;
+    let old_label = read_label(buffer)?;
+    let label_offset = header.protocol_type_offset()? + PROTOCOL_LEN;
+    let label_end = label_offset + old_label.len();
+
+    // check the new lengths
+    let gse_len = header.gse_len - old_label.len() + label.len();
+    if GSE_LEN_MAX < gse_len {
+        return Err(HeaderError::ErrorLength);
+    }
+    let pkt_len = FIXED_HEADER_LEN + gse_len;
+    if buffer.len() < pkt_len {
+        return Err(HeaderError::ErrorSizeBuffer);
+    }
+
+    let mut correction = 0;
+    if header.pkt_type == PktType::FirstFragPkt {
+        let old_total_len = read_total_length(buffer)?;
+        let total_len = old_total_len as usize - old_label.len() + label.len();
+        if TOTAL_LEN_MAX < total_len {
+            return Err(HeaderError::ErrorLength);
+        }
+        let total_len = total_len as u16;
+
+        // the crc covers the protocol type field, or the protocol type following the header extensions
+        let mut protocol_type = read_protocol_type(buffer)?;
+        if protocol_type < SECOND_RANGE_PTYPE && coverage == ExtensionCoverage::Legacy {
+            match GsePacketView::parse(&buffer[..header.pkt_len()], manager) {
+                Ok(view) => protocol_type = view.protocol_type().unwrap(),
+                Err(_) => return Err(HeaderError::ErrorInvalidPacket),
+            }
+        }
+        let old_crc =
+            crc_calculator.init_crc32(protocol_type, old_total_len, old_label.get_bytes());
+        let new_crc = crc_calculator.init_crc32(protocol_type, total_len, label.get_bytes());
+        correction = crc_correction(
+            crc_calculator,
+            old_crc ^ new_crc,
+            pdu_len(old_total_len, &old_label)?,
+        );
+
+        let offset = FIXED_HEADER_LEN + FRAG_ID_LEN;
+        buffer[offset..offset + TOTAL_LENGTH_LEN].copy_from_slice(&total_len.to_be_bytes());
+    }
+
+    // shift the rest of the packet and write the label
+    buffer.copy_within(label_end..header.pkt_len(), label_offset + label.len());
+    buffer[label_offset..label_offset + label.len()].copy_from_slice(label.get_bytes());
+    let gse_header = generate_gse_header(&header.pkt_type, &label.get_type(), gse_len as u16);
+    buffer[..FIXED_HEADER_LEN].copy_from_slice(&gse_header.to_be_bytes());
+
+    Ok((pkt_len, correction))
+}
+
+/// Apply a crc correction, returned by [`set_label`] or [`set_protocol_type`], to the end fragment at the start of the buffer
+pub fn correct_crc(buffer: &mut [u8], correction: u32) -> Result<(), HeaderError> {
+    let header = Header::read(buffer)?;
+    if header.pkt_type != PktType::EndFragPkt {
+        return Err(HeaderError::ErrorPktType);
+    }
+    let offset = header.pkt_len() - CRC_LEN;
+    let crc = u32::from_be_bytes([
+        buffer[offset],
+        buffer[offset + 1],
+        buffer[offset + 2],
+        buffer[offset + 3],
+    ]);
+    buffer[offset..offset + CRC_LEN].copy_from_slice(&(crc ^ correction).to_be_bytes());
+    Ok(())
+}
+
+/// Length of the bytes covered by the crc after the label, computed from the total length of the first fragment
+///
+/// They are the pdu, preceded by the header extensions if they are counted in the total length.
+fn pdu_len(total_len: u16, label: &Label) -> Result<usize, HeaderError> {
+    match (total_len as usize).checked_sub(PROTOCOL_LEN + label.len()) {
+        Some(pdu_len) => Ok(pdu_len),
+        None => Err(HeaderError::ErrorInvalidPacket),
+    }
+}
+
+/// Compute the change of the crc of a pdu when the state of the crc before the pdu changes by `state_diff`
+///
+/// The crc being linear, the change only depends on `state_diff` and on the length of the pdu:
+/// it is the difference between the states reached by `state_diff` and by 0 over `pdu_len` zeros.
+fn crc_correction<C: CrcCalculator>(crc_calculator: &C, state_diff: u32, pdu_len: usize) -> u32 {
+    const ZEROS: [u8; 64] = [0; 64];
+    if state_diff == 0 {
+        return 0;
+    }
+    let mut diff = state_diff;
+    let mut zero = 0;
+    let mut remaining = pdu_len;
+    while remaining > 0 {
+        let len = remaining.min(ZEROS.len());
+        diff = crc_calculator.update_crc32(diff, &ZEROS[..len]);
+        zero = crc_calculator.update_crc32(zero, &ZEROS[..len]);
+        remaining -= len;
+    }
+    diff ^ zero
+}
@@ -0,0 +1,352 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::{DefaultCrc, FastCrc};
+use crate::gse_decap::{DecapMetadata, DecapStatus, Decapsulator, GseDecapMemory, SimpleGseMemory};
+use crate::gse_encap::{EncapMetadata, EncapStatus, Encapsulator};
+use crate::gse_header::{
+    correct_crc, read_frag_id, read_gse_len, read_label, read_protocol_type, read_total_length,
+    set_frag_id, set_label, set_label_with_coverage, set_protocol_type, HeaderError,
+};
+use crate::header_extension::{
+    Extension, ExtensionCoverage, SimpleMandatoryExtensionHeaderManager,
+};
+use crate::label::Label;
+
+const MANAGER: SimpleMandatoryExtensionHeaderManager = SimpleMandatoryExtensionHeaderManager {};
+const PDU: &[u8; 52] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Encapsulate the pdu in a first fragment of `first_len` bytes and an end fragment, return their lengths
+fn encap_first_end(
+    encapsulator: &mut Encapsulator<DefaultCrc>,
+    metadata: EncapMetadata,
+    extensions: Vec<Extension>,
+    first_len: usize,
+    buffer: &mut [u8],
+) -> (usize, usize) {
+    let status = match extensions.is_empty() {
+        true => encapsulator.encap(PDU, 3, metadata, &mut buffer[..first_len]),
+        false => {
+            // the first fragment is cut from a complete packet
+            let mut packet = [0; 100];
+            encapsulator
+                .encap_ext(PDU, 0, metadata, &mut packet, extensions)
+                .unwrap();
+            encapsulator.refrag(&packet, 3, &MANAGER, &mut buffer[..first_len])
+        }
+    };
+    let Ok(EncapStatus::FragmentedPkt(len1, context)) = status else {
+        panic!("{:?}", status)
+    };
+    let len1 = len1 as usize;
+    let Ok(EncapStatus::CompletedPkt(len2)) =
+        encapsulator.encap_frag(PDU, &context, &mut buffer[len1..])
+    else {
+        panic!()
+    };
+    (len1, len2 as usize)
+}
+
+/// Decapsulate all the packets of the buffer with a new decapsulator, return the last status
+fn decap_all(buffer: &[u8]) -> DecapStatus {
+    let mut memory = SimpleGseMemory::new(1, PDU.len(), 0, 0);
+    memory
+        .provision_storage(vec![0; PDU.len()].into_boxed_slice())
+        .unwrap();
+    let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, MANAGER);
+    let mut offset = 0;
+    loop {
+        let (status, pkt_len) = decapsulator.decap(&buffer[offset..]).unwrap();
+        offset += pkt_len;
+        if offset == buffer.len() {
+            return status;
+        }
+    }
+}
+
+#[test]
+fn test_read_header_001() {
+    let comment = "read the fields of a first fragment";
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let mut buffer = [0; 100];
+    let (len1, _) = encap_first_end(
+        &mut Encapsulator::new(DefaultCrc {}),
+        metadata,
+        vec![],
+        30,
+        &mut buffer,
+    );
+
+    assert_eq!(Ok(28), read_gse_len(&buffer), "{}", comment);
+    assert_eq!(Ok(3), read_frag_id(&buffer), "{}", comment);
+    assert_eq!(Ok(57), read_total_length(&buffer), "{}", comment);
+    assert_eq!(Ok(0x1234), read_protocol_type(&buffer), "{}", comment);
+    assert_eq!(
+        Ok(Label::ThreeBytesLabel(*b"abc")),
+        read_label(&buffer),
+        "{}",
+        comment
+    );
+
+    let end = &buffer[len1..];
+    assert_eq!(Ok(3), read_frag_id(end), "{}", comment);
+    assert_eq!(
+        Err(HeaderError::ErrorPktType),
+        read_label(end),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(HeaderError::ErrorPktType),
+        read_total_length(end),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(HeaderError::ErrorInvalidPacket),
+        read_label(&[0; 10]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(HeaderError::ErrorInvalidPacket),
+        read_label(&buffer[..20]),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_set_label_001() {
+    let comment = "6 bytes label of a complete packet translated into a 3 bytes label";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let mut buffer = [0; 100];
+    Encapsulator::new(DefaultCrc {})
+        .encap(PDU, 0, metadata, &mut buffer)
+        .unwrap();
+
+    let obs = set_label(
+        &mut buffer,
+        Label::ThreeBytesLabel(*b"abc"),
+        &DefaultCrc {},
+        &MANAGER,
+    );
+
+    assert_eq!(Ok((2 + 2 + 3 + 52, 0)), obs, "{}", comment);
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::ThreeBytesLabel(*b"abc"), vec![]),
+    );
+    assert_eq!(exp_status, decap_all(&buffer[..59]), "{}", comment);
+}
+
+#[test]
+fn test_set_label_002() {
+    let comment =
+        "re-use label of a first fragment replaced by a 6 bytes label, the crc is corrected";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    // the first packet sets the label re-used by the fragmented one
+    let mut frame = [0; 100];
+    encapsulator.encap(b"x", 0, metadata, &mut frame).unwrap();
+    let mut buffer = [0; 100];
+    let (len1, len2) = encap_first_end(&mut encapsulator, metadata, vec![], 30, &mut buffer);
+    assert_eq!(Ok(Label::ReUse), read_label(&buffer), "{}", comment);
+
+    // the end fragment is moved to let the first fragment grow
+    let mut end = buffer[len1..len1 + len2].to_vec();
+    let (new_len1, correction) = set_label(
+        &mut buffer,
+        Label::SixBytesLabel(*b"012345"),
+        &DefaultCrc {},
+        &MANAGER,
+    )
+    .unwrap();
+    correct_crc(&mut end, correction).unwrap();
+
+    assert_eq!(len1 + 6, new_len1, "{}", comment);
+    assert_eq!(Ok(54 + 6), read_total_length(&buffer), "{}", comment);
+    let mut fragments = buffer[..new_len1].to_vec();
+    fragments.extend_from_slice(&end);
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::SixBytesLabel(*b"012345"), vec![]),
+    );
+    assert_eq!(exp_status, decap_all(&fragments), "{}", comment);
+}
+
+#[test]
+fn test_set_label_003() {
+    let comment =
+        "label of a first fragment with header extensions shortened, the crc is corrected";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+    let mut buffer = [0; 100];
+    let (len1, len2) = encap_first_end(
+        &mut Encapsulator::new(DefaultCrc {}),
+        metadata,
+        extensions.clone(),
+        40,
+        &mut buffer,
+    );
+
+    let (new_len1, correction) =
+        set_label(&mut buffer, Label::Broadcast, &DefaultCrc {}, &MANAGER).unwrap();
+    assert_eq!(len1 - 6, new_len1, "{}", comment);
+    buffer.copy_within(len1..len1 + len2, new_len1);
+    correct_crc(&mut buffer[new_len1..], correction).unwrap();
+
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::Broadcast, extensions),
+    );
+    assert_eq!(
+        exp_status,
+        decap_all(&buffer[..new_len1 + len2]),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_set_label_004() {
+    let comment = "errors of the modification of the label";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let mut buffer = [0; 100];
+    let (len1, len2) = encap_first_end(
+        &mut Encapsulator::new(DefaultCrc {}),
+        metadata,
+        vec![],
+        30,
+        &mut buffer,
+    );
+
+    let label = Label::SixBytesLabel(*b"012345");
+    let obs = set_label(&mut buffer[..len1 + 5], label, &DefaultCrc {}, &MANAGER);
+    assert_eq!(Err(HeaderError::ErrorSizeBuffer), obs, "{}", comment);
+    let obs = set_label(&mut buffer[len1..], label, &DefaultCrc {}, &MANAGER);
+    assert_eq!(Err(HeaderError::ErrorPktType), obs, "{}", comment);
+    let obs = set_label(
+        &mut buffer,
+        Label::SixBytesLabel([0; 6]),
+        &DefaultCrc {},
+        &MANAGER,
+    );
+    assert_eq!(Err(HeaderError::ErrorInvalidLabel), obs, "{}", comment);
+    assert_eq!(
+        Err(HeaderError::ErrorPktType),
+        correct_crc(&mut buffer, 0),
+        "{}",
+        comment
+    );
+    assert!(
+        correct_crc(&mut buffer[len1..len1 + len2], 0).is_ok(),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_set_label_005() {
+    let comment =
+        "label of a legacy first fragment with header extensions shortened, the crc is corrected";
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel(*b"012345"));
+    let extensions = vec![Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()];
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    encapsulator.set_extension_coverage(ExtensionCoverage::Legacy);
+    let mut buffer = [0; 100];
+    let (len1, len2) = encap_first_end(
+        &mut encapsulator,
+        metadata,
+        extensions.clone(),
+        40,
+        &mut buffer,
+    );
+
+    let (new_len1, correction) = set_label_with_coverage(
+        &mut buffer,
+        Label::Broadcast,
+        &DefaultCrc {},
+        &MANAGER,
+        ExtensionCoverage::Legacy,
+    )
+    .unwrap();
+    buffer.copy_within(len1..len1 + len2, new_len1);
+    correct_crc(&mut buffer[new_len1..], correction).unwrap();
+
+    let mut memory = SimpleGseMemory::new(1, PDU.len(), 0, 0);
+    memory
+        .provision_storage(vec![0; PDU.len()].into_boxed_slice())
+        .unwrap();
+    let mut decapsulator = Decapsulator::new(memory, DefaultCrc {}, MANAGER);
+    decapsulator.set_extension_coverage(ExtensionCoverage::Legacy);
+    decapsulator.decap(&buffer[..new_len1]).unwrap();
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x1234, Label::Broadcast, extensions),
+    );
+    assert_eq!(
+        Ok((exp_status, len2)),
+        decapsulator.decap(&buffer[new_len1..new_len1 + len2]),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_set_protocol_type_001() {
+    let comment = "protocol type of a first fragment modified, the crc is corrected";
+    let metadata = EncapMetadata::new(0x1234, Label::ThreeBytesLabel(*b"abc"));
+    let mut buffer = [0; 100];
+    let (len1, len2) = encap_first_end(
+        &mut Encapsulator::new(DefaultCrc {}),
+        metadata,
+        vec![],
+        30,
+        &mut buffer,
+    );
+
+    let correction = set_protocol_type(&mut buffer, 0x0800, &FastCrc::new()).unwrap();
+    correct_crc(&mut buffer[len1..], correction).unwrap();
+    set_frag_id(&mut buffer, 9).unwrap();
+    set_frag_id(&mut buffer[len1..], 9).unwrap();
+
+    assert_eq!(Ok(9), read_frag_id(&buffer[len1..]), "{}", comment);
+    let exp_status = DecapStatus::CompletedPkt(
+        Box::new(*PDU),
+        DecapMetadata::new(PDU.len(), 0x0800, Label::ThreeBytesLabel(*b"abc"), vec![]),
+    );
+    assert_eq!(exp_status, decap_all(&buffer[..len1 + len2]), "{}", comment);
+}
+
+#[test]
+fn test_set_protocol_type_002() {
+    let comment = "protocol type can not be set in place of header extensions";
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let mut buffer = [0; 100];
+    Encapsulator::new(DefaultCrc {})
+        .encap_ext(
+            PDU,
+            0,
+            metadata,
+            &mut buffer,
+            vec![Extension::new(0x0100, &[]).unwrap()],
+        )
+        .unwrap();
+
+    let obs = set_protocol_type(&mut buffer, 0x0800, &DefaultCrc {});
+    assert_eq!(Err(HeaderError::ErrorProtocolType), obs, "{}", comment);
+
+    Encapsulator::new(DefaultCrc {})
+        .encap(PDU, 0, metadata, &mut buffer)
+        .unwrap();
+    let obs = set_protocol_type(&mut buffer, 0x0100, &DefaultCrc {});
+    assert_eq!(Err(HeaderError::ErrorProtocolType), obs, "{}", comment);
+    assert_eq!(
+        Ok(0),
+        set_protocol_type(&mut buffer, 0x0800, &DefaultCrc {}),
+        "{}",
+        comment
+    );
+    assert_eq!(Ok(0x0800), read_protocol_type(&buffer), "{}", comment);
+}
@@ -51,5 +51,15 @@ pub const MAX_MANDATORY_VAL_PTYPE : u16 = 0x100;
 // Section 5.1.0
 pub const NCR_PROTOCOL_ID: u16 = 0x0081;
 pub const INTERNAL_SIGNALING_PROTOCOL_ID: u16 = 0x0082;
+// https://www.rfc-editor.org/rfc/rfc4326 Section 5.2, also used by GSE (RFC 5163)
+pub const BRIDGED_FRAME_PROTOCOL_ID: u16 = 0x0001;
+pub const ETHERNET_HEADER_LEN: usize = 14;
+// https://www.rfc-editor.org/rfc/rfc5163 Section 3.2
+pub const PDU_CONCAT_PROTOCOL_ID: u16 = 0x0003;
+pub const PDU_CONCAT_LEN_LEN: usize = 2;
+// https://www.rfc-editor.org/rfc/rfc5163 Section 3.1, TS packets of ISO/IEC 13818-1
+pub const TS_CONCAT_PROTOCOL_ID: u16 = 0x0002;
+pub const TS_PACKET_LEN: usize = 188;
+pub const TS_SYNC_BYTE: u8 = 0x47;
 // Gse Mask for Header Extension 
 pub const H_LEN_MASK: u16 = 0b111 << 8;
@@ -0,0 +1,436 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the zero-copy view of GSE packets
+//!
+//! [`GsePacketView`] parses any GSE packet (complete, first fragment, intermediate fragment, end fragment or padding)
+//! directly in the buffer it was received in. The fields are exposed through getters and the payload is a slice of the buffer,
+//! so a packet can be inspected for monitoring or routing without copying or allocating anything.
+//!
+//! The header extensions are decoded lazily by [`GsePacketView::extensions`].
+//! As for the `Decapsulator`, the size of the mandatory header extensions is given by a [`MandatoryHeaderExtensionManager`].
+//!
+//! # Example
+//!
+//! ```
+//! use dvb_gse_rust::gse_view::GsePacketView;
+//! use dvb_gse_rust::header_extension::SimpleMandatoryExtensionHeaderManager;
+//! use dvb_gse_rust::label::Label;
+//!
+//! // complete packet, 3 bytes label, protocol type 0xFFFF
+//! let buffer = [0xD0, 0x08, 0xFF, 0xFF, 0x01, 0x02, 0x03, b'a', b'b', b'c', 0x00, 0x00];
+//! let manager = SimpleMandatoryExtensionHeaderManager {};
+//! let view = GsePacketView::parse(&buffer, &manager).unwrap();
+//!
+//! assert_eq!(view.len(), 10);
+//! assert_eq!(view.protocol_type(), Some(0xFFFF));
+//! assert_eq!(view.label(), Some(Label::ThreeBytesLabel([1, 2, 3])));
+//! assert_eq!(view.payload(), b"abc");
+//! assert_eq!(view.extensions().count(), 0);
+//!
+//! // the rest of the buffer is padding
+//! let padding = GsePacketView::parse(&buffer[view.len()..], &manager).unwrap();
+//! assert!(padding.is_padding());
+//! assert_eq!(padding.len(), 2);
+//! ```
+
+use crate::gse_decap::read_gse_header;
+use crate::gse_standard::{
+    CRC_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, H_LEN_MASK, PROTOCOL_LEN, SECOND_RANGE_PTYPE,
+    TOTAL_LENGTH_LEN,
+};
+use crate::header_extension::{
+    optionnal_extension_data_size_from_hlen, Extension, MandatoryHeaderExt,
+    MandatoryHeaderExtensionManager, SimpleMandatoryExtensionHeaderManager,
+};
+use crate::label::{Label, LabelType};
+use crate::pkt_type::PktType;
+
+#[cfg(test)]
+mod tests;
+
+// start bit, end bit and label type all to 0
+const PADDING_MASK: u8 = 0xF0;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Error returned by [`GsePacketView::parse`] when the buffer does not start with a valid packet
+pub enum ViewError {
+    /// Indicates that the buffer ends before the end of the packet.
+    ErrorTruncated,
+
+    /// Indicates that the gse length is too small to contain the fields of the packet.
+    ErrorGseLength,
+
+    /// Indicates that the packet contains a mandatory header extension unknown by the manager.
+    ErrorUnknownMandatoryHeader,
+
+    /// Indicates that the header extensions can not be decoded.
+    ErrorInvalidHeaderExtension,
+}
+
+impl ViewError {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::ErrorTruncated => "Buffer ends before the end of the packet",
+            Self::ErrorGseLength => "Gse length too small to contain the fields of the packet",
+            Self::ErrorUnknownMandatoryHeader => {
+                "Header contains unknown Mandatory Header Extension"
+            }
+            Self::ErrorInvalidHeaderExtension => "Header extension can not be decoded",
+        }
+    }
+}
+
+/// Structure GsePacketView
+///
+/// Read-only view of one GSE packet borrowed from the buffer it is read from.
+///
+/// The getters return `None` for the fields that are not carried by the packet type,
+/// e.g. the label of an intermediate fragment or the crc of a complete packet.
+pub struct GsePacketView<
+    'a,
+    M: MandatoryHeaderExtensionManager = SimpleMandatoryExtensionHeaderManager,
+> {
+    packet: &'a [u8],
+    manager: &'a M,
+    pkt_type: Option<PktType>,
+    label_type: LabelType,
+    gse_len: usize,
+    frag_id: Option<u8>,
+    total_length: Option<u16>,
+    label: Option<Label>,
+    first_ext_id: Option<u16>,
+    ext_offset: usize,
+    protocol_type: Option<u16>,
+    payload_offset: usize,
+    payload_len: usize,
+    crc: Option<u32>,
+}
+
+impl<'a, M: MandatoryHeaderExtensionManager> GsePacketView<'a, M> {
+    /// Parse the packet at the start of `buffer`
+    ///
+    /// The buffer may contain other packets after the first one, the length of the packet parsed is given by [`Self::len`].
+    /// If the buffer starts with padding, the whole buffer is considered as padding.
+    ///
+    /// The header extensions are checked with `manager`: a packet with an unknown mandatory header extension is rejected.
+    pub fn parse(buffer: &'a [u8], manager: &'a M) -> Result<Self, ViewError> {
+        let Some(&first_byte) = buffer.first() else {
+            return Err(ViewError::ErrorTruncated);
+        };
+
+        // the first 4 bits to 0 means padding until the end of the buffer
+        if first_byte & PADDING_MASK == 0 {
+            return Ok(Self::new(
+                buffer,
+                manager,
+                None,
+                LabelType::SixBytesLabel,
+                0,
+            ));
+        }
+
+        let Some(header) = buffer.get(..FIXED_HEADER_LEN) else {
+            return Err(ViewError::ErrorTruncated);
+        };
+        let Some((gse_len, pkt_type, label_type)) =
+            read_gse_header(u16::from_be_bytes([header[0], header[1]]))
+        else {
+            unreachable!() // padding is already handled
+        };
+
+        let Some(packet) = buffer.get(..FIXED_HEADER_LEN + gse_len) else {
+            return Err(ViewError::ErrorTruncated);
+        };
+        let mut view = Self::new(packet, manager, Some(pkt_type), label_type, gse_len);
+
+        match pkt_type {
+            PktType::CompletePkt => view.parse_start(false)?,
+            PktType::FirstFragPkt => view.parse_start(true)?,
+            PktType::IntermediateFragPkt => view.parse_frag(0)?,
+            PktType::EndFragPkt => view.parse_frag(CRC_LEN)?,
+        }
+        Ok(view)
+    }
+
+    fn new(
+        packet: &'a [u8],
+        manager: &'a M,
+        pkt_type: Option<PktType>,
+        label_type: LabelType,
+        gse_len: usize,
+    ) -> Self {
+        Self {
+            packet,
+            manager,
+            pkt_type,
+            label_type,
+            gse_len,
+            frag_id: None,
+            total_length: None,
+            label: None,
+            first_ext_id: None,
+            ext_offset: 0,
+            protocol_type: None,
+            payload_offset: 0,
+            payload_len: 0,
+            crc: None,
+        }
+    }
+
+    /// Parse the header of a complete or a first fragment packet
+    fn parse_start(&mut self, is_first_frag: bool) -> Result<(), ViewError> {
+        let label_len = self.label_type.len();
+        let frag_len = if is_first_frag {
+            FRAG_ID_LEN + TOTAL_LENGTH_LEN
+        } else {
+            0
+        };
+        if self.gse_len < frag_len + PROTOCOL_LEN + label_len {
+            return Err(ViewError::ErrorGseLength);
+        }
+
+        let mut offset = FIXED_HEADER_LEN;
+        if is_first_frag {
+            self.frag_id = Some(self.packet[offset]);
+            offset += FRAG_ID_LEN;
+            self.total_length = Some(u16::from_be_bytes([
+                self.packet[offset],
+                self.packet[offset + 1],
+            ]));
+            offset += TOTAL_LENGTH_LEN;
+        }
+
+        let protocol_type = u16::from_be_bytes([self.packet[offset], self.packet[offset + 1]]);
+        offset += PROTOCOL_LEN;
+
+        self.label = Some(Label::new(
+            &self.label_type,
+            &self.packet[offset..offset + label_len],
+        ));
+        offset += label_len;
+
+        // walk through the header extensions to find the protocol type and the start of the payload
+        self.ext_offset = offset;
+        let mut extensions =
+            RawExtensionIter::new(&self.packet[offset..], self.manager, protocol_type);
+        for extension in extensions.by_ref() {
+            extension?;
+        }
+        if protocol_type < SECOND_RANGE_PTYPE {
+            self.first_ext_id = Some(protocol_type);
+        }
+        self.protocol_type = Some(extensions.protocol_type);
+        self.payload_offset = offset + extensions.offset;
+        self.payload_len = self.packet.len() - self.payload_offset;
+        Ok(())
+    }
+
+    /// Parse the header of an intermediate or an end fragment packet
+    fn parse_frag(&mut self, crc_len: usize) -> Result<(), ViewError> {
+        if self.gse_len < FRAG_ID_LEN + crc_len {
+            return Err(ViewError::ErrorGseLength);
+        }
+        self.frag_id = Some(self.packet[FIXED_HEADER_LEN]);
+        self.payload_offset = FIXED_HEADER_LEN + FRAG_ID_LEN;
+        self.payload_len = self.gse_len - FRAG_ID_LEN - crc_len;
+        if crc_len != 0 {
+            let offset = self.payload_offset + self.payload_len;
+            self.crc = Some(u32::from_be_bytes([
+                self.packet[offset],
+                self.packet[offset + 1],
+                self.packet[offset + 2],
+                self.packet[offset + 3],
+            ]));
+        }
+        Ok(())
+    }
+
+    /// Return true if the view is padding
+    pub fn is_padding(&self) -> bool {
+        self.pkt_type.is_none()
+    }
+
+    /// Return the start indicator of the packet
+    pub fn start_indicator(&self) -> bool {
+        matches!(
+            self.pkt_type,
+            Some(PktType::CompletePkt | PktType::FirstFragPkt)
+        )
+    }
+
+    /// Return the end indicator of the packet
+    pub fn end_indicator(&self) -> bool {
+        matches!(
+            self.pkt_type,
+            Some(PktType::CompletePkt | PktType::EndFragPkt)
+        )
+    }
+
+    /// Return the label type field of the packet
+    pub fn label_type(&self) -> LabelType {
+        self.label_type
+    }
+
+    /// Return the gse length field of the packet, 0 for padding
+    pub fn gse_len(&self) -> usize {
+        self.gse_len
+    }
+
+    /// Return the length of the packet in the buffer, header included
+    ///
+    /// For padding, it is the length of the whole buffer.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.packet.len()
+    }
+
+    /// Return the fragment id of a fragmented packet
+    pub fn frag_id(&self) -> Option<u8> {
+        self.frag_id
+    }
+
+    /// Return the total length of the pdu carried by a first fragment packet
+    pub fn total_length(&self) -> Option<u16> {
+        self.total_length
+    }
+
+    /// Return the protocol type of a complete or a first fragment packet
+    ///
+    /// When the header extensions end with a final mandatory header extension, it is the id of this extension.
+    pub fn protocol_type(&self) -> Option<u16> {
+        self.protocol_type
+    }
+
+    /// Return the label of a complete or a first fragment packet
+    pub fn label(&self) -> Option<Label> {
+        self.label
+    }
+
+    /// Return an iterator over the header extensions of a complete or a first fragment packet
+    ///
+    /// The extensions are decoded on the fly, in the order of the packet.
+    pub fn extensions(&self) -> ExtensionIter<'a, M> {
+        let chain = match self.first_ext_id {
+            Some(_) => &self.packet[self.ext_offset..],
+            None => &[],
+        };
+        ExtensionIter {
+            raw: RawExtensionIter::new(
+                chain,
+                self.manager,
+                self.first_ext_id.unwrap_or(SECOND_RANGE_PTYPE),
+            ),
+        }
+    }
+
+    /// Return the payload carried by the packet
+    ///
+    /// It is the pdu (or the part of the pdu) without the header and the crc, empty for padding.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.packet[self.payload_offset..self.payload_offset + self.payload_len]
+    }
+
+    /// Return the crc of an end fragment packet
+    pub fn crc(&self) -> Option<u32> {
+        self.crc
+    }
+
+    /// Return the bytes of the whole packet
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.packet
+    }
+}
+
+/// Structure RawExtensionIter
+///
+/// Walk through a chain of header extensions, the first id being given apart as it replaces the protocol type.
+/// Once the walk is over, `protocol_type` is the protocol type following the chain and `offset` the length of the chain.
+struct RawExtensionIter<'a, M: MandatoryHeaderExtensionManager> {
+    chain: &'a [u8],
+    manager: &'a M,
+    protocol_type: u16,
+    offset: usize,
+    done: bool,
+}
+
+impl<'a, M: MandatoryHeaderExtensionManager> RawExtensionIter<'a, M> {
+    fn new(chain: &'a [u8], manager: &'a M, first_id: u16) -> Self {
+        Self {
+            chain,
+            manager,
+            protocol_type: first_id,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+impl<'a, M: MandatoryHeaderExtensionManager> Iterator for RawExtensionIter<'a, M> {
+    type Item = Result<(u16, &'a [u8]), ViewError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.protocol_type >= SECOND_RANGE_PTYPE {
+            return None;
+        }
+        let id = self.protocol_type;
+        let h_len = ((id & H_LEN_MASK) >> 8) as u8;
+        let (data_len, is_final) = if h_len == 0 {
+            match self
+                .manager
+                .decode_mandatory_header(id, &self.chain[self.offset..])
+            {
+                MandatoryHeaderExt::Unknown => {
+                    self.done = true;
+                    return Some(Err(ViewError::ErrorUnknownMandatoryHeader));
+                }
+                MandatoryHeaderExt::Final(size) => (size as usize, true),
+                MandatoryHeaderExt::NonFinal(size) => (size as usize, false),
+            }
+        } else {
+            match optionnal_extension_data_size_from_hlen(h_len) {
+                Ok(size) => (size, false),
+                Err(_) => {
+                    self.done = true;
+                    return Some(Err(ViewError::ErrorInvalidHeaderExtension));
+                }
+            }
+        };
+
+        let Some(data) = self.chain.get(self.offset..self.offset + data_len) else {
+            self.done = true;
+            return Some(Err(ViewError::ErrorGseLength));
+        };
+        self.offset += data_len;
+
+        if is_final {
+            // the final mandatory header extension replaces the protocol type
+            self.done = true;
+        } else {
+            let Some(next) = self.chain.get(self.offset..self.offset + PROTOCOL_LEN) else {
+                self.done = true;
+                return Some(Err(ViewError::ErrorGseLength));
+            };
+            self.protocol_type = u16::from_be_bytes([next[0], next[1]]);
+            self.offset += PROTOCOL_LEN;
+        }
+        Some(Ok((id, data)))
+    }
+}
+
+/// Structure ExtensionIter
+///
+/// Iterator over the header extensions of a [`GsePacketView`], returned by [`GsePacketView::extensions`].
+pub struct ExtensionIter<'a, M: MandatoryHeaderExtensionManager> {
+    raw: RawExtensionIter<'a, M>,
+}
+
+impl<'a, M: MandatoryHeaderExtensionManager> Iterator for ExtensionIter<'a, M> {
+    type Item = Extension;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the chain has been checked by the parsing of the view
+        let (id, data) = self.raw.next()?.ok()?;
+        Extension::new(id, data).ok()
+    }
+}
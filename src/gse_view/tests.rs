@@ -0,0 +1,249 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::DefaultCrc;
+use crate::gse_encap::{EncapMetadata, EncapStatus, Encapsulator};
+use crate::gse_standard::NCR_PROTOCOL_ID;
+use crate::gse_view::{GsePacketView, ViewError};
+use crate::header_extension::{
+    Extension, SignalisationMandatoryExtensionHeaderManager, SimpleMandatoryExtensionHeaderManager,
+};
+use crate::label::{Label, LabelType};
+
+const MANAGER: SimpleMandatoryExtensionHeaderManager = SimpleMandatoryExtensionHeaderManager {};
+
+#[test]
+fn test_view_complete_001() {
+    let comment = "complete packet with a 6 bytes label";
+    #[rustfmt::skip]
+    let buffer = [
+        0xC0, 0x0D,
+        0x12, 0x34,
+        b'0', b'1', b'2', b'3', b'4', b'5',
+        b'a', b'b', b'c', b'd', b'e',
+        0xFF, 0xFF,
+    ];
+
+    let view = GsePacketView::parse(&buffer, &MANAGER).unwrap();
+
+    assert!(!view.is_padding(), "{}", comment);
+    assert!(view.start_indicator(), "{}", comment);
+    assert!(view.end_indicator(), "{}", comment);
+    assert_eq!(LabelType::SixBytesLabel, view.label_type(), "{}", comment);
+    assert_eq!(13, view.gse_len(), "{}", comment);
+    assert_eq!(15, view.len(), "{}", comment);
+    assert_eq!(None, view.frag_id(), "{}", comment);
+    assert_eq!(None, view.total_length(), "{}", comment);
+    assert_eq!(Some(0x1234), view.protocol_type(), "{}", comment);
+    assert_eq!(
+        Some(Label::SixBytesLabel(*b"012345")),
+        view.label(),
+        "{}",
+        comment
+    );
+    assert_eq!(0, view.extensions().count(), "{}", comment);
+    assert_eq!(b"abcde", view.payload(), "{}", comment);
+    assert_eq!(None, view.crc(), "{}", comment);
+    assert_eq!(&buffer[..15], view.as_bytes(), "{}", comment);
+}
+
+#[test]
+fn test_view_complete_002() {
+    let comment = "complete packet with an optional and a final mandatory header extensions";
+    #[rustfmt::skip]
+    let buffer = [
+        0xE0, 0x0A,
+        0x02, 0x00,
+        0xAA, 0xBB,
+        0x00, 0x81,
+        b'a', b'b', b'c', b'd',
+    ];
+    let manager = SignalisationMandatoryExtensionHeaderManager {};
+
+    let view = GsePacketView::parse(&buffer, &manager).unwrap();
+
+    let exp_extensions = vec![
+        Extension::new(0x0200, &[0xAA, 0xBB]).unwrap(),
+        Extension::new(NCR_PROTOCOL_ID, &[]).unwrap(),
+    ];
+    assert_eq!(Some(NCR_PROTOCOL_ID), view.protocol_type(), "{}", comment);
+    assert_eq!(Some(Label::Broadcast), view.label(), "{}", comment);
+    assert_eq!(
+        exp_extensions,
+        view.extensions().collect::<Vec<_>>(),
+        "{}",
+        comment
+    );
+    assert_eq!(b"abcd", view.payload(), "{}", comment);
+}
+
+#[test]
+fn test_view_complete_003() {
+    let comment = "complete packet with an unknown mandatory header extension";
+    let buffer = [0xE0, 0x04, 0x00, 0x81, b'a', b'b'];
+
+    let obs = GsePacketView::parse(&buffer, &MANAGER).err();
+
+    assert_eq!(
+        Some(ViewError::ErrorUnknownMandatoryHeader),
+        obs,
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_view_complete_004() {
+    let comment = "complete packet with an extension longer than the packet";
+    let buffer = [0xE0, 0x04, 0x05, 0x00, b'a', b'b'];
+
+    let obs = GsePacketView::parse(&buffer, &MANAGER).err();
+
+    assert_eq!(Some(ViewError::ErrorGseLength), obs, "{}", comment);
+}
+
+#[test]
+fn test_view_complete_005() {
+    let comment = "complete packet with a gse length too small to contain the label";
+    let buffer = [0xC0, 0x04, 0x12, 0x34, b'0', b'1', b'2', b'3', b'4', b'5'];
+
+    let obs = GsePacketView::parse(&buffer, &MANAGER).err();
+
+    assert_eq!(Some(ViewError::ErrorGseLength), obs, "{}", comment);
+}
+
+#[test]
+fn test_view_complete_006() {
+    let comment = "complete packet truncated by the end of the buffer";
+    let buffer = [0xD0, 0x08, 0xFF, 0xFF, 0x01, 0x02, 0x03, b'a'];
+
+    let obs = GsePacketView::parse(&buffer, &MANAGER).err();
+
+    assert_eq!(Some(ViewError::ErrorTruncated), obs, "{}", comment);
+}
+
+#[test]
+fn test_view_frag_001() {
+    let comment = "first, intermediate and end fragments written by the encapsulator";
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let metadata = EncapMetadata {
+        protocol_type: 0xFFFF,
+        label: Label::ThreeBytesLabel(*b"lbl"),
+    };
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut buffer = [0; 100];
+
+    let Ok(EncapStatus::FragmentedPkt(len1, context)) =
+        encapsulator.encap(pdu, 7, metadata, &mut buffer[..20])
+    else {
+        panic!("{}", comment)
+    };
+    let len1 = len1 as usize;
+    let Ok(EncapStatus::FragmentedPkt(len2, context)) =
+        encapsulator.encap_frag(pdu, &context, &mut buffer[len1..len1 + 10])
+    else {
+        panic!("{}", comment)
+    };
+    let len2 = len2 as usize;
+    let Ok(EncapStatus::CompletedPkt(len3)) =
+        encapsulator.encap_frag(pdu, &context, &mut buffer[len1 + len2..])
+    else {
+        panic!("{}", comment)
+    };
+    let len3 = len3 as usize;
+
+    let first = GsePacketView::parse(&buffer, &MANAGER).unwrap();
+    assert!(
+        first.start_indicator() && !first.end_indicator(),
+        "{}",
+        comment
+    );
+    assert_eq!(len1, first.len(), "{}", comment);
+    assert_eq!(Some(7), first.frag_id(), "{}", comment);
+    // the total length covers the protocol type, the label and the pdu
+    assert_eq!(
+        Some(pdu.len() as u16 + 5),
+        first.total_length(),
+        "{}",
+        comment
+    );
+    assert_eq!(Some(0xFFFF), first.protocol_type(), "{}", comment);
+    assert_eq!(
+        Some(Label::ThreeBytesLabel(*b"lbl")),
+        first.label(),
+        "{}",
+        comment
+    );
+    assert_eq!(&pdu[..len1 - 10], first.payload(), "{}", comment);
+
+    let intermediate = GsePacketView::parse(&buffer[len1..], &MANAGER).unwrap();
+    assert!(!intermediate.start_indicator(), "{}", comment);
+    assert!(!intermediate.end_indicator(), "{}", comment);
+    assert_eq!(len2, intermediate.len(), "{}", comment);
+    assert_eq!(Some(7), intermediate.frag_id(), "{}", comment);
+    assert_eq!(None, intermediate.label(), "{}", comment);
+    assert_eq!(
+        &pdu[len1 - 10..len1 - 10 + len2 - 3],
+        intermediate.payload(),
+        "{}",
+        comment
+    );
+
+    let end = GsePacketView::parse(&buffer[len1 + len2..], &MANAGER).unwrap();
+    assert!(!end.start_indicator() && end.end_indicator(), "{}", comment);
+    assert_eq!(len3, end.len(), "{}", comment);
+    assert_eq!(Some(7), end.frag_id(), "{}", comment);
+    assert_eq!(&pdu[len1 - 10 + len2 - 3..], end.payload(), "{}", comment);
+    let crc_offset = len1 + len2 + len3 - 4;
+    let exp_crc = u32::from_be_bytes(buffer[crc_offset..crc_offset + 4].try_into().unwrap());
+    assert_eq!(Some(exp_crc), end.crc(), "{}", comment);
+}
+
+#[test]
+fn test_view_frag_002() {
+    let comment = "end fragment with a gse length too small to contain the crc";
+    let buffer = [0x70, 0x03, 0x01, 0x00, 0x00, 0x00];
+
+    let obs = GsePacketView::parse(&buffer, &MANAGER).err();
+
+    assert_eq!(Some(ViewError::ErrorGseLength), obs, "{}", comment);
+}
+
+#[test]
+fn test_view_padding_001() {
+    let comment = "padding until the end of the buffer";
+    let buffer = [0x00, 0x00, 0x00];
+
+    let view = GsePacketView::parse(&buffer, &MANAGER).unwrap();
+
+    assert!(view.is_padding(), "{}", comment);
+    assert!(
+        !view.start_indicator() && !view.end_indicator(),
+        "{}",
+        comment
+    );
+    assert_eq!(3, view.len(), "{}", comment);
+    assert_eq!(b"", view.payload(), "{}", comment);
+    assert_eq!(None, view.protocol_type(), "{}", comment);
+    assert_eq!(None, view.frag_id(), "{}", comment);
+
+    let obs = GsePacketView::parse(&[], &MANAGER).err();
+    assert_eq!(Some(ViewError::ErrorTruncated), obs, "{}", comment);
+}
+
+#[test]
+fn test_view_no_panic_001() {
+    let comment = "any header followed by any length of data";
+    let manager = SignalisationMandatoryExtensionHeaderManager {};
+    let mut buffer = [0x05; 16];
+    for header in 0..=u16::MAX {
+        buffer[..2].copy_from_slice(&header.to_be_bytes());
+        for len in 0..buffer.len() {
+            if let Ok(view) = GsePacketView::parse(&buffer[..len], &manager) {
+                assert!(view.len() <= len, "{}", comment);
+                assert!(view.payload().len() <= view.len(), "{}", comment);
+                view.extensions().count();
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the Bridged Frame extension
+//!
+//! The final mandatory extension Bridged Frame (0x0001) replaces the protocol type of a pdu which is an Ethernet frame,
+//! so that the link can bridge Ethernet. The extension has no data, the frame starts with its Ethernet header:
+//! ```text
+//!    +-----------------+-----------------+------------+-------------------------------------+
+//!    | MAC destination |   MAC source    | EtherType  |               Payload               |
+//!    +-----------------+-----------------+------------+-------------------------------------+
+//!    <---- 6 bytes ----><---- 6 bytes ----><- 2 bytes ->
+//! ```
+//! The frame check sequence of the Ethernet frame is not sent.
+//!
+//! The `Encapsulator` sends a frame with [`encap_bridged_frame`](crate::gse_encap::Encapsulator::encap_bridged_frame).
+//! The `Decapsulator` reads the [`EthernetHeader`] of the frame received, it is given by the metadata whose protocol type is the EtherType,
+//! and only the payload of the frame is delivered. A manager must know the extension for the frame to be received:
+//! [`BridgedFrameMandatoryExtensionHeaderManager`] knows it, and [`BridgedFrameHandler`] handles it in an [`ExtensionRegistry`](super::ExtensionRegistry).
+//!
+//! ## Specification
+//! See `[RFC 4326]` : "Unidirectional Lightweight Encapsulation (ULE) for Transmission of IP Datagrams over an MPEG-2 Transport Stream (TS)" - § 5.2 \
+//! and `[RFC 5163]` : "Extension Formats for Unidirectional Lightweight Encapsulation (ULE) and the Generic Stream Encapsulation (GSE)"
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+    Extension, ExtensionData, ExtensionHandler, ExtensionHandlerError, MandatoryHeaderExt,
+    MandatoryHeaderExtensionManager,
+};
+use crate::gse_standard::{BRIDGED_FRAME_PROTOCOL_ID, ETHERNET_HEADER_LEN};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Structure EthernetHeader
+///
+/// Header of the Ethernet frame carried by a pdu with the Bridged Frame extension.
+pub struct EthernetHeader {
+    pub destination: [u8; 6],
+    pub source: [u8; 6],
+    pub ether_type: u16,
+}
+
+impl EthernetHeader {
+    /// Read the header at the start of `frame`, return `None` if the frame is too short
+    pub fn decode(frame: &[u8]) -> Option<Self> {
+        let header = frame.get(..ETHERNET_HEADER_LEN)?;
+        Some(Self {
+            destination: header[0..6].try_into().unwrap(),
+            source: header[6..12].try_into().unwrap(),
+            ether_type: u16::from_be_bytes([header[12], header[13]]),
+        })
+    }
+
+    /// Return the header as written at the start of a frame
+    pub fn encode(&self) -> [u8; ETHERNET_HEADER_LEN] {
+        let mut header = [0; ETHERNET_HEADER_LEN];
+        header[0..6].copy_from_slice(&self.destination);
+        header[6..12].copy_from_slice(&self.source);
+        header[12..].copy_from_slice(&self.ether_type.to_be_bytes());
+        header
+    }
+}
+
+/// Return the extension sent with a bridged frame
+pub fn bridged_frame_extension() -> Extension {
+    Extension {
+        id: BRIDGED_FRAME_PROTOCOL_ID,
+        data: ExtensionData::MandatoryData(vec![]),
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Handler of the Bridged Frame extension in an [`ExtensionRegistry`](super::ExtensionRegistry)
+///
+/// The extension is final without data, the Ethernet header of the pdu is read by the decapsulator itself.
+pub struct BridgedFrameHandler;
+
+impl ExtensionHandler for BridgedFrameHandler {
+    fn decode_len(&self, _data: &[u8]) -> MandatoryHeaderExt {
+        MandatoryHeaderExt::Final(0)
+    }
+
+    fn validate(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::MandatoryData(data) if data.is_empty() => Ok(()),
+            _ => Err(ExtensionHandlerError::InvalidData),
+        }
+    }
+
+    fn encode(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        self.validate(extension)?;
+        Ok(extension.clone())
+    }
+}
+
+/// Implementation of the trait [`MandatoryHeaderExtensionManager`] for Ethernet bridging.
+///
+/// It knows the final extension 0x0001 Bridged Frame, with no data.
+#[derive(Copy, Clone)]
+pub struct BridgedFrameMandatoryExtensionHeaderManager {}
+impl MandatoryHeaderExtensionManager for BridgedFrameMandatoryExtensionHeaderManager {
+    fn is_mandatory_header_id_known(&self, id: u16) -> MandatoryHeaderExt {
+        match id {
+            BRIDGED_FRAME_PROTOCOL_ID => MandatoryHeaderExt::Final(0),
+            _ => MandatoryHeaderExt::Unknown,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use super::{
+    bridged_frame_extension, BridgedFrameHandler, BridgedFrameMandatoryExtensionHeaderManager,
+    EthernetHeader,
+};
+use crate::header_extension::{
+    Extension, ExtensionHandler, ExtensionHandlerError, MandatoryHeaderExt,
+    MandatoryHeaderExtensionManager,
+};
+
+const FRAME: [u8; 16] = [
+    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x08, 0x00, 0x61, 0x62,
+];
+
+#[test]
+fn test_ethernet_header_001() {
+    let comment = "ethernet header read at the start of the frame and written back";
+    let exp_header = EthernetHeader {
+        destination: [1, 2, 3, 4, 5, 6],
+        source: [0x11, 0x12, 0x13, 0x14, 0x15, 0x16],
+        ether_type: 0x0800,
+    };
+
+    let header = EthernetHeader::decode(&FRAME);
+
+    assert_eq!(Some(exp_header), header, "{}", comment);
+    assert_eq!(FRAME[..14], exp_header.encode(), "{}", comment);
+}
+
+#[test]
+fn test_ethernet_header_002() {
+    let comment = "frame too short to hold an ethernet header";
+
+    assert_eq!(None, EthernetHeader::decode(&FRAME[..13]), "{}", comment);
+}
+
+#[test]
+fn test_bridged_frame_handler_001() {
+    let comment = "final extension without data";
+    let handler = BridgedFrameHandler;
+    let extension = bridged_frame_extension();
+    let with_data = Extension::new(0x0001, &[1]).unwrap();
+
+    assert_eq!(
+        MandatoryHeaderExt::Final(0),
+        handler.decode_len(&FRAME),
+        "{}",
+        comment
+    );
+    assert_eq!(Ok(()), handler.validate(&extension), "{}", comment);
+    assert_eq!(
+        Ok(extension.clone()),
+        handler.encode(&extension),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidData),
+        handler.encode(&with_data),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_bridged_frame_manager_001() {
+    let comment = "manager knowing the bridged frame extension only";
+    let manager = BridgedFrameMandatoryExtensionHeaderManager {};
+
+    assert_eq!(
+        MandatoryHeaderExt::Final(0),
+        manager.is_mandatory_header_id_known(0x0001),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        MandatoryHeaderExt::Unknown,
+        manager.is_mandatory_header_id_known(0x0081),
+        "{}",
+        comment
+    );
+}
@@ -16,6 +16,17 @@
 //! The trait [`MandatoryHeaderExtensionManager`] (given at the creation of the `decapsulator`) allows user to define which mandatory extension are known and how to treat them. \
 //! Its default implementation [`SimpleMandatoryExtensionHeaderManager`] doesn't known any mandatory extension.
 //! 
+//! The [`ExtensionRegistry`] implements this trait with one handler per extension id, mandatory or optional,
+//! which decodes and checks the extension, may transform the pdu received with it, and encodes it again on encap, see the [`registry`] module.
+//! 
+//! The final mandatory extension Bridged Frame carries an Ethernet frame, see the [`bridged_frame`] module.
+//! The mandatory extension PDU-Concat packs several pdus of the same protocol type in one packet, see the [`pdu_concat`] module.
+//! The final mandatory extension TS-Concat carries a sequence of MPEG-2 TS packets, see the [`ts_concat`] module.
+//! 
+//! # Fragmented PDU
+//! The header extensions are sent in the first fragment. They are counted in its total length and covered by the crc of the pdu. \
+//! [`ExtensionCoverage`] allows to leave them out of both, as the previous versions of this crate did.
+//! 
 //! 
 //! 
 //! # Examples of packet
@@ -52,7 +63,19 @@
 //! * `[ETSI EN 301 542-2]` : "Digital Video Broadcasting (DVB) ; Second Generation DVB Interactive Satellite System" - § Section 5.1
 #[cfg(test)]
 mod tests;
+pub mod bridged_frame;
+pub mod pdu_concat;
+pub mod registry;
+pub mod ts_concat;
+pub use self::bridged_frame::{
+    BridgedFrameHandler, BridgedFrameMandatoryExtensionHeaderManager, EthernetHeader,
+};
+pub use self::pdu_concat::{PduConcat, PduConcatHandler};
+pub use self::registry::{ExtensionHandler, ExtensionHandlerError, ExtensionRegistry};
+pub use self::ts_concat::{TsConcatHandler, TsConcatMandatoryExtensionHeaderManager};
+use crate::crc::CrcCalculator;
 use crate::gse_standard::{INTERNAL_SIGNALING_PROTOCOL_ID, MAX_MANDATORY_VAL_PTYPE, NCR_PROTOCOL_ID, PROTOCOL_LEN, SECOND_RANGE_PTYPE};
+use std::ops::Range;
 
 
 pub type ExtID = u16;
@@ -145,7 +168,7 @@ impl Extension {
     /// # Warning 
     /// Extension should always be created using new 
     pub fn new(id : u16,  data : &[u8]) -> Result<Self,NewExtensionError>{
-        if id > SECOND_RANGE_PTYPE {
+        if id >= SECOND_RANGE_PTYPE {
             return Err(NewExtensionError::IncorrectExtensionId)
         }
         if id < MAX_MANDATORY_VAL_PTYPE {
@@ -153,7 +176,7 @@ impl Extension {
         }
 
         let data_size_from_id = match optionnal_extension_data_size_from_hlen((id >> 8).try_into().unwrap()){
-            Err(_) => unreachable!(), // HLEN > 5 <=> id > SECOND_RANGE_PTYPE, HLEN = 0 <=> id < MAX_MANDATORY_VAL_PTYPE
+            Err(_) => unreachable!(), // HLEN > 5 <=> id >= SECOND_RANGE_PTYPE, HLEN = 0 <=> id < MAX_MANDATORY_VAL_PTYPE
             Ok(size) => size,
         };
 
@@ -192,7 +215,7 @@ impl Extension {
 ///
 /// A known, non-final mandatory header extension is treated similarly to an optional header extension.
 /// However, its size cannot be extracted from the packet directly but is known to the receiver through the trait.
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum MandatoryHeaderExt {
     Final(u8),
     NonFinal(u8),
@@ -200,10 +223,57 @@ pub enum MandatoryHeaderExt {
 }
 
 /// Trait defining which mandatory extension are known by the receiver (and their data length).
+///
+/// The other functions have a default implementation accepting every extension as it is,
+/// they are overriden by the [`ExtensionRegistry`] to decode, check and encode the extensions with their handlers.
 pub trait MandatoryHeaderExtensionManager {
     /// For each known mandatory header extension, it should return the size of the data following this header extension.
     /// should return `MandatoryHeaderExt::Unknown` if the extension is unknown
     fn is_mandatory_header_id_known(&self, id: u16) -> MandatoryHeaderExt;
+
+    /// Return the size of the data of the mandatory header extension `id`, `data` being the rest of the header following its id.
+    ///
+    /// By default, the size is given by [`Self::is_mandatory_header_id_known`] whatever the data.
+    fn decode_mandatory_header(&self, id: u16, _data: &[u8]) -> MandatoryHeaderExt {
+        self.is_mandatory_header_id_known(id)
+    }
+
+    /// Check a header extension read by the decapsulator, the packet is dropped if it is not valid.
+    fn check_extension(&self, _extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        Ok(())
+    }
+
+    /// Return the range of the pdu delivered once the pdu has been received with these header extensions.
+    fn decode_pdu(&self, _extensions: &[Extension], pdu: &[u8]) -> Result<Range<usize>, ExtensionHandlerError> {
+        Ok(0..pdu.len())
+    }
+
+    /// Return the header extension written by the encapsulator for `extension`.
+    fn encode_extension(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        Ok(extension.clone())
+    }
+}
+
+impl<M: MandatoryHeaderExtensionManager> MandatoryHeaderExtensionManager for &M {
+    fn is_mandatory_header_id_known(&self, id: u16) -> MandatoryHeaderExt {
+        (*self).is_mandatory_header_id_known(id)
+    }
+
+    fn decode_mandatory_header(&self, id: u16, data: &[u8]) -> MandatoryHeaderExt {
+        (*self).decode_mandatory_header(id, data)
+    }
+
+    fn check_extension(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        (*self).check_extension(extension)
+    }
+
+    fn decode_pdu(&self, extensions: &[Extension], pdu: &[u8]) -> Result<Range<usize>, ExtensionHandlerError> {
+        (*self).decode_pdu(extensions, pdu)
+    }
+
+    fn encode_extension(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        (*self).encode_extension(extension)
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -271,4 +341,54 @@ pub(crate) fn optionnal_extension_data_size_from_hlen(h_len: u8) -> Result<usize
         5 => Ok(8),
         _ => Err(HlenError::UnknownHLen),
     }
-}
\ No newline at end of file
+}
+/// Coverage of the header extensions by the total length and the crc of a fragmented pdu
+///
+/// `[ETSI TS 102 606]` counts the header extensions in the total length of the first fragment,
+/// and computes the crc over the total length, the protocol type field, the label, the header extensions and the pdu. \
+/// The previous versions of this crate left the header extensions out of both, and computed the crc with the protocol type following them.
+/// [`ExtensionCoverage::Legacy`] keeps this behaviour for the peers still using it.
+///
+/// Both give the same total length and crc for the pdus sent without header extension.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ExtensionCoverage {
+    /// Header extensions counted in the total length and covered by the crc, as specified by `[ETSI TS 102 606]`
+    #[default]
+    Standard,
+    /// Header extensions left out of the total length and of the crc, as done by the previous versions of this crate
+    Legacy,
+}
+
+impl ExtensionCoverage {
+    /// Return the total length of a pdu of `pdu_len` bytes, sent with a label of `label_len` bytes and `extensions_len` bytes of header extensions
+    ///
+    /// `extensions_len` is the length of the bytes between the label and the pdu.
+    pub fn total_len(self, pdu_len: usize, label_len: usize, extensions_len: usize) -> usize {
+        match self {
+            Self::Standard => PROTOCOL_LEN + label_len + extensions_len + pdu_len,
+            Self::Legacy => PROTOCOL_LEN + label_len + pdu_len,
+        }
+    }
+
+    /// Start the computation of the crc of a fragmented pdu, see [`CrcCalculator::init_crc32`]
+    ///
+    /// `protocol_type_field` is the field following the total length, i.e. the id of the first header extension if any,
+    /// `protocol_type` is the protocol type of the pdu and `extensions` the bytes between the label and the pdu.
+    pub fn init_crc32<C: CrcCalculator>(
+        self,
+        crc_calculator: &C,
+        protocol_type_field: u16,
+        protocol_type: u16,
+        total_len: u16,
+        label: &[u8],
+        extensions: &[u8],
+    ) -> u32 {
+        match self {
+            Self::Standard => crc_calculator.update_crc32(
+                crc_calculator.init_crc32(protocol_type_field, total_len, label),
+                extensions,
+            ),
+            Self::Legacy => crc_calculator.init_crc32(protocol_type, total_len, label),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the PDU-Concat extension
+//!
+//! The non final mandatory extension PDU-Concat (0x0003) packs several small pdus of the same protocol type in one packet,
+//! to save the header of a packet per pdu. The extension has no data and is followed by the protocol type of the pdus,
+//! the pdu of the packet is the sequence of the pdus, each one preceded by its length:
+//! ```text
+//!    +------------+-----------------+------------+-----------------+-----+------------+-----------------+
+//!    |  Length 1  |      PDU 1      |  Length 2  |      PDU 2      | ... |  Length N  |      PDU N      |
+//!    +------------+-----------------+------------+-----------------+-----+------------+-----------------+
+//!    <- 2 bytes ->                  <- 2 bytes ->                        <- 2 bytes ->
+//! ```
+//!
+//! The `Encapsulator` sends a batch of pdus with [`encap_ext_concat`](crate::gse_encap::Encapsulator::encap_ext_concat),
+//! the pdus are not copied but written as the segments given by [`PduConcat`].
+//! The `Decapsulator` splits the packet received back into its pdus, given by
+//! [`DecapStatus::ConcatenatedPkt`](crate::gse_decap::DecapStatus::ConcatenatedPkt).
+//! A manager must know the extension for the packet to be received, [`PduConcatHandler`] handles it in an [`ExtensionRegistry`](super::ExtensionRegistry).
+//!
+//! ## Specification
+//! See `[RFC 5163]` : "Extension Formats for Unidirectional Lightweight Encapsulation (ULE) and the Generic Stream Encapsulation (GSE)" - § 3.2
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+    Extension, ExtensionData, ExtensionHandler, ExtensionHandlerError, MandatoryHeaderExt,
+};
+use crate::gse_standard::{PDU_CONCAT_LEN_LEN, PDU_CONCAT_PROTOCOL_ID};
+use std::ops::Range;
+
+/// Return the extension sent with a batch of pdus
+pub fn pdu_concat_extension() -> Extension {
+    Extension {
+        id: PDU_CONCAT_PROTOCOL_ID,
+        data: ExtensionData::MandatoryData(vec![]),
+    }
+}
+
+/// Return true if the extensions contain the PDU-Concat extension
+pub fn is_pdu_concat(extensions: &[Extension]) -> bool {
+    extensions
+        .iter()
+        .any(|extension| extension.id() == PDU_CONCAT_PROTOCOL_ID)
+}
+
+/// Structure PduConcat
+///
+/// Batch of pdus written as the pdu of a PDU-Concat packet.
+pub struct PduConcat<'a> {
+    pdus: &'a [&'a [u8]],
+    lengths: Vec<[u8; PDU_CONCAT_LEN_LEN]>,
+}
+
+impl<'a> PduConcat<'a> {
+    /// Create the batch of `pdus`, return `None` if there is no pdu or if a pdu is too long for its length field
+    pub fn new(pdus: &'a [&'a [u8]]) -> Option<Self> {
+        if pdus.is_empty() {
+            return None;
+        }
+        let lengths = pdus
+            .iter()
+            .map(|pdu| u16::try_from(pdu.len()).ok().map(u16::to_be_bytes))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { pdus, lengths })
+    }
+
+    /// Return the segments of the pdu of the packet, the length of each pdu followed by the pdu itself
+    pub fn segments(&self) -> Vec<&[u8]> {
+        self.lengths
+            .iter()
+            .zip(self.pdus)
+            .flat_map(|(length, pdu)| [&length[..], pdu])
+            .collect()
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    /// Get the length of the pdu of the packet
+    pub fn len(&self) -> usize {
+        self.pdus
+            .iter()
+            .map(|pdu| PDU_CONCAT_LEN_LEN + pdu.len())
+            .sum()
+    }
+}
+
+/// Split the pdu of a PDU-Concat packet, return the range of each pdu
+pub fn split(pdu: &[u8]) -> Result<Vec<Range<usize>>, ExtensionHandlerError> {
+    let mut pdus = vec![];
+    let mut offset = 0;
+    while offset < pdu.len() {
+        let Some(length) = pdu.get(offset..offset + PDU_CONCAT_LEN_LEN) else {
+            return Err(ExtensionHandlerError::InvalidPdu);
+        };
+        let start = offset + PDU_CONCAT_LEN_LEN;
+        let end = start + u16::from_be_bytes([length[0], length[1]]) as usize;
+        if end > pdu.len() {
+            return Err(ExtensionHandlerError::InvalidPdu);
+        }
+        pdus.push(start..end);
+        offset = end;
+    }
+    if pdus.is_empty() {
+        return Err(ExtensionHandlerError::InvalidPdu);
+    }
+    Ok(pdus)
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Handler of the PDU-Concat extension in an [`ExtensionRegistry`](super::ExtensionRegistry)
+///
+/// The extension is non final without data, the pdu is split by the decapsulator itself.
+pub struct PduConcatHandler;
+
+impl ExtensionHandler for PduConcatHandler {
+    fn decode_len(&self, _data: &[u8]) -> MandatoryHeaderExt {
+        MandatoryHeaderExt::NonFinal(0)
+    }
+
+    fn validate(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::MandatoryData(data) if data.is_empty() => Ok(()),
+            _ => Err(ExtensionHandlerError::InvalidData),
+        }
+    }
+
+    fn encode(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        self.validate(extension)?;
+        Ok(extension.clone())
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use super::{is_pdu_concat, pdu_concat_extension, split, PduConcat, PduConcatHandler};
+use crate::header_extension::{
+    Extension, ExtensionHandler, ExtensionHandlerError, MandatoryHeaderExt,
+};
+
+#[test]
+fn test_pdu_concat_001() {
+    let comment = "each pdu preceded by its length";
+    let pdus: [&[u8]; 3] = [b"abc", b"", b"de"];
+
+    let concat = PduConcat::new(&pdus).unwrap();
+
+    assert_eq!(
+        vec![&[0, 3][..], b"abc", &[0, 0], b"", &[0, 2], b"de"],
+        concat.segments(),
+        "{}",
+        comment
+    );
+    assert_eq!(11, concat.len(), "{}", comment);
+}
+
+#[test]
+fn test_pdu_concat_002() {
+    let comment = "empty batch and pdu too long for its length field";
+    let long_pdu = vec![0; 0x10000];
+    let pdus: [&[u8]; 2] = [b"abc", &long_pdu];
+
+    assert!(PduConcat::new(&[]).is_none(), "{}", comment);
+    assert!(PduConcat::new(&pdus).is_none(), "{}", comment);
+}
+
+#[test]
+fn test_split_001() {
+    let comment = "sequence of pdus split back";
+    let pdu = [0, 3, b'a', b'b', b'c', 0, 0, 0, 2, b'd', b'e'];
+
+    assert_eq!(Ok(vec![2..5, 7..7, 9..11]), split(&pdu), "{}", comment);
+}
+
+#[test]
+fn test_split_002() {
+    let comment = "empty sequence, truncated length, truncated pdu";
+
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        split(&[]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        split(&[0, 1, b'a', 0]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        split(&[0, 3, b'a', b'b']),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_pdu_concat_handler_001() {
+    let comment = "non final extension without data";
+    let handler = PduConcatHandler;
+    let extension = pdu_concat_extension();
+    let with_data = Extension::new(0x0003, &[1]).unwrap();
+
+    assert_eq!(
+        MandatoryHeaderExt::NonFinal(0),
+        handler.decode_len(&[0x08, 0x00]),
+        "{}",
+        comment
+    );
+    assert_eq!(Ok(()), handler.validate(&extension), "{}", comment);
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidData),
+        handler.validate(&with_data),
+        "{}",
+        comment
+    );
+    assert!(is_pdu_concat(&[with_data, extension]), "{}", comment);
+    assert!(
+        !is_pdu_concat(&[Extension::new(0x0300, &[1, 2, 3, 4]).unwrap()]),
+        "{}",
+        comment
+    );
+}
@@ -0,0 +1,176 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the registry of the header extension handlers
+//!
+//! The [`MandatoryHeaderExtensionManager`] only gives a fixed data size for each known mandatory extension.
+//! The [`ExtensionRegistry`] associates each extension id, mandatory or optional, with an [`ExtensionHandler`] which:
+//! * decodes the size of the data of a mandatory extension from the header itself, so that it can be variable,
+//! * validates the extension read by the decapsulator, the packet being dropped otherwise,
+//! * optionally transforms the pdu received with the extension, e.g. to strip the part of the pdu it describes,
+//! * encodes the extension again before it is written by the encapsulator.
+//!
+//! The registry implements [`MandatoryHeaderExtensionManager`], so the same registry drives the
+//! [`Decapsulator`](crate::gse_decap::Decapsulator) and [`Encapsulator::encap_ext_with_manager`](crate::gse_encap::Encapsulator::encap_ext_with_manager).
+//! The extensions without handler are processed as before: the optional ones are accepted as they are and the mandatory ones are unknown.
+
+#[cfg(test)]
+mod tests;
+
+use super::{ExtID, Extension, MandatoryHeaderExt, MandatoryHeaderExtensionManager};
+use crate::gse_standard::{INTERNAL_SIGNALING_PROTOCOL_ID, NCR_PROTOCOL_ID};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Error returned by an [`ExtensionHandler`] when it fails.
+///
+/// This enum is intended to be used as the `Err` variant in a `Result` type.
+pub enum ExtensionHandlerError {
+    /// Indicates that the data of the extension is not valid.
+    InvalidData,
+
+    /// Indicates that the pdu can not be decoded with the extension.
+    InvalidPdu,
+}
+
+impl ExtensionHandlerError {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::InvalidData => "Header extension data is not valid",
+            Self::InvalidPdu => "Pdu can not be decoded with the header extension",
+        }
+    }
+}
+
+/// Trait handling one header extension id in an [`ExtensionRegistry`].
+///
+/// Every function has a default implementation accepting the extension and the pdu as they are,
+/// but a handler of a mandatory extension must implement [`Self::decode_len`].
+pub trait ExtensionHandler {
+    /// Return the size of the data of the mandatory extension, `data` being the rest of the header following its id.
+    ///
+    /// `data` is empty when the size is requested without the header, through [`MandatoryHeaderExtensionManager::is_mandatory_header_id_known`].
+    /// The size of the data of an optional extension is given by its id, so this function is not called for them.
+    fn decode_len(&self, _data: &[u8]) -> MandatoryHeaderExt {
+        MandatoryHeaderExt::Unknown
+    }
+
+    /// Check the extension read by the decapsulator
+    fn validate(&self, _extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        Ok(())
+    }
+
+    /// Return the range of `pdu` delivered once received with the extension
+    fn decode_pdu(
+        &self,
+        _extension: &Extension,
+        pdu: &[u8],
+    ) -> Result<Range<usize>, ExtensionHandlerError> {
+        Ok(0..pdu.len())
+    }
+
+    /// Return the extension written by the encapsulator
+    fn encode(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        Ok(extension.clone())
+    }
+}
+
+/// A mandatory extension of fixed size can be registered with its size directly.
+impl ExtensionHandler for MandatoryHeaderExt {
+    fn decode_len(&self, _data: &[u8]) -> MandatoryHeaderExt {
+        *self
+    }
+}
+
+#[derive(Default)]
+/// Structure ExtensionRegistry
+///
+/// The registry contains the handler of each extension id known, see the module documentation.
+pub struct ExtensionRegistry {
+    handlers: BTreeMap<ExtID, Box<dyn ExtensionHandler>>,
+}
+
+impl ExtensionRegistry {
+    /// Create a registry without any handler
+    pub fn new() -> Self {
+        Self {
+            handlers: BTreeMap::new(),
+        }
+    }
+
+    /// Create a registry knowing the final extensions used in signalisation,
+    /// as [`SignalisationMandatoryExtensionHeaderManager`](super::SignalisationMandatoryExtensionHeaderManager)
+    pub fn signalisation() -> Self {
+        let mut registry = Self::new();
+        registry.register(NCR_PROTOCOL_ID, MandatoryHeaderExt::Final(0));
+        registry.register(INTERNAL_SIGNALING_PROTOCOL_ID, MandatoryHeaderExt::Final(0));
+        registry
+    }
+
+    /// Handle the extension `id` with `handler`, return the handler replaced if there was one
+    pub fn register<H: ExtensionHandler + 'static>(
+        &mut self,
+        id: ExtID,
+        handler: H,
+    ) -> Option<Box<dyn ExtensionHandler>> {
+        self.handlers.insert(id, Box::new(handler))
+    }
+
+    /// Remove the handler of the extension `id`, return it if there was one
+    pub fn unregister(&mut self, id: ExtID) -> Option<Box<dyn ExtensionHandler>> {
+        self.handlers.remove(&id)
+    }
+
+    /// Return the handler of the extension `id`
+    pub fn handler(&self, id: ExtID) -> Option<&dyn ExtensionHandler> {
+        self.handlers.get(&id).map(|handler| handler.as_ref())
+    }
+}
+
+impl MandatoryHeaderExtensionManager for ExtensionRegistry {
+    fn is_mandatory_header_id_known(&self, id: u16) -> MandatoryHeaderExt {
+        self.decode_mandatory_header(id, &[])
+    }
+
+    fn decode_mandatory_header(&self, id: u16, data: &[u8]) -> MandatoryHeaderExt {
+        self.handler(id)
+            .map_or(MandatoryHeaderExt::Unknown, |handler| {
+                handler.decode_len(data)
+            })
+    }
+
+    fn check_extension(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        match self.handler(extension.id()) {
+            Some(handler) => handler.validate(extension),
+            None => Ok(()),
+        }
+    }
+
+    /// The pdu is decoded by the handler of each extension in turn, each one receiving the range returned by the previous one
+    fn decode_pdu(
+        &self,
+        extensions: &[Extension],
+        pdu: &[u8],
+    ) -> Result<Range<usize>, ExtensionHandlerError> {
+        let mut range = 0..pdu.len();
+        for extension in extensions {
+            let Some(handler) = self.handler(extension.id()) else {
+                continue;
+            };
+            let decoded = handler.decode_pdu(extension, &pdu[range.clone()])?;
+            if decoded.start > decoded.end || decoded.end > range.len() {
+                return Err(ExtensionHandlerError::InvalidPdu);
+            }
+            range = range.start + decoded.start..range.start + decoded.end;
+        }
+        Ok(range)
+    }
+
+    fn encode_extension(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        match self.handler(extension.id()) {
+            Some(handler) => handler.encode(extension),
+            None => Ok(extension.clone()),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use super::{ExtensionHandler, ExtensionHandlerError, ExtensionRegistry};
+use crate::header_extension::{
+    Extension, ExtensionData, MandatoryHeaderExt, MandatoryHeaderExtensionManager,
+};
+use std::ops::Range;
+
+/// Non final mandatory extension whose data starts with the length of the rest of its data
+struct LengthPrefixed;
+
+impl ExtensionHandler for LengthPrefixed {
+    fn decode_len(&self, data: &[u8]) -> MandatoryHeaderExt {
+        match data.first() {
+            Some(&len) if len < u8::MAX => MandatoryHeaderExt::NonFinal(len + 1),
+            _ => MandatoryHeaderExt::Unknown,
+        }
+    }
+
+    fn validate(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::MandatoryData(data) if data.len() == data[0] as usize + 1 => Ok(()),
+            _ => Err(ExtensionHandlerError::InvalidData),
+        }
+    }
+}
+
+/// Optional extension giving the number of bytes to strip at the end of the pdu
+struct Trailer;
+
+impl ExtensionHandler for Trailer {
+    fn decode_pdu(
+        &self,
+        extension: &Extension,
+        pdu: &[u8],
+    ) -> Result<Range<usize>, ExtensionHandlerError> {
+        let ExtensionData::Data2(data) = extension.data() else {
+            return Err(ExtensionHandlerError::InvalidData);
+        };
+        let trailer_len = u16::from_be_bytes(*data) as usize;
+        match pdu.len().checked_sub(trailer_len) {
+            Some(len) => Ok(0..len),
+            None => Err(ExtensionHandlerError::InvalidPdu),
+        }
+    }
+
+    fn encode(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::Data2([0, 0]) => Err(ExtensionHandlerError::InvalidData),
+            _ => Ok(extension.clone()),
+        }
+    }
+}
+
+/// Optional extension giving the number of bytes to strip at the start of the pdu
+struct Header;
+
+impl ExtensionHandler for Header {
+    fn decode_pdu(
+        &self,
+        extension: &Extension,
+        pdu: &[u8],
+    ) -> Result<Range<usize>, ExtensionHandlerError> {
+        let ExtensionData::Data2(data) = extension.data() else {
+            return Err(ExtensionHandlerError::InvalidData);
+        };
+        Ok(u16::from_be_bytes(*data) as usize..pdu.len())
+    }
+}
+
+#[test]
+fn test_registry_001() {
+    let comment = "signalisation registry knows the final extensions of signalisation only";
+    let registry = ExtensionRegistry::signalisation();
+
+    assert_eq!(
+        MandatoryHeaderExt::Final(0),
+        registry.is_mandatory_header_id_known(0x0081),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        MandatoryHeaderExt::Final(0),
+        registry.decode_mandatory_header(0x0082, &[1, 2, 3]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        MandatoryHeaderExt::Unknown,
+        registry.is_mandatory_header_id_known(0x0001),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_registry_002() {
+    let comment = "mandatory extension of variable size decoded from the header and validated";
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0010, LengthPrefixed);
+
+    assert_eq!(
+        MandatoryHeaderExt::NonFinal(3),
+        registry.decode_mandatory_header(0x0010, &[2, 5, 6, 0x08, 0x00]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        MandatoryHeaderExt::Unknown,
+        registry.decode_mandatory_header(0x0010, &[]),
+        "{}",
+        comment
+    );
+    let valid = Extension::new(0x0010, &[2, 5, 6]).unwrap();
+    let invalid = Extension::new(0x0010, &[3, 5, 6]).unwrap();
+    assert_eq!(Ok(()), registry.check_extension(&valid), "{}", comment);
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidData),
+        registry.check_extension(&invalid),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_registry_003() {
+    let comment =
+        "pdu decoded by the handler of each extension in turn, extension without handler ignored";
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0200, Trailer);
+    registry.register(0x0201, Header);
+    let extensions = [
+        Extension::new(0x0200, &[0, 3]).unwrap(),
+        Extension::new(0x0300, &[1, 2, 3, 4]).unwrap(),
+        Extension::new(0x0201, &[0, 2]).unwrap(),
+    ];
+
+    assert_eq!(
+        Ok(2..7),
+        registry.decode_pdu(&extensions, b"abcdefghij"),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        registry.decode_pdu(&extensions[..1], b"ab"),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_registry_004() {
+    let comment = "range out of the pdu returned by a handler";
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0201, Header);
+    let extensions = [Extension::new(0x0201, &[0, 5]).unwrap()];
+
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        registry.decode_pdu(&extensions, b"abc"),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_registry_005() {
+    let comment = "extension encoded by its handler, extension without handler unchanged";
+    let mut registry = ExtensionRegistry::new();
+    registry.register(0x0200, Trailer);
+    let valid = Extension::new(0x0200, &[0, 1]).unwrap();
+    let invalid = Extension::new(0x0200, &[0, 0]).unwrap();
+    let unhandled = Extension::new(0x0300, &[1, 2, 3, 4]).unwrap();
+
+    assert_eq!(
+        Ok(valid.clone()),
+        registry.encode_extension(&valid),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidData),
+        registry.encode_extension(&invalid),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Ok(unhandled.clone()),
+        registry.encode_extension(&unhandled),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_registry_006() {
+    let comment = "handler replaced then unregistered";
+    let mut registry = ExtensionRegistry::new();
+
+    assert!(
+        registry
+            .register(0x0010, MandatoryHeaderExt::Final(2))
+            .is_none(),
+        "{}",
+        comment
+    );
+    assert!(
+        registry
+            .register(0x0010, MandatoryHeaderExt::NonFinal(4))
+            .is_some(),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        MandatoryHeaderExt::NonFinal(4),
+        registry.is_mandatory_header_id_known(0x0010),
+        "{}",
+        comment
+    );
+    assert!(registry.unregister(0x0010).is_some(), "{}", comment);
+    assert!(registry.handler(0x0010).is_none(), "{}", comment);
+    assert_eq!(
+        MandatoryHeaderExt::Unknown,
+        registry.is_mandatory_header_id_known(0x0010),
+        "{}",
+        comment
+    );
+}
@@ -176,4 +176,17 @@ fn test_new_extension_012() {
         Ok(ext) => panic!("Expected Err but got Ok {:?}",ext),
         Err(e) => assert_eq!(e, exp_err),
     } 
-}
\ No newline at end of file
+}
+/// new extension with the first id of the protocol type range (0x600)
+#[test]
+fn test_new_extension_013() {
+    let data = [0; 8];
+    let extension = Extension::new(0x600, &data);
+
+    let exp_err = NewExtensionError::IncorrectExtensionId;
+
+    match extension {
+        Ok(ext) => panic!("Expected Err but got Ok {:?}", ext),
+        Err(e) => assert_eq!(e, exp_err),
+    }
+}
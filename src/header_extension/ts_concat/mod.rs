@@ -0,0 +1,95 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the TS-Concat extension
+//!
+//! The final mandatory extension TS-Concat (0x0002) replaces the protocol type of a pdu which is a sequence of MPEG-2 TS packets,
+//! to carry the packets of legacy TS services. The extension has no data and the packets have no length field,
+//! each one has the fixed size of a TS packet and starts with its sync byte:
+//! ```text
+//!    +------+----------------------+------+----------------------+-----+------+----------------------+
+//!    | 0x47 |     TS packet 1      | 0x47 |     TS packet 2      | ... | 0x47 |     TS packet N      |
+//!    +------+----------------------+------+----------------------+-----+------+----------------------+
+//!    <---------- 188 bytes -------->
+//! ```
+//!
+//! The `Encapsulator` sends a sequence of TS packets with [`encap_ts_concat`](crate::gse_encap::Encapsulator::encap_ts_concat).
+//! The `Decapsulator` checks the packets received and gives them apart with
+//! [`DecapStatus::ConcatenatedPkt`](crate::gse_decap::DecapStatus::ConcatenatedPkt), the packet is dropped if one of them is not valid.
+//! A manager must know the extension for the packets to be received:
+//! [`TsConcatMandatoryExtensionHeaderManager`] knows it, and [`TsConcatHandler`] handles it in an [`ExtensionRegistry`](super::ExtensionRegistry).
+//!
+//! ## Specification
+//! See `[RFC 5163]` : "Extension Formats for Unidirectional Lightweight Encapsulation (ULE) and the Generic Stream Encapsulation (GSE)" - § 3.1
+
+#[cfg(test)]
+mod tests;
+
+use super::{
+    Extension, ExtensionData, ExtensionHandler, ExtensionHandlerError, MandatoryHeaderExt,
+    MandatoryHeaderExtensionManager,
+};
+use crate::gse_standard::{TS_CONCAT_PROTOCOL_ID, TS_PACKET_LEN, TS_SYNC_BYTE};
+use std::ops::Range;
+
+/// Return the extension sent with a sequence of TS packets
+pub fn ts_concat_extension() -> Extension {
+    Extension {
+        id: TS_CONCAT_PROTOCOL_ID,
+        data: ExtensionData::MandatoryData(vec![]),
+    }
+}
+
+/// Return true if `packet` has the size of a TS packet and starts with the sync byte
+pub fn is_ts_packet(packet: &[u8]) -> bool {
+    packet.len() == TS_PACKET_LEN && packet[0] == TS_SYNC_BYTE
+}
+
+/// Split the pdu of a TS-Concat packet, return the range of each TS packet
+pub fn split(pdu: &[u8]) -> Result<Vec<Range<usize>>, ExtensionHandlerError> {
+    if pdu.is_empty() || !pdu.chunks(TS_PACKET_LEN).all(is_ts_packet) {
+        return Err(ExtensionHandlerError::InvalidPdu);
+    }
+    Ok((0..pdu.len())
+        .step_by(TS_PACKET_LEN)
+        .map(|start| start..start + TS_PACKET_LEN)
+        .collect())
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Handler of the TS-Concat extension in an [`ExtensionRegistry`](super::ExtensionRegistry)
+///
+/// The extension is final without data, the TS packets are checked and split by the decapsulator itself.
+pub struct TsConcatHandler;
+
+impl ExtensionHandler for TsConcatHandler {
+    fn decode_len(&self, _data: &[u8]) -> MandatoryHeaderExt {
+        MandatoryHeaderExt::Final(0)
+    }
+
+    fn validate(&self, extension: &Extension) -> Result<(), ExtensionHandlerError> {
+        match extension.data() {
+            ExtensionData::MandatoryData(data) if data.is_empty() => Ok(()),
+            _ => Err(ExtensionHandlerError::InvalidData),
+        }
+    }
+
+    fn encode(&self, extension: &Extension) -> Result<Extension, ExtensionHandlerError> {
+        self.validate(extension)?;
+        Ok(extension.clone())
+    }
+}
+
+/// Implementation of the trait [`MandatoryHeaderExtensionManager`] for MPEG-2 TS services.
+///
+/// It knows the final extension 0x0002 TS-Concat, with no data.
+#[derive(Copy, Clone)]
+pub struct TsConcatMandatoryExtensionHeaderManager {}
+impl MandatoryHeaderExtensionManager for TsConcatMandatoryExtensionHeaderManager {
+    fn is_mandatory_header_id_known(&self, id: u16) -> MandatoryHeaderExt {
+        match id {
+            TS_CONCAT_PROTOCOL_ID => MandatoryHeaderExt::Final(0),
+            _ => MandatoryHeaderExt::Unknown,
+        }
+    }
+}
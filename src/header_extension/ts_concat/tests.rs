@@ -0,0 +1,118 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use super::{
+    is_ts_packet, split, ts_concat_extension, TsConcatHandler,
+    TsConcatMandatoryExtensionHeaderManager,
+};
+use crate::header_extension::{
+    Extension, ExtensionHandler, ExtensionHandlerError, MandatoryHeaderExt,
+    MandatoryHeaderExtensionManager,
+};
+
+fn ts_packets(count: usize) -> Vec<u8> {
+    let mut packets = vec![0; count * 188];
+    for (index, packet) in packets.chunks_mut(188).enumerate() {
+        packet[0] = 0x47;
+        packet[1] = index as u8;
+    }
+    packets
+}
+
+#[test]
+fn test_is_ts_packet_001() {
+    let comment = "ts packet of 188 bytes starting with the sync byte";
+    let packet = ts_packets(1);
+    let mut unsynced = packet.clone();
+    unsynced[0] = 0x48;
+
+    assert!(is_ts_packet(&packet), "{}", comment);
+    assert!(!is_ts_packet(&unsynced), "{}", comment);
+    assert!(!is_ts_packet(&packet[..187]), "{}", comment);
+    assert!(!is_ts_packet(&[]), "{}", comment);
+}
+
+#[test]
+fn test_split_001() {
+    let comment = "sequence of ts packets split back";
+
+    assert_eq!(
+        Ok(vec![0..188, 188..376, 376..564]),
+        split(&ts_packets(3)),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_split_002() {
+    let comment = "empty sequence, truncated ts packet, ts packet without sync byte";
+    let mut unsynced = ts_packets(2);
+    unsynced[188] = 0x00;
+
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        split(&[]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        split(&ts_packets(2)[..300]),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidPdu),
+        split(&unsynced),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_ts_concat_handler_001() {
+    let comment = "final extension without data";
+    let handler = TsConcatHandler;
+    let extension = ts_concat_extension();
+    let with_data = Extension::new(0x0002, &[1]).unwrap();
+
+    assert_eq!(
+        MandatoryHeaderExt::Final(0),
+        handler.decode_len(&[0x47]),
+        "{}",
+        comment
+    );
+    assert_eq!(Ok(()), handler.validate(&extension), "{}", comment);
+    assert_eq!(
+        Ok(extension.clone()),
+        handler.encode(&extension),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        Err(ExtensionHandlerError::InvalidData),
+        handler.encode(&with_data),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_ts_concat_manager_001() {
+    let comment = "manager knowing the ts concat extension only";
+    let manager = TsConcatMandatoryExtensionHeaderManager {};
+
+    assert_eq!(
+        MandatoryHeaderExt::Final(0),
+        manager.is_mandatory_header_id_known(0x0002),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        MandatoryHeaderExt::Unknown,
+        manager.is_mandatory_header_id_known(0x0003),
+        "{}",
+        comment
+    );
+}
@@ -20,7 +20,7 @@ pub enum Label {
     ReUse,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 /// Represent the type of Label
 pub enum LabelType {
     SixBytesLabel,
@@ -10,7 +10,13 @@
 //! To customize CRC calculation, see the [`crc`] module.
 //! 
 //! Encapsulation is handled by the `Encapsulator` struct. For more information, see the [`gse_encap`] module. \
-//! Decapsulation is handled by the `Decapsulator` struct. For more information, see the [`gse_decap`] module. 
+//! To fill whole base band frames, see the [`gse_encap::frame_packer`] module. \
+//! Decapsulation is handled by the `Decapsulator` struct. For more information, see the [`gse_decap`] module. \
+//! To keep only the pdus addressed to some labels, see the [`gse_decap::label_filter`] module. \
+//! To be notified of the events of the decapsulation, see the [`gse_decap::observer`] module. \
+//! To inspect packets in place without copying them, see the [`gse_view`] module. \
+//! To modify the header of packets already written, see the [`gse_header`] module. \
+//! To count the packets and the errors of the encapsulator and of the decapsulator, see the [`stats`] module.
 //! 
 //! # Example
 //!
@@ -129,8 +135,11 @@
 pub mod crc;
 pub mod gse_decap;
 pub mod gse_encap;
+pub mod gse_header;
 pub mod gse_standard;
+pub mod gse_view;
 pub mod header_extension;
 pub mod label;
 mod pkt_type;
+pub mod stats;
 pub mod utils;
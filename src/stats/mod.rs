@@ -0,0 +1,233 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+//! Module for the statistics of the encapsulation and of the decapsulation
+//!
+//! The counters are disabled by default, they are enabled in the [`Encapsulator`](crate::gse_encap::Encapsulator)
+//! or the [`Decapsulator`](crate::gse_decap::Decapsulator) with `enable_stats`.
+//! While they are disabled, nothing is counted and the only cost is the check of an `Option`.
+//!
+//! `stats` returns a snapshot of the counters, a copy that is not updated by the next packets,
+//! and `reset_stats` sets all the counters back to 0.
+
+#[cfg(test)]
+mod tests;
+
+use crate::gse_decap::{DecapError, DecapErrorKind};
+use crate::gse_encap::EncapError;
+use crate::label::{Label, LabelType};
+use crate::pkt_type::PktType;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Number of packets of each type
+pub struct PktCounters {
+    complete: u64,
+    first_frag: u64,
+    intermediate_frag: u64,
+    end_frag: u64,
+}
+
+impl PktCounters {
+    pub fn complete(&self) -> u64 {
+        self.complete
+    }
+    pub fn first_frag(&self) -> u64 {
+        self.first_frag
+    }
+    pub fn intermediate_frag(&self) -> u64 {
+        self.intermediate_frag
+    }
+    pub fn end_frag(&self) -> u64 {
+        self.end_frag
+    }
+
+    /// Return the number of packets of all the types
+    pub fn total(&self) -> u64 {
+        self.complete + self.first_frag + self.intermediate_frag + self.end_frag
+    }
+
+    fn count(&mut self, pkt_type: PktType) {
+        match pkt_type {
+            PktType::CompletePkt => self.complete += 1,
+            PktType::FirstFragPkt => self.first_frag += 1,
+            PktType::IntermediateFragPkt => self.intermediate_frag += 1,
+            PktType::EndFragPkt => self.end_frag += 1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+/// Statistics of an [`Encapsulator`](crate::gse_encap::Encapsulator)
+///
+/// The packets are counted by type when they are written, the pdus by label and by protocol type when their
+/// complete packet or first fragment is written, and the errors by variant when they are returned.
+/// The packets moved by the refragmentation are not counted.
+pub struct EncapStats {
+    pkts: PktCounters,
+    re_use_labels: u64,
+    labels: Vec<(Label, u64)>,
+    protocol_types: Vec<(u16, u64)>,
+    errors: Vec<(EncapError, u64)>,
+}
+
+impl EncapStats {
+    pub fn pkts(&self) -> PktCounters {
+        self.pkts
+    }
+
+    /// Return the number of labels replaced by the re use label
+    pub fn re_use_labels(&self) -> u64 {
+        self.re_use_labels
+    }
+
+    /// Return the number of pdus sent with this label, the label given in the metadata and not the re use label
+    pub fn label_count(&self, label: &Label) -> u64 {
+        counter(&self.labels, label)
+    }
+
+    /// Return the number of pdus sent with this protocol type
+    pub fn protocol_type_count(&self, protocol_type: u16) -> u64 {
+        counter(&self.protocol_types, &protocol_type)
+    }
+
+    /// Return the number of times this error has been returned
+    pub fn error_count(&self, error: &EncapError) -> u64 {
+        counter(&self.errors, error)
+    }
+
+    pub fn labels(&self) -> &[(Label, u64)] {
+        &self.labels
+    }
+    pub fn protocol_types(&self) -> &[(u16, u64)] {
+        &self.protocol_types
+    }
+    pub fn errors(&self) -> &[(EncapError, u64)] {
+        &self.errors
+    }
+
+    pub(crate) fn count_pkt(&mut self, pkt_type: PktType) {
+        self.pkts.count(pkt_type);
+    }
+
+    pub(crate) fn count_re_use_label(&mut self) {
+        self.re_use_labels += 1;
+    }
+
+    pub(crate) fn count_pdu(&mut self, label: Label, protocol_type: u16) {
+        increment(&mut self.labels, label);
+        increment(&mut self.protocol_types, protocol_type);
+    }
+
+    pub(crate) fn count_error(&mut self, error: &EncapError) {
+        increment(&mut self.errors, error.clone());
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+/// Statistics of a [`Decapsulator`](crate::gse_decap::Decapsulator)
+///
+/// The packets are counted by type when they are read without error, the pdus by label and by protocol type when
+/// they are completely received, and the errors by [`DecapErrorKind`] when they are returned, without the data they carry.
+/// The packets dropped by the label filter are counted both by type and as filtered packets.
+pub struct DecapStats {
+    pkts: PktCounters,
+    padding: u64,
+    padding_bytes: u64,
+    re_use_labels: u64,
+    filtered_pkts: u64,
+    labels: Vec<(Label, u64)>,
+    protocol_types: Vec<(u16, u64)>,
+    errors: Vec<(DecapErrorKind, u64)>,
+}
+
+impl DecapStats {
+    pub fn pkts(&self) -> PktCounters {
+        self.pkts
+    }
+
+    /// Return the number of paddings detected, the rest of a base band frame being a single padding
+    pub fn padding(&self) -> u64 {
+        self.padding
+    }
+
+    pub fn padding_bytes(&self) -> u64 {
+        self.padding_bytes
+    }
+
+    /// Return the number of packets received with the re use label
+    pub fn re_use_labels(&self) -> u64 {
+        self.re_use_labels
+    }
+
+    /// Return the number of packets dropped by the label filter
+    pub fn filtered_pkts(&self) -> u64 {
+        self.filtered_pkts
+    }
+
+    /// Return the number of pdus received with this label, the re use label being resolved
+    pub fn label_count(&self, label: &Label) -> u64 {
+        counter(&self.labels, label)
+    }
+
+    /// Return the number of pdus received with this protocol type
+    pub fn protocol_type_count(&self, protocol_type: u16) -> u64 {
+        counter(&self.protocol_types, &protocol_type)
+    }
+
+    /// Return the number of times an error of this kind has been returned, e.g. `ErrorCrc` or `ErrorMemory(StorageUnderflow)`
+    pub fn error_count(&self, kind: DecapErrorKind) -> u64 {
+        counter(&self.errors, &kind)
+    }
+
+    pub fn labels(&self) -> &[(Label, u64)] {
+        &self.labels
+    }
+    pub fn protocol_types(&self) -> &[(u16, u64)] {
+        &self.protocol_types
+    }
+    pub fn errors(&self) -> &[(DecapErrorKind, u64)] {
+        &self.errors
+    }
+
+    /// Count the packet, `label_type` is only meaningful for the packets starting a pdu
+    pub(crate) fn count_pkt(&mut self, pkt_type: PktType, label_type: LabelType) {
+        self.pkts.count(pkt_type);
+        if label_type == LabelType::ReUse
+            && matches!(pkt_type, PktType::CompletePkt | PktType::FirstFragPkt)
+        {
+            self.re_use_labels += 1;
+        }
+    }
+
+    pub(crate) fn count_padding(&mut self, len: usize) {
+        self.padding += 1;
+        self.padding_bytes += len as u64;
+    }
+
+    pub(crate) fn count_filtered(&mut self) {
+        self.filtered_pkts += 1;
+    }
+
+    pub(crate) fn count_pdu(&mut self, label: Label, protocol_type: u16) {
+        increment(&mut self.labels, label);
+        increment(&mut self.protocol_types, protocol_type);
+    }
+
+    pub(crate) fn count_error(&mut self, error: &DecapError) {
+        increment(&mut self.errors, error.kind());
+    }
+}
+
+fn counter<K: PartialEq>(counters: &[(K, u64)], key: &K) -> u64 {
+    counters
+        .iter()
+        .find(|(counted, _)| counted == key)
+        .map_or(0, |(_, count)| *count)
+}
+
+fn increment<K: PartialEq>(counters: &mut Vec<(K, u64)>, key: K) {
+    match counters.iter_mut().find(|(counted, _)| *counted == key) {
+        Some((_, count)) => *count += 1,
+        None => counters.push((key, 1)),
+    }
+}
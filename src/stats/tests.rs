@@ -0,0 +1,346 @@
+// Copyright 2023, Viveris Technologies
+// Distributed under the terms of the MIT License
+
+use crate::crc::DefaultCrc;
+use crate::gse_decap::{
+    DecapBorrowedStatus, DecapError, DecapErrorKind, DecapMemoryError, DecapMemoryErrorKind,
+    DecapStatus, Decapsulator, GseDecapMemory, LabelFilter, SimpleGseMemory,
+};
+use crate::gse_encap::{EncapError, EncapMetadata, EncapStatus, Encapsulator};
+use crate::header_extension::SimpleMandatoryExtensionHeaderManager;
+use crate::label::Label;
+use crate::stats::{DecapStats, EncapStats};
+
+const OWN_LABEL: Label = Label::SixBytesLabel(*b"012345");
+const PDU: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+const FRAME_LEN: usize = 100;
+// 2 complete packets of 13 and 7 bytes, a first fragment of 15 bytes and an end fragment of 25 bytes
+const DATA_LEN: usize = 60;
+
+fn create_decapsulator(
+    storage_count: usize,
+) -> Decapsulator<SimpleGseMemory, DefaultCrc, SimpleMandatoryExtensionHeaderManager> {
+    let mut memory = SimpleGseMemory::new(storage_count, PDU.len(), 0, 0);
+    for _ in 0..storage_count {
+        memory
+            .provision_storage(vec![0; PDU.len()].into_boxed_slice())
+            .unwrap();
+    }
+    Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+    )
+}
+
+/// Build a frame with 2 complete packets of the own label, the second re-using it, and a pdu fragmented with the broadcast label
+fn create_frame() -> [u8; FRAME_LEN] {
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut frame = [0; FRAME_LEN];
+    let metadata = EncapMetadata::new(0x1234, OWN_LABEL);
+    encapsulator
+        .encap(b"pdu", 0, metadata, &mut frame[..13])
+        .unwrap();
+    encapsulator
+        .encap(b"pdu", 0, metadata, &mut frame[13..20])
+        .unwrap();
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let Ok(EncapStatus::FragmentedPkt(15, context)) =
+        encapsulator.encap(PDU, 1, metadata, &mut frame[20..35])
+    else {
+        panic!()
+    };
+    let Ok(EncapStatus::CompletedPkt(25)) =
+        encapsulator.encap_frag(PDU, &context, &mut frame[35..])
+    else {
+        panic!()
+    };
+    frame
+}
+
+#[test]
+fn test_stats_001() {
+    let comment = "encapsulator counters";
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut buffer = [0; 100];
+    assert_eq!(None, encapsulator.stats(), "{}", comment);
+    encapsulator.enable_stats();
+
+    let metadata = EncapMetadata::new(0x1234, OWN_LABEL);
+    encapsulator
+        .encap(b"pdu", 0, metadata, &mut buffer)
+        .unwrap();
+    encapsulator
+        .encap(b"pdu", 0, metadata, &mut buffer)
+        .unwrap();
+
+    let metadata = EncapMetadata::new(0x1234, Label::Broadcast);
+    let Ok(EncapStatus::FragmentedPkt(_, context)) =
+        encapsulator.encap(PDU, 1, metadata, &mut buffer[..15])
+    else {
+        panic!("{}", comment)
+    };
+    let Ok(EncapStatus::FragmentedPkt(_, context)) =
+        encapsulator.encap_frag(PDU, &context, &mut buffer[..10])
+    else {
+        panic!("{}", comment)
+    };
+    assert_eq!(
+        Err(EncapError::ErrorSizeBuffer),
+        encapsulator.encap_frag(PDU, &context, &mut buffer[..2]),
+        "{}",
+        comment
+    );
+    encapsulator.encap_frag(PDU, &context, &mut buffer).unwrap();
+
+    let metadata = EncapMetadata::new(0x1234, Label::SixBytesLabel([0; 6]));
+    assert_eq!(
+        Err(EncapError::ErrorInvalidLabel),
+        encapsulator.encap(PDU, 2, metadata, &mut buffer),
+        "{}",
+        comment
+    );
+
+    let stats = encapsulator.stats().unwrap();
+    let pkts = stats.pkts();
+    assert_eq!(
+        (2, 1, 1, 1, 5),
+        (
+            pkts.complete(),
+            pkts.first_frag(),
+            pkts.intermediate_frag(),
+            pkts.end_frag(),
+            pkts.total()
+        ),
+        "{}",
+        comment
+    );
+    assert_eq!(1, stats.re_use_labels(), "{}", comment);
+    assert_eq!(
+        &[(OWN_LABEL, 2), (Label::Broadcast, 1)],
+        stats.labels(),
+        "{}",
+        comment
+    );
+    assert_eq!(3, stats.protocol_type_count(0x1234), "{}", comment);
+    assert_eq!(0, stats.protocol_type_count(0x0800), "{}", comment);
+    assert_eq!(
+        1,
+        stats.error_count(&EncapError::ErrorSizeBuffer),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        1,
+        stats.error_count(&EncapError::ErrorInvalidLabel),
+        "{}",
+        comment
+    );
+    assert_eq!(
+        0,
+        stats.error_count(&EncapError::ErrorPduLength),
+        "{}",
+        comment
+    );
+
+    // the snapshot is not updated by the next packets
+    encapsulator.encap(b"pdu", 3, metadata, &mut buffer).ok();
+    assert_eq!(2, stats.errors().len(), "{}", comment);
+
+    encapsulator.reset_stats();
+    assert_eq!(
+        Some(EncapStats::default()),
+        encapsulator.stats(),
+        "{}",
+        comment
+    );
+    encapsulator.disable_stats();
+    assert_eq!(None, encapsulator.stats(), "{}", comment);
+}
+
+#[test]
+fn test_stats_002() {
+    let comment = "decapsulator counters";
+    let mut frame = create_frame();
+    let mut decapsulator = create_decapsulator(3);
+    decapsulator.enable_stats();
+
+    let pdus: Vec<_> = decapsulator
+        .decap_frame(&frame)
+        .filter_map(|status| match status.unwrap() {
+            DecapStatus::CompletedPkt(pdu, _) => Some(pdu),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(3, pdus.len(), "{}", comment);
+
+    let stats = decapsulator.stats().unwrap();
+    let pkts = stats.pkts();
+    assert_eq!(
+        (2, 1, 0, 1),
+        (
+            pkts.complete(),
+            pkts.first_frag(),
+            pkts.intermediate_frag(),
+            pkts.end_frag()
+        ),
+        "{}",
+        comment
+    );
+    assert_eq!(1, stats.re_use_labels(), "{}", comment);
+    assert_eq!(
+        (1, (FRAME_LEN - DATA_LEN) as u64),
+        (stats.padding(), stats.padding_bytes()),
+        "{}",
+        comment
+    );
+    assert_eq!(2, stats.label_count(&OWN_LABEL), "{}", comment);
+    assert_eq!(1, stats.label_count(&Label::Broadcast), "{}", comment);
+    assert_eq!(3, stats.protocol_type_count(0x1234), "{}", comment);
+    assert_eq!(0, stats.filtered_pkts(), "{}", comment);
+    assert!(stats.errors().is_empty(), "{}", comment);
+
+    // crc failure of the end fragment
+    for pdu in pdus {
+        decapsulator.provision_storage(pdu).unwrap();
+    }
+    decapsulator.reset_stats();
+    frame[DATA_LEN - 1] ^= 0xFF;
+    let errors: Vec<_> = decapsulator
+        .decap_frame(&frame)
+        .filter_map(|status| status.err())
+        .collect();
+    assert_eq!(1, errors.len(), "{}", comment);
+    let stats = decapsulator.stats().unwrap();
+    assert_eq!(
+        1,
+        stats.error_count(DecapErrorKind::ErrorCrc),
+        "{}",
+        comment
+    );
+    assert_eq!(1, stats.pkts().first_frag(), "{}", comment);
+    assert_eq!(0, stats.pkts().end_frag(), "{}", comment);
+
+    // memory underflow
+    let mut decapsulator = create_decapsulator(0);
+    decapsulator.enable_stats();
+    assert!(decapsulator.decap(&frame).is_err(), "{}", comment);
+    let underflow = DecapErrorKind::ErrorMemory(DecapMemoryErrorKind::StorageUnderflow);
+    assert_eq!(
+        &[(underflow, 1)],
+        decapsulator.stats().unwrap().errors(),
+        "{}",
+        comment
+    );
+}
+
+#[test]
+fn test_stats_003() {
+    let comment = "decapsulator counters with the label filter and the borrowed pdus";
+    let frame = create_frame();
+    let mut decapsulator = create_decapsulator(1);
+    let mut label_filter = LabelFilter::new();
+    label_filter.add_label(OWN_LABEL, 0);
+    decapsulator.set_label_filter(Some(label_filter));
+    decapsulator.enable_stats();
+
+    let mut offset = 0;
+    while offset < FRAME_LEN {
+        let (status, pkt_len) = decapsulator.decap_borrowed(&frame[offset..]).unwrap();
+        assert!(
+            !matches!(status, DecapBorrowedStatus::ReassembledPkt(_, _)),
+            "{}",
+            comment
+        );
+        offset += pkt_len;
+    }
+
+    let stats = decapsulator.stats().unwrap();
+    let pkts = stats.pkts();
+    assert_eq!(
+        (2, 1, 0, 1),
+        (
+            pkts.complete(),
+            pkts.first_frag(),
+            pkts.intermediate_frag(),
+            pkts.end_frag()
+        ),
+        "{}",
+        comment
+    );
+    assert_eq!(2, stats.filtered_pkts(), "{}", comment);
+    assert_eq!(1, stats.re_use_labels(), "{}", comment);
+    assert_eq!(&[(OWN_LABEL, 2)], stats.labels(), "{}", comment);
+    assert_eq!(1, stats.padding(), "{}", comment);
+
+    decapsulator.disable_stats();
+    assert_eq!(None, decapsulator.stats(), "{}", comment);
+}
+
+#[test]
+fn test_stats_004() {
+    let comment = "errors counted by kind, without the buffers they carry";
+    let mut stats = DecapStats::default();
+
+    for len in 1..=3 {
+        let buffer = vec![0; len].into_boxed_slice();
+        stats.count_error(&DecapError::ErrorMemory(DecapMemoryError::BufferTooSmall(
+            buffer,
+        )));
+    }
+    stats.count_error(&DecapError::ErrorCrc);
+
+    let too_small = DecapErrorKind::ErrorMemory(DecapMemoryErrorKind::BufferTooSmall);
+    assert_eq!(
+        &[(too_small, 3), (DecapErrorKind::ErrorCrc, 1)],
+        stats.errors(),
+        "{}",
+        comment
+    );
+    assert_eq!(3, stats.error_count(too_small), "{}", comment);
+}
+
+#[test]
+fn test_stats_005() {
+    let comment = "same counters with the pdus copied or borrowed";
+    let mut frame = [0; FRAME_LEN + 10];
+    frame[..FRAME_LEN].copy_from_slice(&create_frame());
+    // complete packet with an invalid label, replacing the padding
+    frame[DATA_LEN..DATA_LEN + 10].copy_from_slice(&[0xC0, 0x08, 0x12, 0x34, 0, 0, 0, 0, 0, 0]);
+
+    let mut stats = vec![];
+    for borrowed in [false, true] {
+        let mut decapsulator = create_decapsulator(1);
+        let mut label_filter = LabelFilter::new();
+        label_filter.add_label(OWN_LABEL, 0);
+        decapsulator.set_label_filter(Some(label_filter));
+        decapsulator.enable_stats();
+
+        let mut offset = 0;
+        while offset < frame.len() {
+            let result = match borrowed {
+                false => decapsulator.decap(&frame[offset..]).map(|(status, len)| {
+                    // give the pdu copied back to the memory
+                    if let DecapStatus::CompletedPkt(pdu, _) = status {
+                        decapsulator.provision_storage(pdu).unwrap();
+                    }
+                    len
+                }),
+                true => decapsulator
+                    .decap_borrowed(&frame[offset..])
+                    .map(|(_, len)| len),
+            };
+            offset += result.unwrap_or_else(|(_, len)| len);
+        }
+        stats.push(decapsulator.stats().unwrap());
+    }
+
+    assert_eq!(stats[0], stats[1], "{}", comment);
+    assert_eq!(2, stats[1].filtered_pkts(), "{}", comment);
+    assert_eq!(
+        1,
+        stats[1].error_count(DecapErrorKind::ErrorInvalidLabel),
+        "{}",
+        comment
+    );
+}
@@ -4,6 +4,8 @@
 //! Module for Utils
 //!
 //! This module contains the functional tools for creating and parsing GSE packets.
+//!
+//! The parsing and the generation never panic, a malformed packet or a buffer too small is reported with a [`SerialisableError`].
 use crate::gse_decap::read_gse_header;
 use crate::gse_encap::generate_gse_header;
 use crate::gse_standard::{CRC_LEN, FIXED_HEADER_LEN, FRAG_ID_LEN, PROTOCOL_LEN, TOTAL_LENGTH_LEN};
@@ -13,16 +15,89 @@ use crate::pkt_type::PktType;
 #[cfg(test)]
 mod tests;
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Error of the parsing or the generation of a packet
+pub enum SerialisableError {
+    /// Indicates that the buffer ends before the end of the packet.
+    ErrorTruncated,
+
+    /// Indicates that the packet type read in the header is not the one of the structure.
+    ErrorWrongPktType,
+
+    /// Indicates that the buffer starts with padding instead of a packet.
+    ErrorPadding,
+
+    /// Indicates that the gse length is too small to contain the fields of the packet.
+    ErrorBadLength,
+
+    /// Indicates that the buffer is too small to contain the packet generated.
+    ErrorSizeBuffer,
+}
+
+impl SerialisableError {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::ErrorTruncated => "Buffer ends before the end of the packet",
+            Self::ErrorWrongPktType => "Wrong PktType",
+            Self::ErrorPadding => "Padding found instead of a packet",
+            Self::ErrorBadLength => "Gse length too small to contain the fields of the packet",
+            Self::ErrorSizeBuffer => "Buffer too small to contain the packet",
+        }
+    }
+}
+
 pub trait Serialisable<'a> {
-    // Serialise a packet in a buffer
-    fn generate(&self, buffer: &mut [u8]);
+    /// Serialise a packet in a buffer, return the length of the packet written
+    fn generate(&self, buffer: &mut [u8]) -> Result<usize, SerialisableError>;
 
-    // Deserialise a packet into a structure structure
-    fn parse(buffer: &'a [u8]) -> Result<Self, &'static str>
+    /// Deserialise a packet into a structure
+    fn parse(buffer: &'a [u8]) -> Result<Self, SerialisableError>
     where
         Self: Sized;
 }
 
+/// Read the fixed header of a packet and check that the whole packet is in the buffer
+///
+/// `fields_len` is the length of the fields following the fixed header, label excluded.
+/// Return the gse length and the label type.
+fn read_header(
+    buffer: &[u8],
+    exp_pkt_type: PktType,
+    fields_len: usize,
+) -> Result<(usize, LabelType), SerialisableError> {
+    let Some(header) = buffer.get(..FIXED_HEADER_LEN) else {
+        return Err(SerialisableError::ErrorTruncated);
+    };
+    let Some((gse_len, pkt_type, label_type)) =
+        read_gse_header(u16::from_be_bytes([header[0], header[1]]))
+    else {
+        return Err(SerialisableError::ErrorPadding);
+    };
+    if pkt_type != exp_pkt_type {
+        return Err(SerialisableError::ErrorWrongPktType);
+    }
+    // the label is only in the first packet of a pdu
+    let label_len = match pkt_type {
+        PktType::CompletePkt | PktType::FirstFragPkt => label_type.len(),
+        PktType::IntermediateFragPkt | PktType::EndFragPkt => 0,
+    };
+    if gse_len < fields_len + label_len {
+        return Err(SerialisableError::ErrorBadLength);
+    }
+    if buffer.len() < FIXED_HEADER_LEN + gse_len {
+        return Err(SerialisableError::ErrorTruncated);
+    }
+    Ok((gse_len, label_type))
+}
+
+/// Check that the packet generated fits in the buffer
+fn check_buffer(buffer: &[u8], pkt_len: usize) -> Result<usize, SerialisableError> {
+    if buffer.len() < pkt_len {
+        return Err(SerialisableError::ErrorSizeBuffer);
+    }
+    Ok(pkt_len)
+}
+
 /// Structure of Gse Complete Packet:
 #[derive(PartialEq, Eq, Debug)]
 pub struct GseCompletePacket<'a> {
@@ -44,7 +119,11 @@ impl<'a> GseCompletePacket<'a> {
 }
 
 impl<'a> Serialisable<'a> for GseCompletePacket<'a> {
-    fn generate(&self, buffer: &mut [u8]) {
+    fn generate(&self, buffer: &mut [u8]) -> Result<usize, SerialisableError> {
+        let pkt_len = check_buffer(
+            buffer,
+            FIXED_HEADER_LEN + PROTOCOL_LEN + self.label.len() + self.pdu.len(),
+        )?;
         let mut offset = 0;
 
         let fixed_header: u16 =
@@ -59,22 +138,17 @@ impl<'a> Serialisable<'a> for GseCompletePacket<'a> {
         offset += self.label.len();
 
         buffer[offset..offset + self.pdu.len()].copy_from_slice(self.pdu);
+
+        Ok(pkt_len)
     }
 
-    fn parse(buffer: &[u8]) -> Result<GseCompletePacket, &'static str> {
+    fn parse(buffer: &[u8]) -> Result<GseCompletePacket, SerialisableError> {
         let mut offset = 0;
 
-        let (gse_len, pkt_type, label_type) = read_gse_header(u16::from_be_bytes(
-            buffer[..FIXED_HEADER_LEN].try_into().unwrap(),
-        ))
-        .unwrap();
-        if pkt_type != PktType::CompletePkt {
-            return Err("Wrong PktType");
-        }
+        let (gse_len, label_type) = read_header(buffer, PktType::CompletePkt, PROTOCOL_LEN)?;
         offset += FIXED_HEADER_LEN;
 
-        let protocol_type =
-            u16::from_be_bytes(buffer[offset..offset + PROTOCOL_LEN].try_into().unwrap());
+        let protocol_type = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
         offset += PROTOCOL_LEN;
 
         let label = Label::new(&label_type, &buffer[offset..offset + label_type.len()]);
@@ -83,7 +157,7 @@ impl<'a> Serialisable<'a> for GseCompletePacket<'a> {
         let pdu = &buffer[offset..gse_len + FIXED_HEADER_LEN];
 
         Ok(GseCompletePacket::new(
-            gse_len.try_into().unwrap(),
+            gse_len as u16,
             protocol_type,
             label,
             pdu,
@@ -123,7 +197,16 @@ impl<'a> GseFirstFragPacket<'a> {
 }
 
 impl<'a> Serialisable<'a> for GseFirstFragPacket<'a> {
-    fn generate(&self, buffer: &mut [u8]) {
+    fn generate(&self, buffer: &mut [u8]) -> Result<usize, SerialisableError> {
+        let pkt_len = check_buffer(
+            buffer,
+            FIXED_HEADER_LEN
+                + FRAG_ID_LEN
+                + TOTAL_LENGTH_LEN
+                + PROTOCOL_LEN
+                + self.label.len()
+                + self.pdu.len(),
+        )?;
         let mut offset = 0;
 
         let fixed_header: u16 =
@@ -144,32 +227,27 @@ impl<'a> Serialisable<'a> for GseFirstFragPacket<'a> {
         offset += self.label.len();
 
         buffer[offset..offset + self.pdu.len()].copy_from_slice(self.pdu);
+
+        Ok(pkt_len)
     }
 
-    fn parse(buffer: &[u8]) -> Result<GseFirstFragPacket, &'static str> {
+    fn parse(buffer: &[u8]) -> Result<GseFirstFragPacket, SerialisableError> {
         let mut offset = 0;
 
-        let (gse_len, pkt_type, label_type) = read_gse_header(u16::from_be_bytes(
-            buffer[..FIXED_HEADER_LEN].try_into().unwrap(),
-        ))
-        .unwrap();
-        if pkt_type != PktType::FirstFragPkt {
-            return Err("Wrong PktType");
-        }
+        let (gse_len, label_type) = read_header(
+            buffer,
+            PktType::FirstFragPkt,
+            FRAG_ID_LEN + TOTAL_LENGTH_LEN + PROTOCOL_LEN,
+        )?;
         offset += FIXED_HEADER_LEN;
 
-        let frag_id = u8::from_be_bytes(buffer[offset..offset + FRAG_ID_LEN].try_into().unwrap());
+        let frag_id = buffer[offset];
         offset += FRAG_ID_LEN;
 
-        let total_length = u16::from_be_bytes(
-            buffer[offset..offset + TOTAL_LENGTH_LEN]
-                .try_into()
-                .unwrap(),
-        );
+        let total_length = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
         offset += TOTAL_LENGTH_LEN;
 
-        let protocol_type =
-            u16::from_be_bytes(buffer[offset..offset + PROTOCOL_LEN].try_into().unwrap());
+        let protocol_type = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
         offset += PROTOCOL_LEN;
 
         let label = Label::new(&label_type, &buffer[offset..offset + label_type.len()]);
@@ -178,7 +256,7 @@ impl<'a> Serialisable<'a> for GseFirstFragPacket<'a> {
         let pdu = &buffer[offset..gse_len + FIXED_HEADER_LEN];
 
         Ok(GseFirstFragPacket::new(
-            gse_len.try_into().unwrap(),
+            gse_len as u16,
             frag_id,
             total_length,
             protocol_type,
@@ -207,7 +285,8 @@ impl<'a> GseIntermediatePacket<'a> {
 }
 
 impl<'a> Serialisable<'a> for GseIntermediatePacket<'a> {
-    fn generate(&self, buffer: &mut [u8]){
+    fn generate(&self, buffer: &mut [u8]) -> Result<usize, SerialisableError> {
+        let pkt_len = check_buffer(buffer, FIXED_HEADER_LEN + FRAG_ID_LEN + self.pdu.len())?;
         let mut offset = 0;
 
         let fixed_header: u16 = generate_gse_header(
@@ -222,27 +301,24 @@ impl<'a> Serialisable<'a> for GseIntermediatePacket<'a> {
         offset += FRAG_ID_LEN;
 
         buffer[offset..offset + self.pdu.len()].copy_from_slice(self.pdu);
+
+        Ok(pkt_len)
     }
 
-    fn parse(buffer: &[u8]) -> Result<GseIntermediatePacket, &'static str> {
+    fn parse(buffer: &[u8]) -> Result<GseIntermediatePacket, SerialisableError> {
         let mut offset = 0;
 
-        let (gse_len, pkt_type, _label_type) = read_gse_header(u16::from_be_bytes(
-            buffer[..FIXED_HEADER_LEN].try_into().unwrap(),
-        ))
-        .unwrap();
-        if pkt_type != PktType::IntermediateFragPkt {
-            return Err("Wrong PktType");
-        }
+        let (gse_len, _label_type) =
+            read_header(buffer, PktType::IntermediateFragPkt, FRAG_ID_LEN)?;
         offset += FIXED_HEADER_LEN;
 
-        let frag_id = u8::from_be_bytes(buffer[offset..offset + FRAG_ID_LEN].try_into().unwrap());
+        let frag_id = buffer[offset];
         offset += FRAG_ID_LEN;
 
         let pdu = &buffer[offset..gse_len + FIXED_HEADER_LEN];
 
         Ok(GseIntermediatePacket::new(
-            gse_len.try_into().unwrap(),
+            gse_len as u16,
             frag_id,
             pdu,
         ))
@@ -270,7 +346,11 @@ impl<'a> GseEndFragPacket<'a> {
 }
 
 impl<'a> Serialisable<'a> for GseEndFragPacket<'a> {
-    fn generate(&self, buffer: &mut [u8]){
+    fn generate(&self, buffer: &mut [u8]) -> Result<usize, SerialisableError> {
+        let pkt_len = check_buffer(
+            buffer,
+            FIXED_HEADER_LEN + FRAG_ID_LEN + self.pdu.len() + CRC_LEN,
+        )?;
         let mut offset = 0;
 
         let fixed_header: u16 =
@@ -285,30 +365,32 @@ impl<'a> Serialisable<'a> for GseEndFragPacket<'a> {
         offset += self.pdu.len();
 
         buffer[offset..offset + CRC_LEN].copy_from_slice(&self.crc.to_be_bytes());
+
+        Ok(pkt_len)
     }
 
-    fn parse(buffer: &[u8]) -> Result<GseEndFragPacket, &'static str> {
+    fn parse(buffer: &[u8]) -> Result<GseEndFragPacket, SerialisableError> {
         let mut offset = 0;
 
-        let (gse_len, pkt_type, _label_type) = read_gse_header(u16::from_be_bytes(
-            buffer[..FIXED_HEADER_LEN].try_into().unwrap(),
-        ))
-        .unwrap();
-        if pkt_type != PktType::EndFragPkt {
-            return Err("Wrong PktType");
-        }
+        let (gse_len, _label_type) =
+            read_header(buffer, PktType::EndFragPkt, FRAG_ID_LEN + CRC_LEN)?;
         offset += FIXED_HEADER_LEN;
 
-        let frag_id = u8::from_be_bytes(buffer[offset..offset + FRAG_ID_LEN].try_into().unwrap());
+        let frag_id = buffer[offset];
         offset += FRAG_ID_LEN;
 
         let pdu = &buffer[offset..gse_len + FIXED_HEADER_LEN - CRC_LEN];
         offset = gse_len + FIXED_HEADER_LEN - CRC_LEN;
 
-        let crc = u32::from_be_bytes(buffer[offset..offset + CRC_LEN].try_into().unwrap());
+        let crc = u32::from_be_bytes([
+            buffer[offset],
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ]);
 
         Ok(GseEndFragPacket::new(
-            gse_len.try_into().unwrap(),
+            gse_len as u16,
             frag_id,
             pdu,
             crc,
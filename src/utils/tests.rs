@@ -5,7 +5,7 @@ use crate::{
     label::Label,
     utils::{
         GseCompletePacket, GseEndFragPacket, GseFirstFragPacket, GseIntermediatePacket,
-        Serialisable,
+        Serialisable, SerialisableError,
     },
 };
 
@@ -45,7 +45,7 @@ fn test_generate_complete_001() {
     exp_buffer[7..33].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
@@ -67,7 +67,7 @@ fn test_generate_complete_002() {
     exp_buffer[10..36].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
@@ -82,7 +82,7 @@ fn test_generate_complete_003() {
     exp_buffer[2..4].copy_from_slice(&4200_u16.to_be_bytes());
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
@@ -98,19 +98,21 @@ fn test_generate_complete_004() {
     exp_buffer[4..30].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
 
 #[test]
-#[should_panic]
-// Label Reuse, Cmplt pkt, Wrong buffer size: Should panic
+// Label Reuse, Cmplt pkt, Wrong buffer size: Error size buffer
 fn test_generate_complete_005() {
     let input = GseCompletePacket::new(2 + 26, 0, Label::ReUse, b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 1];
-    input.generate(&mut obs_buffer);
+    let exp_err = SerialisableError::ErrorSizeBuffer;
+
+    let result = input.generate(&mut obs_buffer);
+    assert_eq!(Err(exp_err), result);
 }
 
 #[test]
@@ -200,7 +202,7 @@ fn test_parse_complete_005() {
     input[2..4].copy_from_slice(&4200_u16.to_be_bytes());
     input[4..30].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseCompletePacket::parse(&input);
     match result_pkt {
@@ -217,7 +219,7 @@ fn test_parse_complete_006() {
     input[2..4].copy_from_slice(&4200_u16.to_be_bytes());
     input[4..30].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseCompletePacket::parse(&input);
     match result_pkt {
@@ -234,7 +236,7 @@ fn test_parse_complete_007() {
     input[2..4].copy_from_slice(&4200_u16.to_be_bytes());
     input[4..30].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseCompletePacket::parse(&input);
     match result_pkt {
@@ -288,7 +290,7 @@ fn test_generate_first_frag_001() {
     exp_buffer[10..36].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
@@ -314,7 +316,7 @@ fn test_generate_first_frag_002() {
     exp_buffer[13..39].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
@@ -331,7 +333,7 @@ fn test_generate_first_frag_003() {
     exp_buffer[5..7].copy_from_slice(&4200_u16.to_be_bytes());
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
@@ -356,14 +358,13 @@ fn test_generate_first_frag_004() {
     exp_buffer[7..33].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
 
 #[test]
-#[should_panic]
-// Label Reuse, first frag pkt
+// Label Reuse, first frag pkt, Wrong buffer size: Error size buffer
 fn test_generate_first_frag_005() {
     let input = GseFirstFragPacket::new(
         2 + 1 + 2 + 26,
@@ -375,7 +376,10 @@ fn test_generate_first_frag_005() {
     );
 
     let mut obs_buffer = [0; 1];
-    input.generate(&mut obs_buffer);
+    let exp_err = SerialisableError::ErrorSizeBuffer;
+
+    let result = input.generate(&mut obs_buffer);
+    assert_eq!(Err(exp_err), result);
 }
 
 #[test]
@@ -486,7 +490,7 @@ fn test_parse_first_frag_005() {
     input[5..7].copy_from_slice(&4200_u16.to_be_bytes());
     input[7..33].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseFirstFragPacket::parse(&input);
     match result_pkt {
@@ -505,7 +509,7 @@ fn test_parse_first_frag_006() {
     input[5..7].copy_from_slice(&4200_u16.to_be_bytes());
     input[7..33].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseFirstFragPacket::parse(&input);
     match result_pkt {
@@ -524,7 +528,7 @@ fn test_parse_first_frag_007() {
     input[5..7].copy_from_slice(&4200_u16.to_be_bytes());
     input[7..33].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseFirstFragPacket::parse(&input);
     match result_pkt {
@@ -561,19 +565,21 @@ fn test_generate_intermediate_001() {
     exp_buffer[3..29].copy_from_slice(b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
 
 #[test]
-#[should_panic]
-// Intermediate pkt, Wrong pkt size: should panic
+// Intermediate pkt, Wrong buffer size: Error size buffer
 fn test_generate_intermediate_002() {
     let input = GseIntermediatePacket::new(1 + 26, 50, b"abcdefghijklmnopqrstuvwxyz");
 
     let mut obs_buffer = [0; 1];
-    input.generate(&mut obs_buffer);
+    let exp_err = SerialisableError::ErrorSizeBuffer;
+
+    let result = input.generate(&mut obs_buffer);
+    assert_eq!(Err(exp_err), result);
 }
 
 #[test]
@@ -599,7 +605,7 @@ fn test_parse_intermediate_002() {
     input[..2].copy_from_slice(&[0xF0, 0x01]);
     input[2..3].copy_from_slice(&255_u8.to_be_bytes());
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseIntermediatePacket::parse(&input);
     match result_pkt {
@@ -615,7 +621,7 @@ fn test_parse_intermediate_003() {
     input[..2].copy_from_slice(&[0xB0, 0x01]);
     input[2..3].copy_from_slice(&255_u8.to_be_bytes());
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseIntermediatePacket::parse(&input);
     match result_pkt {
@@ -631,7 +637,7 @@ fn test_parse_intermediate_004() {
     input[..2].copy_from_slice(&[0x70, 0x01]);
     input[2..3].copy_from_slice(&255_u8.to_be_bytes());
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseIntermediatePacket::parse(&input);
     match result_pkt {
@@ -671,19 +677,21 @@ fn test_generate_end_frag_001() {
     exp_buffer[29..33].copy_from_slice(&0_u32.to_be_bytes());
 
     let mut obs_buffer = [0; 100];
-    input.generate(&mut obs_buffer);
+    input.generate(&mut obs_buffer).unwrap();
 
     assert_eq!(exp_buffer, obs_buffer);
 }
 
 #[test]
-#[should_panic]
-// End Frag pkt, Too small buffer: Should panic
+// End Frag pkt, Too small buffer: Error size buffer
 fn test_generate_end_frag_002() {
     let input = GseEndFragPacket::new(1 + 4 + 26, 50, b"abcdefghijklmnopqrstuvwxyz", 0);
 
     let mut obs_buffer = [0; 1];
-    input.generate(&mut obs_buffer);
+    let exp_err = SerialisableError::ErrorSizeBuffer;
+
+    let result = input.generate(&mut obs_buffer);
+    assert_eq!(Err(exp_err), result);
 }
 
 #[test]
@@ -711,7 +719,7 @@ fn test_parse_end_frag_002() {
     input[2..3].copy_from_slice(&255_u8.to_be_bytes());
     input[3..7].copy_from_slice(&570_u32.to_be_bytes());
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseEndFragPacket::parse(&input);
     match result_pkt {
@@ -728,7 +736,7 @@ fn test_parse_end_frag_003() {
     input[2..3].copy_from_slice(&255_u8.to_be_bytes());
     input[3..7].copy_from_slice(&570_u32.to_be_bytes());
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseEndFragPacket::parse(&input);
     match result_pkt {
@@ -745,7 +753,7 @@ fn test_parse_end_frag_004() {
     input[2..3].copy_from_slice(&255_u8.to_be_bytes());
     input[3..7].copy_from_slice(&570_u32.to_be_bytes());
 
-    let exp_err = "Wrong PktType";
+    let exp_err = SerialisableError::ErrorWrongPktType;
 
     let result_pkt = GseEndFragPacket::parse(&input);
     match result_pkt {
@@ -753,3 +761,106 @@ fn test_parse_end_frag_004() {
         Err(obs_err) => assert_eq!(exp_err, obs_err),
     }
 }
+
+#[test]
+// Cmplt pkt longer than the buffer: Truncated
+fn test_parse_complete_008() {
+    let mut input = [0; 20];
+    input[..2].copy_from_slice(&[0xC0, 0x22]);
+    input[2..4].copy_from_slice(&4200_u16.to_be_bytes());
+    input[4..10].copy_from_slice(b"abcdef");
+
+    let exp_err = SerialisableError::ErrorTruncated;
+
+    let result_pkt = GseCompletePacket::parse(&input);
+    assert_eq!(Err(exp_err), result_pkt);
+    assert_eq!(Err(exp_err), GseCompletePacket::parse(&input[..1]));
+}
+
+#[test]
+// 6B Label, gse len too small for the label: Bad length
+fn test_parse_complete_009() {
+    let mut input = [0; 100];
+    input[..2].copy_from_slice(&[0xC0, 0x04]);
+    input[2..4].copy_from_slice(&4200_u16.to_be_bytes());
+
+    let exp_err = SerialisableError::ErrorBadLength;
+
+    let result_pkt = GseCompletePacket::parse(&input);
+    assert_eq!(Err(exp_err), result_pkt);
+}
+
+#[test]
+// Padding instead of a packet
+fn test_parse_first_frag_008() {
+    let input = [0; 100];
+
+    let exp_err = SerialisableError::ErrorPadding;
+
+    assert_eq!(Err(exp_err), GseFirstFragPacket::parse(&input));
+    assert_eq!(Err(exp_err), GseIntermediatePacket::parse(&input));
+}
+
+#[test]
+// End frag pkt, gse len too small for the crc: Bad length
+fn test_parse_end_frag_005() {
+    let mut input = [0; 100];
+    input[..2].copy_from_slice(&[0x70, 0x04]);
+    input[2..3].copy_from_slice(&255_u8.to_be_bytes());
+
+    let exp_err = SerialisableError::ErrorBadLength;
+
+    let result_pkt = GseEndFragPacket::parse(&input);
+    assert_eq!(Err(exp_err), result_pkt);
+}
+
+#[test]
+// Buffer too small for the packet generated
+fn test_generate_error_001() {
+    let pdu = b"abcdefghijklmnopqrstuvwxyz";
+    let exp_err = Err(SerialisableError::ErrorSizeBuffer);
+
+    let mut buffer = [0; 2 + 2 + 6 + 26 - 1];
+    let complete = GseCompletePacket::new(34, 4200, Label::SixBytesLabel(*b"abcdef"), pdu);
+    assert_eq!(exp_err, complete.generate(&mut buffer));
+    assert_eq!(Ok(buffer.len() + 1), complete.generate(&mut [0; 100]));
+
+    let mut buffer = [0; 2 + 5 + 3 + 26 - 1];
+    let first = GseFirstFragPacket::new(34, 1, 100, 4200, Label::ThreeBytesLabel(*b"abc"), pdu);
+    assert_eq!(exp_err, first.generate(&mut buffer));
+
+    let mut buffer = [0; 2 + 1 + 26 - 1];
+    let intermediate = GseIntermediatePacket::new(27, 1, pdu);
+    assert_eq!(exp_err, intermediate.generate(&mut buffer));
+
+    let mut buffer = [0; 2 + 1 + 26 + 4 - 1];
+    let end = GseEndFragPacket::new(31, 1, pdu, 88);
+    assert_eq!(exp_err, end.generate(&mut buffer));
+    assert_eq!([0; 2 + 1 + 26 + 4 - 1], buffer, "nothing written");
+}
+
+#[test]
+// Random buffers: the parsing never panics
+fn test_parse_no_panic_001() {
+    let mut seed: u32 = 0x1234_5678;
+    let mut random = || {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (seed >> 24) as u8
+    };
+
+    for len in 0..64 {
+        for _ in 0..200 {
+            let mut input = vec![0; len];
+            input.iter_mut().for_each(|octet| *octet = random());
+            // small gse lengths are more likely to fit in the buffer
+            if len > 1 {
+                input[0] &= 0xF0;
+            }
+
+            let _ = GseCompletePacket::parse(&input);
+            let _ = GseFirstFragPacket::parse(&input);
+            let _ = GseIntermediatePacket::parse(&input);
+            let _ = GseEndFragPacket::parse(&input);
+        }
+    }
+}
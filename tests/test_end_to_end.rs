@@ -1,8 +1,9 @@
-use dvb_gse_rust::crc::{CrcCalculator, DefaultCrc};
+use dvb_gse_rust::crc::{CrcCalculator, DefaultCrc, FastCrc};
 use dvb_gse_rust::gse_decap::{
     DecapError, DecapMetadata, DecapStatus, Decapsulator, GseDecapMemory, SimpleGseMemory,
+    StreamGseMemory,
 };
-use dvb_gse_rust::gse_encap::{ContextFrag, EncapMetadata, EncapStatus, Encapsulator};
+use dvb_gse_rust::gse_encap::{ContextFrag, EncapMetadata, EncapStatus, Encapsulator, FramePacker};
 use dvb_gse_rust::gse_standard::{
     FIXED_HEADER_LEN, LABEL_3_B_LEN, LABEL_6_B_LEN, LABEL_REUSE_LEN, PROTOCOL_LEN,
 };
@@ -1824,3 +1825,145 @@ fn test_encap_decap_complete_ext_012() {
     }
 }
 
+
+#[test]
+// Test end-to-end packing of several pdus in base band frames, then decapsulation of each frame.
+fn test_pack_decap_frames_001() {
+    const DFL: usize = 100;
+    const MAX_PDU_LEN: usize = 250;
+
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+    let mut packer = FramePacker::new();
+    let mut decapsulator = create_decapsulator(4, MAX_PDU_LEN);
+
+    let labels = [
+        Label::SixBytesLabel(*b"012345"),
+        Label::SixBytesLabel(*b"012345"),
+        Label::ThreeBytesLabel(*b"abc"),
+        Label::Broadcast,
+    ];
+    let mut exp_pdus = VecDeque::new();
+    for i in 0..20 {
+        let pdu: Vec<u8> = (0..(i * 37) % MAX_PDU_LEN).map(|b| b as u8).collect();
+        let metadata = EncapMetadata::new(0x0800 + i as u16, labels[i % labels.len()]);
+        packer
+            .push(pdu.clone().into_boxed_slice(), metadata)
+            .unwrap();
+        exp_pdus.push_back((pdu, metadata));
+    }
+
+    let mut frame_count = 0;
+    while !packer.is_empty() {
+        let mut frame = [0xAA; DFL];
+        let frame_status = packer.pack(&mut encapsulator, &mut frame, DFL).unwrap();
+        frame_count += 1;
+
+        let statuses: Vec<_> = decapsulator.decap_frame(&frame[..DFL]).collect();
+        for status in statuses {
+            match status.unwrap() {
+                DecapStatus::CompletedPkt(pdu, metadata) => {
+                    let (exp_pdu, exp_metadata) = exp_pdus.pop_front().unwrap();
+                    assert_eq!(exp_pdu, pdu[..metadata.pdu_len()].to_vec());
+                    assert_eq!(exp_metadata.label, metadata.label());
+                    assert_eq!(exp_metadata.protocol_type, metadata.protocol_type());
+                    decapsulator.provision_storage(pdu).unwrap();
+                }
+                DecapStatus::FragmentedPkt(_) => (),
+                status => panic!("unexpected {:?} in frame {}", status, frame_count),
+            }
+        }
+        assert!(frame[frame_status.data_len()..].iter().all(|byte| *byte == 0));
+    }
+
+    assert!(exp_pdus.is_empty());
+}
+
+#[test]
+// Test end-to-end encap/decap of two interleaved fragmented pdus whose frag ids collide in a SimpleGseMemory.
+fn test_encap_decap_interleaved_frag_stream_memory_001() {
+    const PDU_LEN: usize = 26;
+    const MAX_FRAG_ID: usize = 8;
+    let pdus: [&[u8; PDU_LEN]; 2] = [b"abcdefghijklmnopqrstuvwxyz", b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"];
+    let frag_ids = [1, 1 + MAX_FRAG_ID as u8];
+    let metadata = EncapMetadata::new(0xFFFF, Label::Broadcast);
+
+    let mut memory = StreamGseMemory::new(MAX_FRAG_ID, PDU_LEN, 0, 0);
+    for _ in 0..2 {
+        memory
+            .provision_storage(vec![0; PDU_LEN].into_boxed_slice())
+            .unwrap();
+    }
+    let mut decapsulator = Decapsulator::new(
+        memory,
+        DefaultCrc {},
+        SimpleMandatoryExtensionHeaderManager {},
+    );
+    let mut encapsulator = Encapsulator::new(DefaultCrc {});
+
+    // first fragments of both pdus
+    let mut contexts = vec![];
+    for i in 0..2 {
+        let mut buffer = [0; 20];
+        let status = encapsulator.encap(pdus[i], frag_ids[i], metadata, &mut buffer);
+        let Ok(EncapStatus::FragmentedPkt(_, context)) = status else {
+            panic!("expected a fragmented packet, got {:?}", status);
+        };
+        contexts.push(context);
+        let (status, _) = decapsulator.decap(&buffer).unwrap();
+        assert!(matches!(status, DecapStatus::FragmentedPkt(_)));
+    }
+
+    // end fragments of both pdus
+    for i in 0..2 {
+        let mut buffer = [0; 100];
+        encapsulator
+            .encap_frag(pdus[i], &contexts[i], &mut buffer)
+            .unwrap();
+        let (status, _) = decapsulator.decap(&buffer).unwrap();
+        let exp_status = DecapStatus::CompletedPkt(
+            Box::new(*pdus[i]),
+            DecapMetadata::new(PDU_LEN, 0xFFFF, Label::Broadcast, vec![]),
+        );
+        assert_eq!(exp_status, status);
+    }
+}
+
+#[test]
+// Test end-to-end encap with the fast crc and decap with the default crc of a pdu in several fragments
+fn test_encap_decap_fast_crc_001() {
+    let comment = "Fast crc, send a pdu with 5 fragments";
+
+    let pdu: Vec<u8> = (0..2000u32).map(|i| (i * 13 + 5) as u8).collect();
+    let metadata = EncapMetadata::new(0x0800, Label::SixBytesLabel(*b"012345"));
+
+    let mut encapsulator = Encapsulator::new(FastCrc::new());
+    let mut decapsulator = create_decapsulator(1, pdu.len());
+
+    let mut buffer = [0; 500];
+    let mut status = encapsulator.encap(&pdu, 7, metadata, &mut buffer).unwrap();
+    let mut pkt_count = 1;
+    loop {
+        let obs_status = decapsulator.decap(&buffer).map(|(status, _)| status);
+        match status {
+            EncapStatus::FragmentedPkt(_, context) => {
+                assert!(
+                    matches!(obs_status, Ok(DecapStatus::FragmentedPkt(_))),
+                    "{}: {:?}",
+                    comment,
+                    obs_status
+                );
+                status = encapsulator.encap_frag(&pdu, &context, &mut buffer).unwrap();
+                pkt_count += 1;
+            }
+            EncapStatus::CompletedPkt(_) => {
+                let exp_status = Ok(DecapStatus::CompletedPkt(
+                    pdu.clone().into_boxed_slice(),
+                    DecapMetadata::new(pdu.len(), metadata.protocol_type, metadata.label, vec![]),
+                ));
+                assert_eq!(exp_status, obs_status, "{}", comment);
+                break;
+            }
+        }
+    }
+    assert_eq!(5, pkt_count, "{}", comment);
+}